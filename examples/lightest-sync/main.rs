@@ -1,4 +1,5 @@
 mod relayer;
+mod store;
 
 #[macro_use]
 extern crate serde_derive;
@@ -9,6 +10,7 @@ extern crate clap;
 extern crate celo_light_client;
 use celo_light_client::*;
 use relayer::*;
+use store::*;
 
 use clap::{App, Arg};
 use num::cast::ToPrimitive;
@@ -16,8 +18,6 @@ use num::cast::ToPrimitive;
 extern crate log;
 use log::{info, error};
 
-use std::time::{SystemTime, UNIX_EPOCH};
-
 #[tokio::main]
 async fn main(){
     env_logger::init();
@@ -42,6 +42,14 @@ async fn main(){
                 .default_value("17280")
                 .help("The epoch-size of Celo blockchain")
         )
+        .arg(
+                Arg::with_name("trusting-period")
+                .short("t")
+                .long("trusting-period")
+                .takes_value(true)
+                .default_value("0")
+                .help("How long (in seconds) the latest synced header can be trusted, 0 disables the expiration (required to sync from genesis)")
+        )
         .arg(
                 Arg::with_name("db")
                 .short("d")
@@ -50,13 +58,35 @@ async fn main(){
                 .default_value("./local.db")
                 .help("The path to local database")
         )
+        .arg(
+                Arg::with_name("reset")
+                .short("r")
+                .long("reset")
+                .takes_value(false)
+                .help("Removes the local database")
+        )
         .arg(
                 Arg::with_name("addr")
                 .short("a")
                 .long("addr")
                 .takes_value(true)
                 .default_value("http://127.0.0.1:8545")
-                .help("Removes the local database")
+                .help("The address of the Celo node RPC endpoint")
+        )
+        .arg(
+                Arg::with_name("concurrency")
+                .short("c")
+                .long("concurrency")
+                .takes_value(true)
+                .default_value("8")
+                .help("How many epoch headers are fetched concurrently")
+        )
+        .arg(
+                Arg::with_name("retries")
+                .long("retries")
+                .takes_value(true)
+                .default_value("5")
+                .help("How many times a failed request is retried (with the exponential backoff)")
         )
         .get_matches();
 
@@ -65,29 +95,61 @@ async fn main(){
         _ => true,
     };
 
-    let first_epoch = 0;
     let epoch_size = value_t!(matches.value_of("epoch-size"), u64).unwrap();
+    let trusting_period = value_t!(matches.value_of("trusting-period"), u64).unwrap();
     let addr = matches.value_of("addr").unwrap();
+    let db_path = matches.value_of("db").unwrap();
+    let fetch_config = FetchConfig {
+        concurrency: value_t!(matches.value_of("concurrency"), usize).unwrap(),
+        max_retries: value_t!(matches.value_of("retries"), u32).unwrap(),
+        ..FetchConfig::default()
+    };
 
     // setup relayer
     info!("Setting up relayer");
     let relayer: Relayer = Relayer::new(addr.to_string());
 
+    // setup storage
+    info!("Setting up storage: {}", db_path);
+    let store = Store::open(db_path).unwrap();
+    if matches.occurrences_of("reset") > 0 {
+        info!("Removing the local database");
+        store.reset().unwrap();
+    }
+    store.ensure_epoch_size(epoch_size).unwrap();
+
+    // resume from the last synced epoch, if any
+    let (first_epoch, snapshot) = match store.last_epoch().unwrap() {
+        Some((epoch, snapshot)) => {
+            if store.validators(epoch).unwrap().as_ref() != Some(&snapshot.validators) {
+                panic!("Local database is corrupted, the validator set of epoch {} doesn't match the snapshot, use --reset to start over", epoch);
+            }
+
+            info!(
+                "Resuming from epoch {} (header num: {}, hash: {}, validators: {})",
+                epoch, snapshot.number, snapshot.hash, snapshot.validators.len()
+            );
+            (epoch + 1, snapshot)
+        },
+        None => (0, Snapshot::new()),
+    };
+
     // setup state container
-    info!("Setting up storage");
     let state_config = Config {
        epoch_size,
        allowed_clock_skew: 5,
+       trusting_period,
 
        verify_epoch_headers: validate_all_headers,
        verify_non_epoch_headers: validate_all_headers,
        verify_header_timestamp: true,
+       trust_threshold: None,
     };
-    let snapshot = Snapshot::new();
-    let mut state = State::new(snapshot, &state_config);
+    let clock = SystemClock;
+    let mut state = State::new(snapshot, &state_config, &clock);
 
     info!("Fetching latest block header from: {}", addr);
-    let current_block_header: Header = relayer.get_block_header_by_number("latest").await.unwrap();
+    let current_block_header: Header = relayer.get_block_header_with_retry("latest", &fetch_config).await.unwrap();
     let current_epoch_number: u64 = get_epoch_number(current_block_header.number.to_u64().unwrap(), epoch_size);
 
     info!(
@@ -95,26 +157,66 @@ async fn main(){
         first_epoch, current_epoch_number, current_block_header.number, epoch_size
     );
 
-    // build up state from the genesis block to the latest
+    // build up state from the genesis block to the latest, the headers are prefetched
+    // concurrently but inserted one by one in order
+    let epoch_block_nums = (first_epoch..current_epoch_number)
+        .map(|epoch| get_epoch_last_block_number(epoch, epoch_size))
+        .collect();
+    let mut headers = relayer.fetch_block_headers(epoch_block_nums, fetch_config);
+
     for epoch in first_epoch..current_epoch_number {
-        let current_timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
-        let epoch_block_num = get_epoch_last_block_number(epoch, epoch_size);
-        let epoch_block_number_hex = format!("0x{:x}", epoch_block_num);
-        let header = relayer.get_block_header_by_number(&epoch_block_number_hex).await;
-
-        if header.is_ok() {
-            match state.insert_header(&header.unwrap(), current_timestamp) {
-                Ok(_) => info!("[{}/{}] Inserted epoch header: {}", epoch + 1, current_epoch_number, epoch_block_number_hex),
-                Err(e) => error!("Failed to insert epoch header {}: {}", epoch_block_number_hex, e)
+        let (epoch_block_num, header) = headers.next().await.expect("header of every epoch is fetched");
+        let header = match header {
+            Ok(header) => header,
+            Err(e) => {
+                error!("Failed to fetch epoch {} header: {}", epoch, e);
+                report_sync_stopped(&state, epoch);
+                std::process::exit(1);
+            }
+        };
+
+        match state.insert_header(&header) {
+            Ok(_) => {
+                info!(
+                    "[{}/{}] Inserted epoch header: 0x{:x} (hash: {}, validators: {})",
+                    epoch + 1,
+                    current_epoch_number,
+                    epoch_block_num,
+                    state.snapshot().hash,
+                    state.snapshot().validators.len()
+                );
+
+                // persist the progress, so that the sync can be resumed from this epoch
+                if let Err(e) = store.put_epoch(epoch, state.snapshot()) {
+                    error!("Failed to persist epoch {}: {}", epoch, e);
+                }
+            },
+            Err(e) => {
+                error!(
+                    "Failed to verify epoch {} header 0x{:x} (hash: {}): {}",
+                    epoch,
+                    epoch_block_num,
+                    header.hash().map(|hash| hash.to_string()).unwrap_or_else(|_| "unknown".to_string()),
+                    e
+                );
+                report_sync_stopped(&state, epoch);
+                std::process::exit(1);
             }
-        } else {
-            error!("Failed to fetch block header num: {}", epoch_block_number_hex);
         }
     }
 
-    let current_timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
-    match state.verify_header(&current_block_header, current_timestamp) {
+    match state.verify_header(&current_block_header) {
         Ok(_) => info!("Succesfully validated latest header against local state: {}", current_block_header.number),
         Err(e) => error!("Failed to validate latest header against local state: {}", e)
     }
 }
+
+fn report_sync_stopped(state: &State, epoch: u64) {
+    error!(
+        "Sync stopped at epoch {}, the local state stays at header num: {} (hash: {}, validators: {})",
+        epoch,
+        state.snapshot().number,
+        state.snapshot().hash,
+        state.snapshot().validators.len()
+    );
+}
@@ -7,6 +7,42 @@ use hyper::Body;
 use serde::de::DeserializeOwned;
 use serde_json::json;
 
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use log::warn;
+
+pub type RelayerError = Box<dyn std::error::Error + Send + Sync>;
+
+/// FetchConfig controls how many requests are in flight and how the failed ones are retried
+#[derive(Clone, Copy, Debug)]
+pub struct FetchConfig {
+    /// Maximum number of headers fetched concurrently
+    pub concurrency: usize,
+
+    /// Number of retries of a failed request, before giving up
+    pub max_retries: u32,
+
+    /// Delay before the first retry, doubled on each subsequent one
+    pub initial_backoff: Duration,
+
+    /// Upper bound of the delay between the retries
+    pub max_backoff: Duration,
+}
+
+impl Default for FetchConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: 8,
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Relayer {
     client: Client<HttpConnector, Body>,
     uri: String,
@@ -20,7 +56,7 @@ impl Relayer {
         }
     }
 
-    pub async fn get_block_header_by_number(&self, hex_num: &str) -> Result<Header, Box<dyn std::error::Error>> {
+    pub async fn get_block_header_by_number(&self, hex_num: &str) -> Result<Header, RelayerError> {
         let req = json!({
             "jsonrpc": "2.0",
             "method": "eth_getBlockByNumber",
@@ -31,7 +67,47 @@ impl Relayer {
         return self.fetch(req).await;
     }
 
-    async fn fetch<'de, T: DeserializeOwned>(&self, body: serde_json::Value) -> Result<T, Box<dyn std::error::Error>> {
+    /// Fetches the header, retrying the failed requests with the exponential backoff
+    pub async fn get_block_header_with_retry(&self, hex_num: &str, config: &FetchConfig) -> Result<Header, RelayerError> {
+        let mut backoff = config.initial_backoff;
+        let mut attempt = 0;
+
+        loop {
+            match self.get_block_header_by_number(hex_num).await {
+                Ok(header) => return Ok(header),
+                Err(e) if attempt < config.max_retries => {
+                    attempt += 1;
+                    warn!(
+                        "Failed to fetch block header num: {} ({}), retrying in {:?} ({}/{})",
+                        hex_num, e, backoff, attempt, config.max_retries
+                    );
+
+                    tokio::time::delay_for(backoff).await;
+                    backoff = std::cmp::min(backoff * 2, config.max_backoff);
+                }
+                Err(e) => {
+                    return Err(format!(
+                        "failed to fetch block header num: {} after {} retries: {}",
+                        hex_num, config.max_retries, e
+                    )
+                    .into())
+                }
+            }
+        }
+    }
+
+    /// Fetches the headers concurrently (see [`FetchConfig`]), while the [`HeaderFetcher`]
+    /// yields them in the order of the given block numbers
+    pub fn fetch_block_headers(&self, numbers: Vec<u64>, config: FetchConfig) -> HeaderFetcher {
+        HeaderFetcher {
+            relayer: self.clone(),
+            numbers: numbers.into(),
+            pending: VecDeque::new(),
+            config,
+        }
+    }
+
+    async fn fetch<'de, T: DeserializeOwned>(&self, body: serde_json::Value) -> Result<T, RelayerError> {
         let req = Request::builder()
             .method("POST")
             .uri(&self.uri)
@@ -51,3 +127,42 @@ impl Relayer {
         Ok(container.result)
     }
 }
+
+/// HeaderFetcher is a pipeline keeping up to `concurrency` header requests in flight
+pub struct HeaderFetcher {
+    relayer: Relayer,
+    numbers: VecDeque<u64>,
+    pending: VecDeque<(u64, JoinHandle<Result<Header, RelayerError>>)>,
+    config: FetchConfig,
+}
+
+impl HeaderFetcher {
+    /// Returns the next header in order, along with its block number, or None if all the
+    /// headers have been fetched
+    pub async fn next(&mut self) -> Option<(u64, Result<Header, RelayerError>)> {
+        // top up the pipeline before waiting for the oldest request
+        while self.pending.len() < self.config.concurrency.max(1) {
+            let number = match self.numbers.pop_front() {
+                Some(number) => number,
+                None => break,
+            };
+
+            let relayer = self.relayer.clone();
+            let config = self.config;
+            let handle = tokio::spawn(async move {
+                let hex_num = format!("0x{:x}", number);
+                relayer.get_block_header_with_retry(&hex_num, &config).await
+            });
+
+            self.pending.push_back((number, handle));
+        }
+
+        let (number, handle) = self.pending.pop_front()?;
+        let result = match handle.await {
+            Ok(result) => result,
+            Err(e) => Err(e.into()),
+        };
+
+        Some((number, result))
+    }
+}
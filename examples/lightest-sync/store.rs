@@ -0,0 +1,101 @@
+use celo_light_client::{FromRlp, Snapshot, ToRlp, ValidatorSet};
+
+use std::convert::TryInto;
+use std::error::Error;
+
+const EPOCH_SIZE_KEY: &[u8] = b"epoch_size";
+const LAST_EPOCH_KEY: &[u8] = b"last_epoch";
+const SNAPSHOT_KEY: &[u8] = b"snapshot";
+const VALIDATORS_PREFIX: &[u8] = b"validators/";
+
+/// Store persists the synced state, so that the sync can be resumed after a restart
+pub struct Store {
+    db: sled::Db,
+}
+
+impl Store {
+    pub fn open(path: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            db: sled::open(path)?,
+        })
+    }
+
+    /// Removes everything from the database, the sync will start from the genesis block
+    pub fn reset(&self) -> Result<(), Box<dyn Error>> {
+        self.db.clear()?;
+        self.db.flush()?;
+
+        Ok(())
+    }
+
+    /// Records the epoch size of the synced chain, or checks it matches the recorded one,
+    /// since the stored epochs are meaningless under a different epoch size
+    pub fn ensure_epoch_size(&self, epoch_size: u64) -> Result<(), Box<dyn Error>> {
+        match self.db.get(EPOCH_SIZE_KEY)? {
+            Some(value) => {
+                let stored = decode_u64(&value)?;
+                if stored != epoch_size {
+                    return Err(format!(
+                        "database was synced with epoch size {} (got: {}), use --reset to start over",
+                        stored, epoch_size
+                    )
+                    .into());
+                }
+            }
+            None => {
+                self.db.insert(EPOCH_SIZE_KEY, &epoch_size.to_be_bytes())?;
+                self.db.flush()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the last synced epoch along with the snapshot taken at its last block
+    pub fn last_epoch(&self) -> Result<Option<(u64, Snapshot)>, Box<dyn Error>> {
+        let epoch = match self.db.get(LAST_EPOCH_KEY)? {
+            Some(value) => decode_u64(&value)?,
+            None => return Ok(None),
+        };
+        let snapshot = match self.db.get(SNAPSHOT_KEY)? {
+            Some(value) => Snapshot::from_rlp(&value)?,
+            None => return Err(format!("missing snapshot of the epoch {}", epoch).into()),
+        };
+
+        Ok(Some((epoch, snapshot)))
+    }
+
+    /// Returns the validator set elected for the epoch, if the epoch has been synced
+    pub fn validators(&self, epoch: u64) -> Result<Option<ValidatorSet>, Box<dyn Error>> {
+        match self.db.get(validators_key(epoch))? {
+            Some(value) => Ok(Some(ValidatorSet::from_rlp(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Atomically stores the snapshot and the validator set of the synced epoch
+    pub fn put_epoch(&self, epoch: u64, snapshot: &Snapshot) -> Result<(), Box<dyn Error>> {
+        let mut batch = sled::Batch::default();
+        batch.insert(validators_key(epoch), snapshot.validators.to_rlp());
+        batch.insert(SNAPSHOT_KEY, snapshot.to_rlp());
+        batch.insert(LAST_EPOCH_KEY, &epoch.to_be_bytes());
+
+        self.db.apply_batch(batch)?;
+        self.db.flush()?;
+
+        Ok(())
+    }
+}
+
+fn validators_key(epoch: u64) -> Vec<u8> {
+    // big endian keeps the epochs sorted in the database
+    [VALIDATORS_PREFIX, &epoch.to_be_bytes()].concat()
+}
+
+fn decode_u64(value: &[u8]) -> Result<u64, Box<dyn Error>> {
+    let bytes: [u8; 8] = value
+        .try_into()
+        .map_err(|_| format!("invalid u64 value length: {}", value.len()))?;
+
+    Ok(u64::from_be_bytes(bytes))
+}
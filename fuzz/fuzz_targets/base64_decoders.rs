@@ -0,0 +1,27 @@
+#![no_main]
+use celo_light_client::contract::serialization::{
+    from_base64, from_base64_json_slice, from_base64_rlp, from_base64_rlp_or_json,
+    from_base64_rlp_or_json_strict,
+};
+use celo_light_client::contract::types::state::{LightClientState, LightConsensusState};
+use celo_light_client::contract::types::storage::StorageProof;
+use celo_light_client::Header;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // the raw input mostly exercises the base64 decoding, the encoded one reaches the decoders
+    let mut inputs = vec![base64::encode(data)];
+    if let Ok(input) = std::str::from_utf8(data) {
+        inputs.push(input.to_string());
+    }
+
+    for input in inputs.iter() {
+        let _ = from_base64(input, "data");
+        let _ = from_base64_rlp::<LightClientState, _>(input, "light_client_state");
+        let _ = from_base64_rlp_or_json::<Header, _>(input, "header");
+        let _ = from_base64_rlp_or_json::<LightConsensusState, _>(input, "consensus_state");
+        let _ = from_base64_rlp_or_json_strict::<Header, _>(input, "header");
+        let _ = from_base64_rlp_or_json_strict::<LightConsensusState, _>(input, "consensus_state");
+        let _ = from_base64_json_slice::<StorageProof, _>(input, "proof");
+    }
+});
@@ -0,0 +1,19 @@
+#![no_main]
+use celo_light_client::{FromRlp, FromRlpStrict, Header, ToRlp};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let header = match Header::from_rlp(data) {
+        Ok(header) => header,
+        Err(_) => return,
+    };
+
+    // the re-encoded header is canonical
+    let encoded = header.to_rlp();
+    assert_eq!(Header::from_rlp_strict(&encoded).unwrap(), header);
+
+    // the canonical encoding is the only one accepted by the strict decoder
+    if Header::from_rlp_strict(data).is_ok() {
+        assert_eq!(encoded, data);
+    }
+});
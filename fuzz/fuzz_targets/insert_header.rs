@@ -0,0 +1,46 @@
+#![no_main]
+use celo_light_client::{
+    Address, Config, FixedClock, FromRlp, Header, SerializedPublicKey, Snapshot, State, Timestamp,
+    Validator,
+};
+use libfuzzer_sys::fuzz_target;
+use std::convert::TryInto;
+
+fuzz_target!(|data: &[u8]| {
+    // [verification flags (1 byte) || current timestamp (8 bytes) || header RLP]
+    if data.len() < 9 {
+        return;
+    }
+
+    let header = match Header::from_rlp(&data[9..]) {
+        Ok(header) => header,
+        Err(_) => return,
+    };
+    let current_timestamp = u64::from_be_bytes(data[1..9].try_into().unwrap());
+    let clock = FixedClock(Timestamp::from_secs(current_timestamp));
+
+    let config = Config {
+        epoch_size: 4,
+        allowed_clock_skew: 5,
+        trusting_period: 0,
+        verify_epoch_headers: data[0] & 1 != 0,
+        verify_non_epoch_headers: data[0] & 2 != 0,
+        verify_header_timestamp: data[0] & 4 != 0,
+        trust_threshold: None,
+    };
+
+    let mut snapshot = Snapshot::new();
+    snapshot.validators = (0..4u8)
+        .map(|i| Validator {
+            address: Address::from([i; 20]),
+            public_key: SerializedPublicKey::from([i; 96]),
+        })
+        .collect::<Vec<Validator>>()
+        .into();
+
+    let mut state = State::new(snapshot, &config, &clock);
+    if state.insert_header(&header).is_ok() {
+        assert_eq!(state.snapshot().number, header.number_u64().unwrap());
+        assert_eq!(state.snapshot().hash, header.hash().unwrap());
+    }
+});
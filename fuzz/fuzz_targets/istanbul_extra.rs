@@ -0,0 +1,22 @@
+#![no_main]
+use celo_light_client::IstanbulExtra;
+use libfuzzer_sys::fuzz_target;
+use std::convert::TryInto;
+
+fuzz_target!(|data: &[u8]| {
+    let extra = match IstanbulExtra::from_rlp(data) {
+        Ok(extra) => extra,
+        Err(_) => return,
+    };
+    let _ = extra.validate();
+
+    // the re-encoded extra-data is canonical
+    let vanity: [u8; 32] = data[..32].try_into().unwrap();
+    let encoded = extra.to_rlp(&vanity);
+    assert_eq!(IstanbulExtra::from_rlp_strict(&encoded).unwrap(), extra);
+
+    // the canonical encoding is the only one accepted by the strict decoder
+    if IstanbulExtra::from_rlp_strict(data).is_ok() {
+        assert_eq!(encoded, data);
+    }
+});
@@ -0,0 +1,14 @@
+#![no_main]
+use celo_light_client::contract::types::state::LightClientState;
+use celo_light_client::{FromRlp, ToRlp};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let state = match LightClientState::from_rlp(data) {
+        Ok(state) => state,
+        Err(_) => return,
+    };
+
+    // older encoding versions are upgraded to the current one
+    assert_eq!(LightClientState::from_rlp(&state.to_rlp()).unwrap(), state);
+});
@@ -0,0 +1,19 @@
+#![no_main]
+use celo_light_client::{FromRlp, FromRlpStrict, Snapshot, ToRlp};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let snapshot = match Snapshot::from_rlp(data) {
+        Ok(snapshot) => snapshot,
+        Err(_) => return,
+    };
+
+    // older encoding versions are upgraded to the current (canonical) one
+    let encoded = snapshot.to_rlp();
+    assert_eq!(Snapshot::from_rlp_strict(&encoded).unwrap(), snapshot);
+
+    // the canonical encoding is the only one accepted by the strict decoder
+    if Snapshot::from_rlp_strict(data).is_ok() {
+        assert_eq!(encoded, data);
+    }
+});
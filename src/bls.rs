@@ -1,40 +1,72 @@
-use crate::algebra::CanonicalDeserialize;
+use crate::algebra::{CanonicalDeserialize, Zero};
 use crate::errors::{Error, Kind};
-use crate::istanbul::min_quorum_size;
 use crate::serialization::rlp::big_int_to_rlp_compat_bytes;
+use crate::traits::SealVerifier;
 use crate::types::header::Hash;
-use crate::types::istanbul::{IstanbulAggregatedSeal, IstanbulMsg};
+use crate::types::istanbul::{IstanbulAggregatedSeal, IstanbulMsg, SerializedPublicKey};
 use crate::types::state::Validator;
-use bls_crypto::{hash_to_curve::try_and_increment::DIRECT_HASH_TO_G1, PublicKey, Signature};
+use crate::types::validator_set::ValidatorSet;
+use bls_crypto::{
+    hash_to_curve::try_and_increment::{COMPOSITE_HASH_TO_G1, DIRECT_HASH_TO_G1},
+    PublicKey, Signature,
+};
 use num_bigint::BigInt as Integer;
 
 /// Uses BLS signature verification to validate header against provided validator set
 pub fn verify_aggregated_seal(
     header_hash: Hash,
-    validators: &[Validator],
+    validators: &ValidatorSet,
     aggregated_seal: &IstanbulAggregatedSeal,
 ) -> Result<(), Error> {
-    let proposal_seal = prepare_commited_seal(header_hash, &aggregated_seal.round);
-    let expected_quorum_size = min_quorum_size(validators.len());
+    validate_aggregated_seal(validators, aggregated_seal)?;
 
-    // Find which public keys signed from the provided validator set
-    let public_keys = validators
-        .iter()
-        .enumerate()
-        .filter(|(i, _)| aggregated_seal.bitmap.bit(*i as u64))
-        .map(|(_, validator)| deserialize_pub_key(&validator.public_key))
-        .collect::<Result<Vec<PublicKey>, Error>>()?;
+    verify_aggregated_signature(header_hash, validators, aggregated_seal)
+}
 
-    if public_keys.len() < expected_quorum_size {
+/// Checks that the aggregated seal fits the validator set (the signature length, the bitmap
+/// within the set size) and that it's signed by a quorum of the validators. It doesn't verify
+/// the signature itself.
+pub fn validate_aggregated_seal(
+    validators: &ValidatorSet,
+    aggregated_seal: &IstanbulAggregatedSeal,
+) -> Result<(), Error> {
+    if validators.is_empty() {
+        return Err(Kind::EmptyValidatorSet.into());
+    }
+
+    aggregated_seal.validate(validators.len())?;
+
+    // Find which public keys signed from the provided validator set
+    let signers = validators.signers(&aggregated_seal.bitmap)?;
+    let expected_quorum_size = validators.quorum_size();
+    if signers.len() < expected_quorum_size {
         return Err(Kind::MissingSeals {
-            current: public_keys.len(),
+            current: signers.len(),
             expected: expected_quorum_size,
         }
         .into());
     }
 
+    Ok(())
+}
+
+/// Verifies the BLS signature of the aggregated seal over the header hash, against the public
+/// keys of the signers picked by the bitmap. The seal is expected to be validated with
+/// [`validate_aggregated_seal`] beforehand.
+pub fn verify_aggregated_signature(
+    header_hash: Hash,
+    validators: &ValidatorSet,
+    aggregated_seal: &IstanbulAggregatedSeal,
+) -> Result<(), Error> {
+    let proposal_seal = prepare_commited_seal(header_hash, &aggregated_seal.round);
+    let signers = validators.signers(&aggregated_seal.bitmap)?;
+
     let sig = deserialize_signature(&aggregated_seal.signature)?;
-    let apk = PublicKey::aggregate(public_keys);
+    let apk = match validators.aggregated_public_key() {
+        // the whole set signed, no need to aggregate the keys again
+        Some(apk) if signers.len() == validators.len() => apk.clone(),
+        _ => aggregate_public_keys(signers)?,
+    };
 
     match apk.verify(&proposal_seal, &[], &sig, &*DIRECT_HASH_TO_G1) {
         Ok(_) => Ok(()),
@@ -42,6 +74,79 @@ pub fn verify_aggregated_seal(
     }
 }
 
+/// BlsSealVerifier is the default seal verification backend, that computes the BLS pairings
+/// with the bls-crypto library
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BlsSealVerifier;
+
+impl SealVerifier for BlsSealVerifier {
+    fn verify_aggregated_signature(
+        &self,
+        header_hash: Hash,
+        validators: &ValidatorSet,
+        aggregated_seal: &IstanbulAggregatedSeal,
+    ) -> Result<(), Error> {
+        verify_aggregated_signature(header_hash, validators, aggregated_seal)
+    }
+}
+
+/// Aggregates the public keys of the validators
+pub fn aggregate_public_keys<'a>(
+    validators: impl IntoIterator<Item = &'a Validator>,
+) -> Result<PublicKey, Error> {
+    let public_keys = validators
+        .into_iter()
+        .map(|validator| deserialize_pub_key(&validator.public_key))
+        .collect::<Result<Vec<PublicKey>, Error>>()?;
+
+    Ok(PublicKey::aggregate(public_keys))
+}
+
+/// Uses BLS signature verification to validate a commit seal of a single validator
+pub fn verify_commited_seal(
+    header_hash: Hash,
+    round: &Integer,
+    validator: &Validator,
+    commited_seal: &[u8],
+) -> Result<(), Error> {
+    let proposal_seal = prepare_commited_seal(header_hash, round);
+    let public_key = deserialize_pub_key(&validator.public_key)?;
+    let sig = deserialize_signature(commited_seal)?;
+
+    match public_key.verify(&proposal_seal, &[], &sig, &*DIRECT_HASH_TO_G1) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(Kind::BlsVerifyError.into()),
+    }
+}
+
+/// Checks that the serialized key is a valid BLS public key, that is a point of the G2 prime order
+/// subgroup (checked on deserialization) other than the identity
+pub fn validate_public_key(key: &SerializedPublicKey) -> Result<PublicKey, Error> {
+    let public_key = deserialize_pub_key(key)?;
+    if public_key.as_ref().is_zero() {
+        return Err(Kind::BlsInvalidPublicKey
+            .context("public key is the identity")
+            .into());
+    }
+
+    Ok(public_key)
+}
+
+/// Verifies the proof-of-possession of the validator BLS key (signature of the validator address
+/// as registered in the Validators contract). It rules out the rogue-key attacks on the
+/// aggregated public key.
+pub fn verify_proof_of_possession(validator: &Validator, proof: &[u8]) -> Result<(), Error> {
+    let public_key = validate_public_key(&validator.public_key)?;
+    let sig = deserialize_signature(proof)?;
+
+    match public_key.verify_pop(&validator.address, &sig, &*COMPOSITE_HASH_TO_G1) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(Kind::BlsInvalidProofOfPossession
+            .context(format!("validator {}", validator.address))
+            .into()),
+    }
+}
+
 fn prepare_commited_seal(hash: Hash, round: &Integer) -> Vec<u8> {
     let round_bytes = big_int_to_rlp_compat_bytes(&round);
     let commit_bytes = [IstanbulMsg::Commit as u8];
@@ -56,3 +161,251 @@ fn deserialize_signature(signature: &[u8]) -> Result<Signature, Error> {
 fn deserialize_pub_key(key: &[u8]) -> Result<PublicKey, Error> {
     PublicKey::deserialize(key).map_err(|e| Kind::BlsInvalidPublicKey.context(e).into())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use super::test_utils::{
+        generate_validators, identity_public_key, sign_aggregated_seal, sign_proof_of_possession,
+    };
+    use crate::types::bitmap::ValidatorBitmap;
+    use crate::types::istanbul::PUBLIC_KEY_LENGTH;
+
+    #[test]
+    fn verifies_aggregated_seal() {
+        let (keys, validators) = generate_validators(4);
+        let validators = ValidatorSet::from(validators);
+        let hash = Hash::from([1; 32]);
+
+        let seal = sign_aggregated_seal(&keys, hash, 0b0111, 0);
+        assert!(verify_aggregated_seal(hash, &validators, &seal).is_ok());
+
+        // signed by a different subset than the bitmap claims
+        let mut seal = sign_aggregated_seal(&keys, hash, 0b0111, 0);
+        seal.bitmap = ValidatorBitmap::from(0b1110);
+        let err = verify_aggregated_seal(hash, &validators, &seal).unwrap_err();
+        assert!(matches!(err.kind(), Kind::BlsVerifyError));
+
+        // signed over different hash
+        let seal = sign_aggregated_seal(&keys, Hash::from([2; 32]), 0b0111, 0);
+        let err = verify_aggregated_seal(hash, &validators, &seal).unwrap_err();
+        assert!(matches!(err.kind(), Kind::BlsVerifyError));
+
+        // not enough signers
+        let seal = sign_aggregated_seal(&keys, hash, 0b0011, 0);
+        let err = verify_aggregated_seal(hash, &validators, &seal).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            Kind::MissingSeals {
+                current: 2,
+                expected: 3
+            }
+        ));
+    }
+
+    #[test]
+    fn verifies_aggregated_seal_with_cached_key() {
+        let (keys, validators) = generate_validators(4);
+        let mut validators = ValidatorSet::from(validators);
+        validators.compute_aggregated_public_key().unwrap();
+        let hash = Hash::from([1; 32]);
+
+        for bitmap in vec![0b1111, 0b0111] {
+            let seal = sign_aggregated_seal(&keys, hash, bitmap, 0);
+            assert!(verify_aggregated_seal(hash, &validators, &seal).is_ok());
+
+            let seal = sign_aggregated_seal(&keys, Hash::from([2; 32]), bitmap, 0);
+            let err = verify_aggregated_seal(hash, &validators, &seal).unwrap_err();
+            assert!(matches!(err.kind(), Kind::BlsVerifyError));
+        }
+    }
+
+    #[test]
+    fn validates_public_keys() {
+        let (_, validators) = generate_validators(4);
+        for validator in validators.iter() {
+            assert!(validate_public_key(&validator.public_key).is_ok());
+        }
+
+        for key in vec![
+            identity_public_key(),
+            SerializedPublicKey::from([0xff; PUBLIC_KEY_LENGTH]),
+        ] {
+            let err = validate_public_key(&key).unwrap_err();
+            assert!(matches!(err.kind(), Kind::BlsInvalidPublicKey));
+        }
+    }
+
+    #[test]
+    fn verifies_proof_of_possession() {
+        let (keys, validators) = generate_validators(2);
+        let proof = sign_proof_of_possession(&keys[0], &validators[0].address);
+        assert!(verify_proof_of_possession(&validators[0], &proof).is_ok());
+
+        // proof of the other validator key
+        let err = verify_proof_of_possession(&validators[1], &proof).unwrap_err();
+        assert!(matches!(err.kind(), Kind::BlsInvalidProofOfPossession));
+
+        let err = verify_proof_of_possession(&validators[0], &proof[1..]).unwrap_err();
+        assert!(matches!(err.kind(), Kind::BlsInvalidSignature));
+    }
+
+    #[test]
+    fn rejects_empty_validator_set() {
+        let result = verify_aggregated_seal(
+            Hash::default(),
+            &ValidatorSet::new(),
+            &IstanbulAggregatedSeal::new(),
+        );
+
+        match result.unwrap_err().kind() {
+            Kind::EmptyValidatorSet => {}
+            kind => panic!("unexpected error kind: {:?}", kind),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use super::*;
+    use crate::algebra::{bls12_377::Fr, CanonicalSerialize};
+    use crate::types::bitmap::ValidatorBitmap;
+    use crate::types::header::Address;
+    use crate::types::istanbul::SerializedPublicKey;
+    use bls_crypto::PrivateKey;
+    use std::cell::RefCell;
+
+    /// SealVerifier that records the seals it was asked to verify, and accepts or rejects all
+    /// of them without computing the pairings
+    #[derive(Default)]
+    pub(crate) struct RecordingSealVerifier {
+        pub(crate) reject: bool,
+        pub(crate) seals: RefCell<Vec<(Hash, IstanbulAggregatedSeal)>>,
+    }
+
+    impl SealVerifier for RecordingSealVerifier {
+        fn verify_aggregated_signature(
+            &self,
+            header_hash: Hash,
+            _validators: &ValidatorSet,
+            aggregated_seal: &IstanbulAggregatedSeal,
+        ) -> Result<(), Error> {
+            self.seals
+                .borrow_mut()
+                .push((header_hash, aggregated_seal.clone()));
+
+            if self.reject {
+                return Err(Kind::BlsVerifyError.into());
+            }
+
+            Ok(())
+        }
+    }
+
+    /// Generates deterministic BLS keys along with the matching validator set
+    pub(crate) fn generate_validators(count: u8) -> (Vec<PrivateKey>, Vec<Validator>) {
+        (0..count)
+            .map(|i| {
+                let private_key = PrivateKey::from(Fr::from(u64::from(i) + 1));
+                let mut public_key = SerializedPublicKey::default();
+                private_key
+                    .to_public()
+                    .serialize(&mut public_key[..])
+                    .unwrap();
+
+                let validator = Validator {
+                    address: Address::from([i; 20]),
+                    public_key,
+                };
+
+                (private_key, validator)
+            })
+            .unzip()
+    }
+
+    /// Returns the serialized identity public key (ie. of the private key set to zero)
+    pub(crate) fn identity_public_key() -> SerializedPublicKey {
+        let mut public_key = SerializedPublicKey::default();
+        PrivateKey::from(Fr::from(0u64))
+            .to_public()
+            .serialize(&mut public_key[..])
+            .unwrap();
+
+        public_key
+    }
+
+    /// Returns the rogue public key, that cancels out the keys of the validators in their
+    /// aggregated public key, so that the aggregated seal can be signed with the private key alone
+    pub(crate) fn rogue_public_key(
+        private_key: &PrivateKey,
+        validators: &[Validator],
+    ) -> SerializedPublicKey {
+        let cancelled = aggregate_public_keys(validators).unwrap();
+        let rogue = PublicKey::from(*private_key.to_public().as_ref() - cancelled.as_ref());
+
+        let mut public_key = SerializedPublicKey::default();
+        rogue.serialize(&mut public_key[..]).unwrap();
+
+        public_key
+    }
+
+    /// Creates a proof-of-possession of the validator key, ie. signature of its address
+    pub(crate) fn sign_proof_of_possession(private_key: &PrivateKey, address: &Address) -> Vec<u8> {
+        let mut signature = Vec::new();
+        private_key
+            .sign_pop(address, &*COMPOSITE_HASH_TO_G1)
+            .unwrap()
+            .serialize(&mut signature)
+            .unwrap();
+
+        signature
+    }
+
+    /// Creates a commit seal of a single validator over the header hash
+    pub(crate) fn sign_commited_seal(
+        private_key: &PrivateKey,
+        header_hash: Hash,
+        round: u64,
+    ) -> Vec<u8> {
+        let proposal_seal = prepare_commited_seal(header_hash, &Integer::from(round));
+
+        let mut signature = Vec::new();
+        private_key
+            .sign(&proposal_seal, &[], &*DIRECT_HASH_TO_G1)
+            .unwrap()
+            .serialize(&mut signature)
+            .unwrap();
+
+        signature
+    }
+
+    /// Creates an aggregated seal over the header hash, signed by the keys selected by bitmap
+    pub(crate) fn sign_aggregated_seal(
+        private_keys: &[PrivateKey],
+        header_hash: Hash,
+        bitmap: u64,
+        round: u64,
+    ) -> IstanbulAggregatedSeal {
+        let round = Integer::from(round);
+        let proposal_seal = prepare_commited_seal(header_hash, &round);
+
+        let signatures: Vec<Signature> = private_keys
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| bitmap & (1 << i) != 0)
+            .map(|(_, key)| key.sign(&proposal_seal, &[], &*DIRECT_HASH_TO_G1).unwrap())
+            .collect();
+
+        let mut signature = Vec::new();
+        Signature::aggregate(signatures)
+            .serialize(&mut signature)
+            .unwrap();
+
+        IstanbulAggregatedSeal {
+            bitmap: ValidatorBitmap::from(bitmap),
+            signature,
+            round,
+        }
+    }
+}
@@ -0,0 +1,526 @@
+use crate::bls::verify_commited_seal;
+use crate::errors::{Error, Kind};
+use crate::istanbul::min_quorum_size;
+use crate::traits::SignerRecovery;
+use crate::types::consensus::{
+    Message, PrePrepare, PreparedCertificate, RoundChangeCertificate, Subject, View,
+};
+use crate::types::header::Hash;
+use crate::types::istanbul::IstanbulMsg;
+use crate::types::state::Validator;
+use num_bigint::BigInt as Integer;
+
+/// Verifies that a quorum of validators prepared (or committed) the certificate proposal.
+/// Returns the view at which the proposal was prepared.
+pub fn verify_prepared_certificate(
+    certificate: &PreparedCertificate,
+    validators: &[Validator],
+    recovery: &dyn SignerRecovery,
+) -> Result<View, Error> {
+    if certificate.is_empty() {
+        return Err(Kind::InvalidCertificate {
+            msg: "prepared certificate is empty",
+        }
+        .into());
+    }
+
+    let proposal_hash = certificate.proposal.hash()?;
+    let mut senders = Senders::new(validators);
+    let mut view: Option<View> = None;
+
+    for message in certificate.prepare_or_commit_messages.iter() {
+        let validator = senders.verify(message, recovery)?;
+
+        let subject: Subject = match message.code {
+            IstanbulMsg::Prepare => message.subject()?,
+            IstanbulMsg::Commit => {
+                let committed_subject = message.committed_subject()?;
+                verify_commited_seal(
+                    proposal_hash,
+                    &Integer::from(committed_subject.subject.view.round),
+                    validator,
+                    &committed_subject.committed_seal,
+                )?;
+
+                committed_subject.subject
+            }
+            _ => {
+                return Err(Kind::InvalidCertificate {
+                    msg: "prepared certificate contains non prepare/commit message",
+                }
+                .into())
+            }
+        };
+
+        if subject.digest != proposal_hash {
+            return Err(Kind::InvalidCertificate {
+                msg: "message digest doesn't match the proposal",
+            }
+            .into());
+        }
+
+        match view {
+            Some(view) if view != subject.view => {
+                return Err(Kind::InvalidCertificate {
+                    msg: "messages refer to different views",
+                }
+                .into())
+            }
+            _ => view = Some(subject.view),
+        }
+    }
+
+    senders.check_quorum()?;
+
+    let view = match view {
+        Some(view) => view,
+        None => {
+            return Err(Kind::InvalidCertificate {
+                msg: "prepared certificate is empty",
+            }
+            .into())
+        }
+    };
+    if certificate.proposal.header.number_u64()? != view.sequence {
+        return Err(Kind::InvalidCertificate {
+            msg: "proposal number doesn't match the view sequence",
+        }
+        .into());
+    }
+
+    Ok(view)
+}
+
+/// Verifies that a quorum of validators requested to move to the given view (or higher round).
+/// Returns the hash of the proposal prepared in the highest round, if any.
+pub fn verify_round_change_certificate(
+    certificate: &RoundChangeCertificate,
+    view: &View,
+    validators: &[Validator],
+    recovery: &dyn SignerRecovery,
+) -> Result<Option<Hash>, Error> {
+    if certificate.is_empty() {
+        return Err(Kind::InvalidCertificate {
+            msg: "round change certificate is empty",
+        }
+        .into());
+    }
+
+    let mut senders = Senders::new(validators);
+    let mut prepared: Option<(View, Hash)> = None;
+
+    for message in certificate.round_change_messages.iter() {
+        senders.verify(message, recovery)?;
+
+        let round_change = message.round_change()?;
+        if round_change.view.sequence != view.sequence || round_change.view.round < view.round {
+            return Err(Kind::InvalidCertificate {
+                msg: "round change message view doesn't match",
+            }
+            .into());
+        }
+
+        if round_change.prepared_certificate.is_empty() {
+            continue;
+        }
+
+        let prepared_view =
+            verify_prepared_certificate(&round_change.prepared_certificate, validators, recovery)?;
+        if prepared_view.sequence != view.sequence || prepared_view.round >= round_change.view.round
+        {
+            return Err(Kind::InvalidCertificate {
+                msg: "prepared certificate view doesn't precede the round change",
+            }
+            .into());
+        }
+
+        let proposal_hash = round_change.prepared_certificate.proposal.hash()?;
+        match prepared {
+            Some((highest, _)) if highest.round >= prepared_view.round => {}
+            _ => prepared = Some((prepared_view, proposal_hash)),
+        }
+    }
+
+    senders.check_quorum()?;
+
+    Ok(prepared.map(|(_, hash)| hash))
+}
+
+/// Verifies the PrePrepare message payload. A proposal for a round other than the first must be
+/// justified by a round change certificate, and must match the highest prepared proposal.
+pub fn verify_preprepare(
+    preprepare: &PrePrepare,
+    validators: &[Validator],
+    recovery: &dyn SignerRecovery,
+) -> Result<(), Error> {
+    if preprepare.proposal.header.number_u64()? != preprepare.view.sequence {
+        return Err(Kind::InvalidCertificate {
+            msg: "proposal number doesn't match the view sequence",
+        }
+        .into());
+    }
+
+    if preprepare.view.round == 0 {
+        return Ok(());
+    }
+
+    let prepared_hash = verify_round_change_certificate(
+        &preprepare.round_change_certificate,
+        &preprepare.view,
+        validators,
+        recovery,
+    )?;
+
+    match prepared_hash {
+        Some(hash) if hash != preprepare.proposal.hash()? => Err(Kind::InvalidCertificate {
+            msg: "proposal doesn't match the highest prepared proposal",
+        }
+        .into()),
+        _ => Ok(()),
+    }
+}
+
+/// Senders tracks the validators that sent messages included in a certificate
+struct Senders<'a> {
+    validators: &'a [Validator],
+    seen: Vec<bool>,
+    count: usize,
+}
+
+impl<'a> Senders<'a> {
+    fn new(validators: &'a [Validator]) -> Self {
+        Self {
+            validators,
+            seen: vec![false; validators.len()],
+            count: 0,
+        }
+    }
+
+    /// Checks the message signature, and makes sure the sender is a validator that hasn't
+    /// been seen before
+    fn verify(
+        &mut self,
+        message: &Message,
+        recovery: &dyn SignerRecovery,
+    ) -> Result<&'a Validator, Error> {
+        let signer = recovery.recover(&message.signing_hash()?, &message.signature)?;
+        if signer != message.address {
+            return Err(Kind::InvalidMessageSignature.into());
+        }
+
+        let position = match self.validators.iter().position(|v| v.address == signer) {
+            Some(position) => position,
+            None => {
+                return Err(Kind::InvalidCertificate {
+                    msg: "message sender is not a validator",
+                }
+                .into())
+            }
+        };
+
+        if self.seen[position] {
+            return Err(Kind::InvalidCertificate {
+                msg: "duplicated message sender",
+            }
+            .into());
+        }
+        self.seen[position] = true;
+        self.count += 1;
+
+        Ok(&self.validators[position])
+    }
+
+    fn check_quorum(&self) -> Result<(), Error> {
+        // the certificates carry the Celo consensus messages, hence the Celo quorum
+        let expected = min_quorum_size(self.validators.len());
+        if self.count < expected {
+            return Err(Kind::MissingSeals {
+                current: self.count,
+                expected,
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use super::*;
+    pub(crate) use crate::ecdsa::{pubkey_to_address, Secp256k1Recovery};
+    use crate::types::istanbul::SerializedPublicKey;
+    use secp256k1::{Message as SecpMessage, PublicKey, Secp256k1, SecretKey};
+
+    /// Generates deterministic ECDSA keys along with the matching validators (without BLS keys)
+    pub(crate) fn secp256k1_validators(count: u8) -> (Vec<SecretKey>, Vec<Validator>) {
+        let secp = Secp256k1::new();
+
+        (0..count)
+            .map(|i| {
+                let key = SecretKey::from_slice(&[i + 1; 32]).unwrap();
+                let validator = Validator {
+                    address: pubkey_to_address(&PublicKey::from_secret_key(&secp, &key)),
+                    public_key: SerializedPublicKey::default(),
+                };
+
+                (key, validator)
+            })
+            .unzip()
+    }
+
+    /// Creates the 65 bytes [R || S || V] signature over the hash
+    pub(crate) fn sign_hash(key: &SecretKey, hash: &Hash) -> Vec<u8> {
+        let (recovery_id, signature) = Secp256k1::new()
+            .sign_recoverable(&SecpMessage::from_slice(hash).unwrap(), key)
+            .serialize_compact();
+
+        [&signature[..], &[recovery_id.to_i32() as u8]].concat()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::{
+        pubkey_to_address, secp256k1_validators, sign_hash, Secp256k1Recovery,
+    };
+    use super::*;
+    use crate::bls::test_utils::{generate_validators, sign_commited_seal};
+    use crate::types::bitmap::ValidatorBitmap;
+    use crate::types::consensus::{CommittedSubject, Proposal, RoundChange};
+    use crate::types::header::Header;
+    use crate::types::istanbul::{IstanbulAggregatedSeal, IstanbulExtra, IstanbulExtraVanity};
+    use bls_crypto::PrivateKey;
+    use secp256k1::{PublicKey, Secp256k1, SecretKey};
+
+    struct TestValidator {
+        ecdsa_key: SecretKey,
+        bls_key: PrivateKey,
+    }
+
+    #[test]
+    fn verifies_prepared_certificate() {
+        let (keys, validators) = test_validators(4);
+        let proposal = proposal(100, Hash::from([1; 32]));
+        let view = View {
+            round: 1,
+            sequence: 100,
+        };
+
+        let certificate = PreparedCertificate {
+            proposal: proposal.clone(),
+            prepare_or_commit_messages: vec![
+                prepare(&keys[0], view, &proposal),
+                commit(&keys[1], view, &proposal),
+                prepare(&keys[2], view, &proposal),
+            ],
+        };
+        assert_eq!(
+            verify_prepared_certificate(&certificate, &validators, &Secp256k1Recovery).unwrap(),
+            view
+        );
+
+        // no quorum
+        let mut certificate_without_quorum = certificate.clone();
+        certificate_without_quorum.prepare_or_commit_messages.pop();
+        let err = verify_prepared_certificate(
+            &certificate_without_quorum,
+            &validators,
+            &Secp256k1Recovery,
+        )
+        .unwrap_err();
+        assert!(matches!(err.kind(), Kind::MissingSeals { .. }));
+
+        // duplicated sender
+        let mut duplicated = certificate.clone();
+        duplicated.prepare_or_commit_messages[2] = prepare(&keys[0], view, &proposal);
+        let err =
+            verify_prepared_certificate(&duplicated, &validators, &Secp256k1Recovery).unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidCertificate { .. }));
+
+        // spoofed sender
+        let mut spoofed = certificate.clone();
+        spoofed.prepare_or_commit_messages[2].address = validators[3].address;
+        let err =
+            verify_prepared_certificate(&spoofed, &validators, &Secp256k1Recovery).unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidMessageSignature));
+
+        // digest of a different proposal
+        let mut different_digest = certificate.clone();
+        different_digest.prepare_or_commit_messages[2] =
+            prepare(&keys[2], view, &self::proposal(100, Hash::from([2; 32])));
+        let err = verify_prepared_certificate(&different_digest, &validators, &Secp256k1Recovery)
+            .unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidCertificate { .. }));
+
+        // different views
+        let mut different_view = certificate;
+        different_view.prepare_or_commit_messages[2] = prepare(
+            &keys[2],
+            View {
+                round: 0,
+                sequence: 100,
+            },
+            &proposal,
+        );
+        let err = verify_prepared_certificate(&different_view, &validators, &Secp256k1Recovery)
+            .unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidCertificate { .. }));
+    }
+
+    #[test]
+    fn verifies_round_change_certificate() {
+        let (keys, validators) = test_validators(4);
+        let proposal = proposal(100, Hash::from([1; 32]));
+        let prepared_view = View {
+            round: 0,
+            sequence: 100,
+        };
+        let view = View {
+            round: 1,
+            sequence: 100,
+        };
+
+        let prepared_certificate = PreparedCertificate {
+            proposal: proposal.clone(),
+            prepare_or_commit_messages: vec![
+                prepare(&keys[0], prepared_view, &proposal),
+                prepare(&keys[1], prepared_view, &proposal),
+                prepare(&keys[2], prepared_view, &proposal),
+            ],
+        };
+        let empty_certificate = PreparedCertificate {
+            proposal: self::proposal(0, Hash::from([0; 32])),
+            prepare_or_commit_messages: Vec::new(),
+        };
+
+        let certificate = RoundChangeCertificate {
+            round_change_messages: vec![
+                round_change(&keys[1], view, &empty_certificate),
+                round_change(&keys[2], view, &prepared_certificate),
+                round_change(&keys[3], view, &empty_certificate),
+            ],
+        };
+        assert_eq!(
+            verify_round_change_certificate(&certificate, &view, &validators, &Secp256k1Recovery)
+                .unwrap(),
+            Some(proposal.hash().unwrap())
+        );
+
+        // the preprepare must re-propose the prepared block
+        let preprepare = PrePrepare {
+            view,
+            proposal: proposal.clone(),
+            round_change_certificate: certificate.clone(),
+        };
+        assert!(verify_preprepare(&preprepare, &validators, &Secp256k1Recovery).is_ok());
+
+        let preprepare = PrePrepare {
+            proposal: self::proposal(100, Hash::from([2; 32])),
+            ..preprepare
+        };
+        let err = verify_preprepare(&preprepare, &validators, &Secp256k1Recovery).unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidCertificate { .. }));
+
+        // round change to a lower round than requested
+        let err = verify_round_change_certificate(
+            &certificate,
+            &View {
+                round: 2,
+                sequence: 100,
+            },
+            &validators,
+            &Secp256k1Recovery,
+        )
+        .unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidCertificate { .. }));
+
+        // non round change message
+        let mut invalid = certificate;
+        invalid.round_change_messages[0] = prepare(&keys[0], view, &proposal);
+        let err = verify_round_change_certificate(&invalid, &view, &validators, &Secp256k1Recovery)
+            .unwrap_err();
+        assert!(matches!(err.kind(), Kind::UnexpectedMessageCode { .. }));
+    }
+
+    fn test_validators(count: u8) -> (Vec<TestValidator>, Vec<Validator>) {
+        let (bls_keys, mut validators) = generate_validators(count);
+        let (ecdsa_keys, ecdsa_validators) = secp256k1_validators(count);
+
+        for (validator, ecdsa_validator) in validators.iter_mut().zip(ecdsa_validators) {
+            validator.address = ecdsa_validator.address;
+        }
+
+        let keys = ecdsa_keys
+            .into_iter()
+            .zip(bls_keys)
+            .map(|(ecdsa_key, bls_key)| TestValidator { ecdsa_key, bls_key })
+            .collect();
+
+        (keys, validators)
+    }
+
+    fn proposal(number: u64, root: Hash) -> Proposal {
+        let mut header = Header::new();
+        header.number = Integer::from(number);
+        header.root = root;
+        header.extra = IstanbulExtra {
+            added_validators: Vec::new(),
+            added_validators_public_keys: Vec::new(),
+            removed_validators: ValidatorBitmap::new(),
+            seal: Vec::new(),
+            aggregated_seal: IstanbulAggregatedSeal::new(),
+            parent_aggregated_seal: IstanbulAggregatedSeal::new(),
+        }
+        .to_rlp(&IstanbulExtraVanity::default());
+
+        Proposal {
+            header,
+            body: Vec::new(),
+        }
+    }
+
+    fn prepare(key: &TestValidator, view: View, proposal: &Proposal) -> Message {
+        let subject = Subject {
+            view,
+            digest: proposal.hash().unwrap(),
+        };
+
+        sign_message(key, IstanbulMsg::Prepare, rlp::encode(&subject))
+    }
+
+    fn commit(key: &TestValidator, view: View, proposal: &Proposal) -> Message {
+        let digest = proposal.hash().unwrap();
+        let committed_subject = CommittedSubject {
+            subject: Subject { view, digest },
+            committed_seal: sign_commited_seal(&key.bls_key, digest, view.round),
+            epoch_validator_set_seal: Vec::new(),
+        };
+
+        sign_message(key, IstanbulMsg::Commit, rlp::encode(&committed_subject))
+    }
+
+    fn round_change(key: &TestValidator, view: View, certificate: &PreparedCertificate) -> Message {
+        let round_change = RoundChange {
+            view,
+            prepared_certificate: certificate.clone(),
+        };
+
+        sign_message(key, IstanbulMsg::RoundChange, rlp::encode(&round_change))
+    }
+
+    fn sign_message(key: &TestValidator, code: IstanbulMsg, msg: Vec<u8>) -> Message {
+        let mut message = Message {
+            code,
+            msg,
+            address: pubkey_to_address(&PublicKey::from_secret_key(
+                &Secp256k1::new(),
+                &key.ecdsa_key,
+            )),
+            signature: Vec::new(),
+        };
+
+        message.signature = sign_hash(&key.ecdsa_key, &message.signing_hash().unwrap());
+        message
+    }
+}
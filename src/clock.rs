@@ -0,0 +1,140 @@
+use crate::errors::{Error, Kind};
+use crate::traits::Clock;
+use std::convert::TryFrom;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Timestamp is a point in time measured since the UNIX epoch. It's meant to be built with an
+/// explicit unit, since Celo headers carry seconds while IBC works with nanoseconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(Duration);
+
+impl Timestamp {
+    /// Creates the timestamp from the number of seconds since the UNIX epoch
+    pub fn from_secs(secs: u64) -> Self {
+        Timestamp(Duration::from_secs(secs))
+    }
+
+    /// Creates the timestamp from the number of nanoseconds since the UNIX epoch
+    pub fn from_nanos(nanos: u64) -> Self {
+        Timestamp(Duration::from_nanos(nanos))
+    }
+
+    /// Returns the number of whole seconds since the UNIX epoch
+    pub fn as_secs(&self) -> u64 {
+        self.0.as_secs()
+    }
+
+    /// Returns the number of nanoseconds since the UNIX epoch, errors out if it doesn't fit u64
+    pub fn as_nanos(&self) -> Result<u64, Error> {
+        u64::try_from(self.0.as_nanos()).map_err(|_| {
+            Kind::ArithmeticOverflow {
+                msg: "timestamp nanoseconds don't fit into u64",
+            }
+            .into()
+        })
+    }
+
+    /// Returns the timestamp moved forward by the duration, or None on overflow
+    pub fn checked_add(&self, duration: Duration) -> Option<Self> {
+        self.0.checked_add(duration).map(Timestamp)
+    }
+
+    /// Returns the timestamp moved forward by the duration, saturating at the max value
+    pub fn saturating_add(&self, duration: Duration) -> Self {
+        self.checked_add(duration)
+            .unwrap_or(Timestamp(Duration::MAX))
+    }
+
+    /// Returns the time elapsed since the earlier timestamp, or None if it's not earlier
+    pub fn duration_since(&self, earlier: Timestamp) -> Option<Duration> {
+        self.0.checked_sub(earlier.0)
+    }
+}
+
+impl std::fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{:09}s", self.0.as_secs(), self.0.subsec_nanos())
+    }
+}
+
+/// SystemClock reads the time from the operating system. It's not available on the wasm
+/// targets, where the time should be provided by the host (see [`FixedClock`]).
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Clock for SystemClock {
+    fn now(&self) -> Timestamp {
+        // the clock set before the UNIX epoch is treated as the epoch itself
+        let since_epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        Timestamp(since_epoch)
+    }
+}
+
+/// FixedClock always returns the same time, ie. the block time of the host chain
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FixedClock(pub Timestamp);
+
+impl Clock for FixedClock {
+    fn now(&self) -> Timestamp {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_between_units() {
+        let timestamp = Timestamp::from_secs(1_600_000_000);
+        assert_eq!(timestamp.as_secs(), 1_600_000_000);
+        assert_eq!(timestamp.as_nanos().unwrap(), 1_600_000_000_000_000_000);
+        assert_eq!(
+            Timestamp::from_nanos(1_600_000_000_999_999_999),
+            timestamp.saturating_add(Duration::new(0, 999_999_999))
+        );
+        assert_eq!(
+            Timestamp::from_nanos(1_600_000_000_999_999_999).as_secs(),
+            1_600_000_000
+        );
+        assert_eq!(
+            Timestamp::from_nanos(1_500_000_000).to_string(),
+            "1.500000000s"
+        );
+    }
+
+    #[test]
+    fn handles_overflows() {
+        let timestamp = Timestamp::from_secs(u64::MAX);
+        assert!(matches!(
+            timestamp.as_nanos().unwrap_err().kind(),
+            Kind::ArithmeticOverflow { .. }
+        ));
+        assert_eq!(timestamp.checked_add(Duration::from_secs(1)), None);
+        assert!(timestamp.saturating_add(Duration::from_secs(1)) > timestamp);
+        assert_eq!(
+            Timestamp::from_secs(2).duration_since(Timestamp::from_secs(1)),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(
+            Timestamp::from_secs(1).duration_since(Timestamp::from_secs(2)),
+            None
+        );
+    }
+
+    #[test]
+    fn reads_the_clock() {
+        assert_eq!(
+            FixedClock(Timestamp::from_secs(5)).now(),
+            Timestamp::from_secs(5)
+        );
+        assert!(SystemClock.now() > Timestamp::from_secs(1_600_000_000));
+    }
+}
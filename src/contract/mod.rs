@@ -1,11 +1,16 @@
 pub mod types;
-mod serialization;
+pub mod serialization;
 mod util;
 mod store;
 
 use crate::contract::{
-    serialization::{from_base64, from_base64_json_slice, from_base64_rlp},
-    store::{get_processed_time, set_processed_time},
+    serialization::{
+        from_base64, from_base64_json_slice, from_base64_rlp, from_base64_rlp_or_json,
+        from_base64_rlp_or_json_strict,
+    },
+    store::{
+        get_processed_time, get_provisional_update, set_processed_time, set_provisional_update,
+    },
     types::ibc::{
         apply_prefix, verify_membership, Channel, ChannelId, ClientId, ClientUpgradePath,
         ConnectionEnd, ConnectionId, Height, MerklePath, MerklePrefix, MerkleProof, MerkleRoot,
@@ -19,19 +24,26 @@ use crate::contract::{
         VerifyPacketCommitmentResult, VerifyPacketReceiptAbsenceResult,
         VerifyUpgradeAndUpdateStateResult,
     },
-    types::state::{LightClientState, LightConsensusState},
+    types::state::{LightClientState, LightConsensusState, ProvisionalUpdate},
+    types::storage::{ConsensusRoot, StorageProof},
     types::wasm::{
         ClientState, ConsensusState, CosmosClientState, CosmosConsensusState, Misbehaviour,
         WasmHeader,
     },
-    util::{to_generic_err, u64_to_big_endian, wrap_response},
+    util::{block_timestamp, to_generic_err, u64_to_big_endian, wrap_response},
 };
-use crate::{state::State, traits::ToRlp, types::header::Header};
+use crate::{evidence::extract_double_sign, state::State, traits::ToRlp, types::header::Header};
+use crate::clock::{FixedClock, Timestamp};
+use crate::istanbul::{get_epoch_last_block_number, get_epoch_number};
+use crate::traits::Clock;
+use crate::types::header::Address;
 
 use cosmwasm_std::{attr, to_vec, Binary};
 use cosmwasm_std::{Deps, DepsMut, Env, MessageInfo};
-use cosmwasm_std::{HandleResponse, InitResponse, StdError, StdResult};
+use cosmwasm_std::{HandleResponse, InitResponse, StdError, StdResult, Storage};
 
+use std::collections::HashMap;
+use std::time::Duration;
 use std::str::FromStr;
 
 // # A few notes on certain design decisions
@@ -57,6 +69,10 @@ use std::str::FromStr;
 // Taken all the possible options I think the easiest way is to use RLP for the structs that fail
 // to serialize/deserialize via JSON (ie. Header, LightConsensusState)
 //
+// Alternatively, Header and LightConsensusState can be submitted as JSON (the same format
+// celo-blockchain RPC returns). Such messages are decoded with the float-free JSON codec
+// (see: `src/serialization/json.rs`) that doesn't depend on serde.
+//
 // ## IBC
 // ### Proof
 // ICS-23 specifies the generic proof structure (ie. ExistenceProof). Without the other side of the
@@ -112,7 +128,14 @@ pub(crate) fn handle(
             header,
             consensus_state,
             me,
-        } => check_header_and_update_state(deps, env, me, consensus_state, header),
+        } => check_header_and_update_state(
+            deps,
+            env,
+            me,
+            consensus_state,
+            header,
+            UpdateMode::Regular,
+        ),
 
         HandleMsg::CheckMisbehaviourAndUpdateState {
             me,
@@ -128,6 +151,12 @@ pub(crate) fn handle(
             consensus_state_2,
         ),
 
+        HandleMsg::ChallengeOptimisticUpdate {
+            me,
+            header,
+            consensus_state,
+        } => challenge_optimistic_update(deps, env, me, consensus_state, header),
+
         HandleMsg::CheckProposedHeaderAndUpdateState {
             header,
             consensus_state,
@@ -351,7 +380,8 @@ pub(crate) fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::ProcessedTime { height } => {
             let processed_time = get_processed_time(deps.storage, height)?;
             Ok(cosmwasm_std::to_binary(&ProcessedTimeResponse {
-                time: processed_time,
+                time: processed_time.as_secs(),
+                time_nanos: processed_time.as_nanos().map_err(to_generic_err)?,
             })?)
         }
     }
@@ -364,9 +394,11 @@ fn init_contract(
     consensus_state: ConsensusState,
     me: ClientState,
 ) -> Result<HandleResponse, StdError> {
+    let latest_height = get_latest_height(&me)?;
+
     // Unmarshal initial state entry (ie. validator set, epoch_size etc.)
     let light_consensus_state: LightConsensusState =
-        from_base64_rlp(&consensus_state.data, "msg.initial_state_entry")?;
+        from_base64_rlp_or_json_strict(&consensus_state.data, "msg.initial_state_entry")?;
 
     // Verify initial state
     match light_consensus_state.verify() {
@@ -380,7 +412,7 @@ fn init_contract(
     }
 
     // set processed time with initial consensus state height equal to initial client state's latest height
-    set_processed_time(deps.storage, me.latest_height.unwrap(), env.block.time)?;
+    set_processed_time(deps.storage, latest_height, block_timestamp(&env))?;
 
     // Update the state
     let response_data = Binary(to_vec(&InitializeStateResult {
@@ -398,28 +430,79 @@ fn init_contract(
     })
 }
 
+// UpdateMode defines how the header is verified before the consensus state gets updated
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum UpdateMode {
+    // The header submitted by a relayer, accepted optimistically if the challenge period is set
+    Regular,
+    // The header submitted via the governance proposal, always fully verified. The expired
+    // client is allowed to be updated, if the proposal is for reviving it.
+    Proposal { expired: bool },
+}
+
 fn check_header_and_update_state(
     deps: DepsMut,
     env: Env,
     me: ClientState,
     consensus_state: ConsensusState,
     wasm_header: WasmHeader,
+    mode: UpdateMode,
 ) -> Result<HandleResponse, StdError> {
-    let current_timestamp: u64 = env.block.time;
+    let clock = FixedClock(block_timestamp(&env));
 
     // Unmarshal header
-    let header: Header = from_base64_rlp(&wasm_header.data, "msg.header")?;
+    let header: Header = from_base64_rlp_or_json_strict(&wasm_header.data, "msg.header")?;
 
     // Unmarshal state entry
     let light_consensus_state: LightConsensusState =
-        from_base64_rlp(&consensus_state.data, "msg.light_consensus_state")?;
+        from_base64_rlp_or_json(&consensus_state.data, "msg.light_consensus_state")?;
 
     // Unmarshal state config
     let light_client_state: LightClientState = from_base64_rlp(&me.data, "msg.light_client_state")?;
 
+    // The expired client can be revived only via the proposal (if allowed by the config)
+    let mut state_config = light_client_state.clone();
+    if mode == (UpdateMode::Proposal { expired: true }) {
+        state_config.trusting_period = 0;
+    }
+
+    // The optimistic update skips the seal verification, the header can be challenged instead
+    let optimistic = mode == UpdateMode::Regular && light_client_state.challenge_period > 0;
+    if optimistic {
+        state_config.verify_epoch_headers = false;
+        state_config.verify_non_epoch_headers = false;
+    }
+    let validators_hash = light_consensus_state.validators.hash();
+
+    if mode == UpdateMode::Regular {
+        // The provisional consensus state can't be built upon until its challenge period has
+        // passed, otherwise a forged consensus state could vouch for the next updates
+        verify_consensus_state_final(deps.storage, get_latest_height(&me)?, &env)?;
+    }
+
+    if optimistic {
+        // The conflicting header is verified against the trusted validator set, that seals the
+        // blocks up to the last block of its epoch only
+        let epoch_size = light_client_state.epoch_size;
+        let epoch = get_epoch_number(light_consensus_state.number.saturating_add(1), epoch_size);
+        let last_block = get_epoch_last_block_number(epoch, epoch_size);
+        if header.number_u64().map_err(to_generic_err)? > last_block {
+            return Err(StdError::generic_err(format!(
+                "Unable to ingest header. Error: optimistic update is allowed up to the block {}",
+                last_block
+            )));
+        }
+    }
+
     // Ingest new header
-    let mut state: State = State::new(light_consensus_state, &light_client_state);
-    match state.insert_header(&header, current_timestamp) {
+    let mut state: State = State::new(light_consensus_state, &state_config, &clock);
+    state.set_proofs_of_possession(get_proofs_of_possession(&wasm_header)?);
+    if optimistic {
+        state.verify_header_fields(&header).map_err(|e| {
+            StdError::generic_err(format!("Unable to ingest header. Error: {}", e))
+        })?;
+    }
+    match state.insert_header(&header) {
         Err(e) => {
             return Err(StdError::generic_err(format!(
                 "Unable to ingest header. Error: {}",
@@ -429,6 +512,9 @@ fn check_header_and_update_state(
         _ => {}
     }
 
+    // Get the root for the IBC proofs (must be computed against the verified header)
+    let root = get_consensus_root(&light_client_state, &header, &wasm_header)?;
+
     // Update the state
     let new_client_state = me.clone();
     let new_consensus_state = ConsensusState {
@@ -436,13 +522,40 @@ fn check_header_and_update_state(
         data: base64::encode(state.snapshot().to_rlp().as_slice()),
         timestamp: header.time,
         root: MerkleRoot {
-            hash: base64::encode(header.root.to_vec().as_slice()),
+            hash: base64::encode(root.as_slice()),
         },
         r#type: consensus_state.r#type,
     };
 
     // set block height as processed time
-    set_processed_time(deps.storage, wasm_header.height, current_timestamp)?;
+    set_processed_time(deps.storage, wasm_header.height, clock.now())?;
+
+    let mut attributes = vec![
+        attr("action", "update_block"),
+        attr("last_consensus_state_height", state.snapshot().number),
+        attr(
+            "validator_set_hash",
+            state.snapshot().validators.hash().to_string(),
+        ),
+    ];
+
+    // the consensus state stays provisional until the challenge period has passed
+    if optimistic {
+        let challenge_deadline = clock
+            .now()
+            .checked_add(Duration::from_secs(light_client_state.challenge_period))
+            .ok_or_else(|| StdError::generic_err("challenge period overflows the block time"))?;
+        let update = ProvisionalUpdate {
+            number: state.snapshot().number,
+            header_hash: state.snapshot().hash,
+            validators_hash,
+            challenge_deadline: challenge_deadline.as_nanos().map_err(to_generic_err)?,
+            reverted: false,
+        };
+        set_provisional_update(deps.storage, wasm_header.height, &update)?;
+
+        attributes.push(attr("challenge_deadline", update.challenge_deadline));
+    }
 
     let response_data = Binary(to_vec(&CheckHeaderAndUpdateStateResult {
         new_client_state,
@@ -452,10 +565,7 @@ fn check_header_and_update_state(
 
     Ok(HandleResponse {
         messages: vec![],
-        attributes: vec![
-            attr("action", "update_block"),
-            attr("last_consensus_state_height", state.snapshot().number),
-        ],
+        attributes,
         data: Some(response_data),
     })
 }
@@ -467,9 +577,11 @@ fn check_proposed_header(
     consensus_state: ConsensusState,
     header: WasmHeader,
 ) -> Result<HandleResponse, StdError> {
-    let current_timestamp: u64 = env.block.time;
+    let current_timestamp = block_timestamp(&env);
     let mut new_client_state = me.clone();
     let light_client_state: LightClientState = from_base64_rlp(&me.data, "msg.light_client_state")?;
+    let light_consensus_state: LightConsensusState =
+        from_base64_rlp_or_json(&consensus_state.data, "msg.light_consensus_state")?;
 
     if me.frozen {
         if !light_client_state.allow_update_after_misbehavior {
@@ -482,12 +594,26 @@ fn check_proposed_header(
         new_client_state.frozen_height = None;
 
         // No softer validation for expired clients
-        return check_header_and_update_state(deps, env, new_client_state, consensus_state, header);
+        return check_header_and_update_state(
+            deps,
+            env,
+            new_client_state,
+            consensus_state,
+            header,
+            UpdateMode::Proposal { expired: false },
+        );
     } else if light_client_state.allow_update_after_expiry
-        && consensus_state.timestamp + light_client_state.trusting_period > current_timestamp
+        && light_consensus_state.is_expired(light_client_state.trusting_period, current_timestamp)
     {
-        // If client is expired, lets perform full validation
-        return check_header_and_update_state(deps, env, new_client_state, consensus_state, header);
+        // If client is expired, lets perform full validation (except for the expiration itself)
+        return check_header_and_update_state(
+            deps,
+            env,
+            new_client_state,
+            consensus_state,
+            header,
+            UpdateMode::Proposal { expired: true },
+        );
     }
 
     Err(StdError::generic_err(
@@ -506,6 +632,7 @@ pub fn verify_upgrade_and_update_state(
     last_height_consensus_state: ConsensusState,
 ) -> Result<HandleResponse, StdError> {
     let specs = vec![ics23::iavl_spec(), ics23::tendermint_spec()];
+    let latest_height = get_latest_height(&me)?;
 
     // Sanity check
     if !(new_client_state.latest_height > me.latest_height) {
@@ -528,10 +655,14 @@ pub fn verify_upgrade_and_update_state(
     )?;
 
     // Check consensus state expiration
-    let current_timestamp: u64 = env.block.time;
+    let current_timestamp = block_timestamp(&env);
     let light_client_state: LightClientState = from_base64_rlp(&me.data, "msg.light_client_state")?;
-    if last_height_consensus_state.timestamp + light_client_state.trusting_period
-        > current_timestamp
+    let last_height_light_consensus_state: LightConsensusState = from_base64_rlp_or_json(
+        &last_height_consensus_state.data,
+        "msg.last_height_consensus_state",
+    )?;
+    if last_height_light_consensus_state
+        .is_expired(light_client_state.trusting_period, current_timestamp)
     {
         return Err(StdError::generic_err("cannot upgrade an expired client"));
     }
@@ -540,7 +671,7 @@ pub fn verify_upgrade_and_update_state(
     let value: Vec<u8> = to_vec(&new_client_state)?;
     let upgrade_client_path = construct_upgrade_merkle_path(
         &light_client_state.upgrade_path,
-        ClientUpgradePath::UpgradedClientState(me.latest_height.unwrap().revision_number),
+        ClientUpgradePath::UpgradedClientState(latest_height.revision_number),
     );
     if !verify_membership(
         &proof_consensus,
@@ -559,7 +690,7 @@ pub fn verify_upgrade_and_update_state(
     let value: Vec<u8> = to_vec(&new_consensus_state)?;
     let upgrade_consensus_state_path = construct_upgrade_merkle_path(
         &light_client_state.upgrade_path,
-        ClientUpgradePath::UpgradedClientConsensusState(me.latest_height.unwrap().revision_number),
+        ClientUpgradePath::UpgradedClientConsensusState(latest_height.revision_number),
     );
     if !verify_membership(
         &proof_client,
@@ -589,7 +720,7 @@ pub fn verify_upgrade_and_update_state(
 
 pub fn check_misbehaviour(
     _deps: DepsMut,
-    _env: Env,
+    env: Env,
     me: ClientState,
     misbehaviour: Misbehaviour,
     consensus_state1: ConsensusState,
@@ -604,20 +735,20 @@ pub fn check_misbehaviour(
     }
 
     // If client is already frozen at earlier height than misbehaviour, return with error
-    if me.frozen
-        && me.frozen_height.is_some()
-        && me.frozen_height.unwrap() <= misbehaviour.header_1.height
-    {
-        return Err(StdError::generic_err(format!(
-            "Client is already frozen at earlier height {} than misbehaviour height {}",
-            me.frozen_height.unwrap(),
-            misbehaviour.header_1.height
-        )));
+    if let Some(frozen_height) = me.frozen_height {
+        if me.frozen && frozen_height <= misbehaviour.header_1.height {
+            return Err(StdError::generic_err(format!(
+                "Client is already frozen at earlier height {} than misbehaviour height {}",
+                frozen_height, misbehaviour.header_1.height
+            )));
+        }
     }
 
     // Unmarshal header
-    let header_1: Header = from_base64_rlp(&misbehaviour.header_1.data, "msg.header")?;
-    let header_2: Header = from_base64_rlp(&misbehaviour.header_2.data, "msg.header")?;
+    let header_1: Header =
+        from_base64_rlp_or_json_strict(&misbehaviour.header_1.data, "msg.header")?;
+    let header_2: Header =
+        from_base64_rlp_or_json_strict(&misbehaviour.header_2.data, "msg.header")?;
 
     // The header state root should differ
     if header_1.root == header_2.root {
@@ -628,8 +759,29 @@ pub fn check_misbehaviour(
 
     // Check the validity of the two conflicting headers against their respective
     // trusted consensus states
-    check_misbehaviour_header(1, &me, &consensus_state1, &header_1)?;
-    check_misbehaviour_header(2, &me, &consensus_state2, &header_2)?;
+    let clock = FixedClock(block_timestamp(&env));
+    let light_consensus_state1 =
+        check_misbehaviour_header(1, &me, &consensus_state1, &header_1, &clock)?;
+    let light_consensus_state2 =
+        check_misbehaviour_header(2, &me, &consensus_state2, &header_2, &clock)?;
+
+    // If both headers were signed by the same validator set, report the double signers. The
+    // seals have been already verified above and the reporting is best-effort, so that it
+    // never prevents the client from being frozen.
+    let mut offenders: Vec<String> = Vec::new();
+    let mut evidence_error: Option<String> = None;
+    if light_consensus_state1.validators.hash() == light_consensus_state2.validators.hash() {
+        match extract_double_sign(&light_consensus_state1.validators, &header_1, &header_2) {
+            Ok(evidence) => {
+                offenders = evidence
+                    .offenders
+                    .iter()
+                    .map(|address| address.to_string())
+                    .collect();
+            }
+            Err(e) => evidence_error = Some(e.to_string()),
+        }
+    }
 
     // Store the new state
     let mut new_client_state = me.clone();
@@ -641,12 +793,18 @@ pub fn check_misbehaviour(
         result: ClientStateCallResponseResult::success(),
     })?);
 
+    let mut attributes = vec![
+        attr("action", "verify_misbehaviour"),
+        attr("height", misbehaviour.header_1.height),
+        attr("offenders", offenders.join(",")),
+    ];
+    if let Some(e) = evidence_error {
+        attributes.push(attr("evidence_error", e));
+    }
+
     Ok(HandleResponse {
         messages: vec![],
-        attributes: vec![
-            attr("action", "verify_misbehaviour"),
-            attr("height", misbehaviour.header_1.height),
-        ],
+        attributes,
         data: Some(response_data),
     })
 }
@@ -656,16 +814,17 @@ pub fn check_misbehaviour_header(
     me: &ClientState,
     consensus_state: &ConsensusState,
     header: &Header,
-) -> Result<(), StdError> {
+    clock: &dyn Clock,
+) -> Result<LightConsensusState, StdError> {
     // Unmarshal state entry
     let light_consensus_state: LightConsensusState =
-        from_base64_rlp(&consensus_state.data, "msg.light_consensus_state")?;
+        from_base64_rlp_or_json(&consensus_state.data, "msg.light_consensus_state")?;
 
     // Unmarshal state config
     let light_client_state: LightClientState = from_base64_rlp(&me.data, "msg.light_client_state")?;
 
     // Verify header
-    let state: State = State::new(light_consensus_state, &light_client_state);
+    let state: State = State::new(light_consensus_state, &light_client_state, clock);
     match state.verify_header_seal(&header) {
         Err(e) => {
             return Err(StdError::generic_err(format!(
@@ -673,24 +832,114 @@ pub fn check_misbehaviour_header(
                 num, e
             )))
         }
-        _ => return Ok(()),
+        _ => return Ok(state.snapshot().clone()),
     }
 }
 
+pub fn challenge_optimistic_update(
+    deps: DepsMut,
+    env: Env,
+    me: ClientState,
+    consensus_state: ConsensusState,
+    wasm_header: WasmHeader,
+) -> Result<HandleResponse, StdError> {
+    let clock = FixedClock(block_timestamp(&env));
+
+    // Only the provisional consensus states can be challenged
+    let mut update = match get_provisional_update(deps.storage, wasm_header.height)? {
+        Some(update) => update,
+        None => {
+            return Err(StdError::generic_err(format!(
+                "No optimistic update at height {}",
+                wasm_header.height
+            )))
+        }
+    };
+    if update.reverted {
+        return Err(StdError::generic_err(
+            "Optimistic update has been already reverted",
+        ));
+    }
+    if Timestamp::from_nanos(update.challenge_deadline) <= clock.now() {
+        return Err(StdError::generic_err(
+            "Challenge period of the optimistic update has passed",
+        ));
+    }
+
+    // Unmarshal header
+    let header: Header = from_base64_rlp_or_json_strict(&wasm_header.data, "msg.header")?;
+
+    // Unmarshal state entry
+    let light_consensus_state: LightConsensusState =
+        from_base64_rlp_or_json(&consensus_state.data, "msg.light_consensus_state")?;
+
+    // Unmarshal state config
+    let light_client_state: LightClientState = from_base64_rlp(&me.data, "msg.light_client_state")?;
+
+    // The header must conflict with the optimistically accepted one...
+    let header_hash = header.hash().map_err(to_generic_err)?;
+    if header.number_u64().map_err(to_generic_err)? != update.number
+        || header_hash == update.header_hash
+    {
+        return Err(StdError::generic_err(
+            "Header doesn't conflict with the optimistic update",
+        ));
+    }
+
+    // ...and be sealed by the validators the optimistic update was expected to be sealed by
+    if light_consensus_state.validators.hash() != update.validators_hash {
+        return Err(StdError::generic_err(
+            "Consensus state doesn't match the optimistic update",
+        ));
+    }
+    let state: State = State::new(light_consensus_state, &light_client_state, &clock);
+    match state.verify_header_seal(&header) {
+        Err(e) => {
+            return Err(StdError::generic_err(format!(
+                "Failed to verify the conflicting header. Error: {}",
+                e
+            )))
+        }
+        _ => {}
+    }
+
+    // Revert the update and freeze the client
+    update.reverted = true;
+    set_provisional_update(deps.storage, wasm_header.height, &update)?;
+
+    let mut new_client_state = me.clone();
+    new_client_state.frozen = true;
+    new_client_state.frozen_height = Some(wasm_header.height);
+
+    let response_data = Binary(to_vec(&CheckMisbehaviourAndUpdateStateResult {
+        new_client_state,
+        result: ClientStateCallResponseResult::success(),
+    })?);
+
+    Ok(HandleResponse {
+        messages: vec![],
+        attributes: vec![
+            attr("action", "challenge_optimistic_update"),
+            attr("height", wasm_header.height),
+            attr("header_hash", header_hash.to_string()),
+        ],
+        data: Some(response_data),
+    })
+}
+
 pub fn verify_client_state(
-    _deps: DepsMut,
-    _env: Env,
-    _me: ClientState,
-    _height: Height,
+    deps: DepsMut,
+    env: Env,
+    me: ClientState,
+    height: Height,
     commitment_prefix: MerklePrefix,
     counterparty_client_identifier: String,
     proof: String,
     counterparty_client_state: CosmosClientState,
     proving_consensus_state: ConsensusState,
 ) -> Result<HandleResponse, StdError> {
-    // Unmarshal proof
-    let proof: MerkleProof = from_base64_json_slice(&proof, "msg.proof")?;
-    let specs = vec![ics23::iavl_spec(), ics23::tendermint_spec()];
+    // Ensure the consensus state is not provisional
+    verify_consensus_state_final(deps.storage, height, &env)?;
 
     // Get root from proving (celo) consensus state
     let root: Vec<u8> = from_base64(
@@ -699,20 +948,13 @@ pub fn verify_client_state(
     )?;
 
     // Build path (proof is used to validate the existance of value under that path)
-    let client_prefixed_path = IcsPath::ClientState(
+    let client_path = IcsPath::ClientState(
         ClientId::from_str(&counterparty_client_identifier).map_err(to_generic_err)?,
-    )
-    .to_string();
+    );
 
     // Verify proof against key-value pair
-    let path = apply_prefix(&commitment_prefix, vec![client_prefixed_path])?;
     let value: Vec<u8> = to_vec(&counterparty_client_state)?;
-
-    if !verify_membership(&proof, &specs, &root, &path, value, 0)? {
-        return Err(StdError::generic_err(
-            "proof membership verification failed (invalid proof)",
-        ));
-    }
+    verify_path_membership(&me, &commitment_prefix, &proof, &root, client_path, value)?;
 
     // Build up the response
     wrap_response(
@@ -724,10 +966,10 @@ pub fn verify_client_state(
 }
 
 pub fn verify_client_consensus_state(
-    _deps: DepsMut,
-    _env: Env,
-    _me: ClientState,
-    _height: Height,
+    deps: DepsMut,
+    env: Env,
+    me: ClientState,
+    height: Height,
     consensus_height: Height,
     commitment_prefix: MerklePrefix,
     counterparty_client_identifier: String,
@@ -735,9 +977,8 @@ pub fn verify_client_consensus_state(
     counterparty_consensus_state: CosmosConsensusState,
     proving_consensus_state: ConsensusState,
 ) -> Result<HandleResponse, StdError> {
-    // Unmarshal proof
-    let proof: MerkleProof = from_base64_json_slice(&proof, "msg.proof")?;
-    let specs = vec![ics23::iavl_spec(), ics23::tendermint_spec()];
+    // Ensure the consensus state is not provisional
+    verify_consensus_state_final(deps.storage, height, &env)?;
 
     // Get root from proving (celo) consensus state
     let root: Vec<u8> = from_base64(
@@ -746,22 +987,15 @@ pub fn verify_client_consensus_state(
     )?;
 
     // Build path (proof is used to validate the existance of value under that path)
-    let client_prefixed_path = IcsPath::ClientConsensusState {
+    let consensus_path = IcsPath::ClientConsensusState {
         client_id: ClientId::from_str(&counterparty_client_identifier).map_err(to_generic_err)?,
         epoch: consensus_height.revision_number,
         height: consensus_height.revision_height,
-    }
-    .to_string();
+    };
 
     // Verify proof against key-value pair
-    let path = apply_prefix(&commitment_prefix, vec![client_prefixed_path])?;
     let value: Vec<u8> = to_vec(&counterparty_consensus_state)?;
-
-    if !verify_membership(&proof, &specs, &root, &path, value, 0)? {
-        return Err(StdError::generic_err(
-            "proof membership verification failed (invalid proof)",
-        ));
-    }
+    verify_path_membership(&me, &commitment_prefix, &proof, &root, consensus_path, value)?;
 
     // Build up the response
     wrap_response(
@@ -773,19 +1007,18 @@ pub fn verify_client_consensus_state(
 }
 
 pub fn verify_connection_state(
-    _deps: DepsMut,
-    _env: Env,
-    _me: ClientState,
-    _height: Height,
+    deps: DepsMut,
+    env: Env,
+    me: ClientState,
+    height: Height,
     commitment_prefix: MerklePrefix,
     proof: String,
     connection_id: String,
     connection_end: ConnectionEnd,
     consensus_state: ConsensusState,
 ) -> Result<HandleResponse, StdError> {
-    // Unmarshal proof
-    let proof: MerkleProof = from_base64_json_slice(&proof, "msg.proof")?;
-    let specs = vec![ics23::iavl_spec(), ics23::tendermint_spec()];
+    // Ensure the consensus state is not provisional
+    verify_consensus_state_final(deps.storage, height, &env)?;
 
     // Get root from proving (celo) consensus state
     let root: Vec<u8> = from_base64(
@@ -795,18 +1028,11 @@ pub fn verify_connection_state(
 
     // Build path (proof is used to validate the existance of value under that path)
     let connection_path =
-        IcsPath::Connections(ConnectionId::from_str(&connection_id).map_err(to_generic_err)?)
-            .to_string();
+        IcsPath::Connections(ConnectionId::from_str(&connection_id).map_err(to_generic_err)?);
 
     // Verify proof against key-value pair
-    let path = apply_prefix(&commitment_prefix, vec![connection_path])?;
     let value: Vec<u8> = to_vec(&connection_end)?;
-
-    if !verify_membership(&proof, &specs, &root, &path, value, 0)? {
-        return Err(StdError::generic_err(
-            "proof membership verification failed (invalid proof)",
-        ));
-    }
+    verify_path_membership(&me, &commitment_prefix, &proof, &root, connection_path, value)?;
 
     // Build up the response
     wrap_response(
@@ -818,10 +1044,10 @@ pub fn verify_connection_state(
 }
 
 pub fn verify_channel_state(
-    _deps: DepsMut,
-    _env: Env,
-    _me: ClientState,
-    _height: Height,
+    deps: DepsMut,
+    env: Env,
+    me: ClientState,
+    height: Height,
     commitment_prefix: MerklePrefix,
     proof: String,
     port_id: String,
@@ -829,9 +1055,8 @@ pub fn verify_channel_state(
     channel: Channel,
     consensus_state: ConsensusState,
 ) -> Result<HandleResponse, StdError> {
-    // Unmarshal proof
-    let proof: MerkleProof = from_base64_json_slice(&proof, "msg.proof")?;
-    let specs = vec![ics23::iavl_spec(), ics23::tendermint_spec()];
+    // Ensure the consensus state is not provisional
+    verify_consensus_state_final(deps.storage, height, &env)?;
 
     // Get root from proving (celo) consensus state
     let root: Vec<u8> = from_base64(
@@ -843,18 +1068,11 @@ pub fn verify_channel_state(
     let channel_path = IcsPath::ChannelEnds(
         PortId::from_str(&port_id).map_err(to_generic_err)?,
         ChannelId::from_str(&channel_id).map_err(to_generic_err)?,
-    )
-    .to_string();
+    );
 
     // Verify proof against key-value pair
-    let path = apply_prefix(&commitment_prefix, vec![channel_path])?;
     let value: Vec<u8> = to_vec(&channel)?;
-
-    if !verify_membership(&proof, &specs, &root, &path, value, 0)? {
-        return Err(StdError::generic_err(
-            "proof membership verification failed (invalid proof)",
-        ));
-    }
+    verify_path_membership(&me, &commitment_prefix, &proof, &root, channel_path, value)?;
 
     // Build up the response
     wrap_response(
@@ -867,8 +1085,8 @@ pub fn verify_channel_state(
 
 pub fn verify_packet_commitment(
     deps: DepsMut,
-    _env: Env,
-    _me: ClientState,
+    env: Env,
+    me: ClientState,
     height: Height,
     commitment_prefix: MerklePrefix,
     proof: String,
@@ -880,9 +1098,8 @@ pub fn verify_packet_commitment(
     commitment_bytes: String,
     consensus_state: ConsensusState,
 ) -> Result<HandleResponse, StdError> {
-    // Unmarshal proof
-    let proof: MerkleProof = from_base64_json_slice(&proof, "msg.proof")?;
-    let specs = vec![ics23::iavl_spec(), ics23::tendermint_spec()];
+    // Ensure the consensus state is not provisional
+    verify_consensus_state_final(deps.storage, height, &env)?;
 
     // Get root from proving (celo) consensus state
     let root: Vec<u8> = from_base64(&consensus_state.root.hash, "msg.consensus_state.root")?;
@@ -895,18 +1112,11 @@ pub fn verify_packet_commitment(
         port_id: PortId::from_str(&port_id).map_err(to_generic_err)?,
         channel_id: ChannelId::from_str(&channel_id).map_err(to_generic_err)?,
         sequence: Sequence::from(sequence),
-    }
-    .to_string();
+    };
 
     // Verify proof against key-value pair
-    let path = apply_prefix(&commitment_prefix, vec![commitment_path])?;
     let value: Vec<u8> = from_base64(&commitment_bytes, "msg.commitment_bytes")?;
-
-    if !verify_membership(&proof, &specs, &root, &path, value, 0)? {
-        return Err(StdError::generic_err(
-            "proof membership verification failed (invalid proof)",
-        ));
-    }
+    verify_path_membership(&me, &commitment_prefix, &proof, &root, commitment_path, value)?;
 
     // Build up the response
     wrap_response(
@@ -919,8 +1129,8 @@ pub fn verify_packet_commitment(
 
 pub fn verify_packet_acknowledgment(
     deps: DepsMut,
-    _env: Env,
-    _me: ClientState,
+    env: Env,
+    me: ClientState,
     height: Height,
     commitment_prefix: MerklePrefix,
     proof: String,
@@ -932,9 +1142,8 @@ pub fn verify_packet_acknowledgment(
     acknowledgement: String,
     consensus_state: ConsensusState,
 ) -> Result<HandleResponse, StdError> {
-    // Unmarshal proof
-    let proof: MerkleProof = from_base64_json_slice(&proof, "msg.proof")?;
-    let specs = vec![ics23::iavl_spec(), ics23::tendermint_spec()];
+    // Ensure the consensus state is not provisional
+    verify_consensus_state_final(deps.storage, height, &env)?;
 
     // Get root from proving (celo) consensus state
     let root: Vec<u8> = from_base64(&consensus_state.root.hash, "msg.consensus_state.root")?;
@@ -947,18 +1156,11 @@ pub fn verify_packet_acknowledgment(
         port_id: PortId::from_str(&port_id).map_err(to_generic_err)?,
         channel_id: ChannelId::from_str(&channel_id).map_err(to_generic_err)?,
         sequence: Sequence::from(sequence),
-    }
-    .to_string();
+    };
 
     // Verify proof against key-value pair
-    let path = apply_prefix(&commitment_prefix, vec![ack_path])?;
     let value: Vec<u8> = from_base64(&acknowledgement, "msg.acknowledgement")?;
-
-    if !verify_membership(&proof, &specs, &root, &path, value, 0)? {
-        return Err(StdError::generic_err(
-            "proof membership verification failed (invalid proof)",
-        ));
-    }
+    verify_path_membership(&me, &commitment_prefix, &proof, &root, ack_path, value)?;
 
     // Build up the response
     wrap_response(
@@ -971,8 +1173,8 @@ pub fn verify_packet_acknowledgment(
 
 pub fn verify_packet_receipt_absence(
     deps: DepsMut,
-    _env: Env,
-    _me: ClientState,
+    env: Env,
+    me: ClientState,
     height: Height,
     commitment_prefix: MerklePrefix,
     proof: String,
@@ -983,9 +1185,8 @@ pub fn verify_packet_receipt_absence(
     sequence: u64,
     consensus_state: ConsensusState,
 ) -> Result<HandleResponse, StdError> {
-    // Unmarshal proof
-    let proof: MerkleProof = from_base64_json_slice(&proof, "msg.proof")?;
-    let specs = vec![ics23::iavl_spec(), ics23::tendermint_spec()];
+    // Ensure the consensus state is not provisional
+    verify_consensus_state_final(deps.storage, height, &env)?;
 
     // Get root from proving (celo) consensus state
     let root: Vec<u8> = from_base64(&consensus_state.root.hash, "msg.consensus_state.root")?;
@@ -998,18 +1199,42 @@ pub fn verify_packet_receipt_absence(
         port_id: PortId::from_str(&port_id).map_err(to_generic_err)?,
         channel_id: ChannelId::from_str(&channel_id).map_err(to_generic_err)?,
         sequence: Sequence::from(sequence),
+    };
+
+    // Verify the IBC host storage proof of the empty slot (if the storage layout is set)
+    let light_client_state: LightClientState = from_base64_rlp(&me.data, "msg.light_client_state")?;
+    if let Some(layout) = light_client_state.ibc_storage_layout {
+        let proof: StorageProof = from_base64_json_slice(&proof, "msg.proof")?;
+        layout.verify(&root, &reciept_path, None, &proof)?;
+
+        return wrap_response(
+            &VerifyPacketReceiptAbsenceResult {
+                result: ClientStateCallResponseResult::success(),
+            },
+            "verify_packet_receipt_absence",
+        );
     }
-    .to_string();
+
+    // Unmarshal proof
+    let proof: MerkleProof = from_base64_json_slice(&proof, "msg.proof")?;
+    let specs = vec![ics23::iavl_spec(), ics23::tendermint_spec()];
 
     // Apply prefix
-    let path = apply_prefix(&commitment_prefix, vec![reciept_path])?;
+    let path = apply_prefix(&commitment_prefix, vec![reciept_path.to_string()])?;
 
     // Verify single proof against key-value pair
-    let key: &[u8] = path.key_path.last().unwrap().as_bytes();
+    let key: &[u8] = match path.key_path.last() {
+        Some(key) => key.as_bytes(),
+        None => return Err(StdError::generic_err("could not retrieve key bytes")),
+    };
+    let commitment_proof = match proof.proofs.first() {
+        Some(commitment_proof) => commitment_proof,
+        None => return Err(StdError::generic_err("proof is empty")),
+    };
 
     // Reference: cosmos-sdk/x/ibc/core/23-commitment/types/merkle.go
     // TODO: ics23-rs library doesn't seem to offer subroot calculation for non_exist
-    if !ics23::verify_non_membership(&proof.proofs[0], &specs[0], &root, key) {
+    if !ics23::verify_non_membership(commitment_proof, &specs[0], &root, key) {
         return Err(StdError::generic_err(
             "proof non membership verification failed (invalid proof)",
         ));
@@ -1026,8 +1251,8 @@ pub fn verify_packet_receipt_absence(
 
 pub fn verify_next_sequence_recv(
     deps: DepsMut,
-    _env: Env,
-    _me: ClientState,
+    env: Env,
+    me: ClientState,
     height: Height,
     commitment_prefix: MerklePrefix,
     proof: String,
@@ -1038,9 +1263,8 @@ pub fn verify_next_sequence_recv(
     next_sequence_recv: u64,
     consensus_state: ConsensusState,
 ) -> Result<HandleResponse, StdError> {
-    // Unmarshal proof
-    let proof: MerkleProof = from_base64_json_slice(&proof, "msg.proof")?;
-    let specs = vec![ics23::iavl_spec(), ics23::tendermint_spec()];
+    // Ensure the consensus state is not provisional
+    verify_consensus_state_final(deps.storage, height, &env)?;
 
     // Get root from proving (celo) consensus state
     let root: Vec<u8> = from_base64(&consensus_state.root.hash, "msg.consensus_state.root")?;
@@ -1052,18 +1276,18 @@ pub fn verify_next_sequence_recv(
     let next_sequence_recv_path = IcsPath::SeqRecvs(
         PortId::from_str(&port_id).map_err(to_generic_err)?,
         ChannelId::from_str(&channel_id).map_err(to_generic_err)?,
-    )
-    .to_string();
+    );
 
     // Verify proof against key-value pair
-    let path = apply_prefix(&commitment_prefix, vec![next_sequence_recv_path])?;
     let value: Vec<u8> = u64_to_big_endian(next_sequence_recv);
-
-    if !verify_membership(&proof, &specs, &root, &path, value, 0)? {
-        return Err(StdError::generic_err(
-            "proof membership verification failed (invalid proof)",
-        ));
-    }
+    verify_path_membership(
+        &me,
+        &commitment_prefix,
+        &proof,
+        &root,
+        next_sequence_recv_path,
+        value,
+    )?;
 
     // Build up the response
     wrap_response(
@@ -1074,6 +1298,112 @@ pub fn verify_next_sequence_recv(
     )
 }
 
+// get_consensus_root returns the root stored in the consensus state, that is the header state root
+// or the IBC host contract storage root (proven by the account proof attached to the header)
+fn get_consensus_root(
+    light_client_state: &LightClientState,
+    header: &Header,
+    wasm_header: &WasmHeader,
+) -> Result<Vec<u8>, StdError> {
+    match &light_client_state.ibc_storage_layout {
+        Some(layout) if layout.consensus_root == ConsensusRoot::HostStorageRoot => {
+            let account_proof = wasm_header
+                .account_proof
+                .iter()
+                .map(|node| from_base64(node, "msg.header.account_proof"))
+                .collect::<Result<Vec<Vec<u8>>, StdError>>()?;
+
+            Ok(layout
+                .host_storage_root(&header.root, &account_proof)?
+                .to_vec())
+        }
+        _ => Ok(header.root.to_vec()),
+    }
+}
+
+// get_proofs_of_possession returns the proofs-of-possession attached to the header, indexed by
+// the validator address
+fn get_proofs_of_possession(
+    wasm_header: &WasmHeader,
+) -> Result<HashMap<Address, Vec<u8>>, StdError> {
+    wasm_header
+        .proofs_of_possession
+        .iter()
+        .map(|proof| {
+            let address = from_base64(&proof.address, "msg.header.proofs_of_possession.address")?;
+            let address = Address::from_slice(&address).map_err(|e| {
+                StdError::parse_err("msg.header.proofs_of_possession.address", e.to_string())
+            })?;
+            let signature =
+                from_base64(&proof.signature, "msg.header.proofs_of_possession.signature")?;
+
+            Ok((address, signature))
+        })
+        .collect()
+}
+
+// verify_path_membership verifies the proof of the value stored under the path. The proof is either
+// the account/storage proof of the IBC host contract (if the storage layout is set in the client
+// state) or the ICS-23 MerkleProof.
+fn verify_path_membership(
+    me: &ClientState,
+    commitment_prefix: &MerklePrefix,
+    proof: &String,
+    root: &Vec<u8>,
+    ics_path: IcsPath,
+    value: Vec<u8>,
+) -> Result<(), StdError> {
+    let light_client_state: LightClientState = from_base64_rlp(&me.data, "msg.light_client_state")?;
+
+    if let Some(layout) = light_client_state.ibc_storage_layout {
+        let proof: StorageProof = from_base64_json_slice(proof, "msg.proof")?;
+
+        return layout.verify(root, &ics_path, Some(value.as_slice()), &proof);
+    }
+
+    let proof: MerkleProof = from_base64_json_slice(proof, "msg.proof")?;
+    let specs = vec![ics23::iavl_spec(), ics23::tendermint_spec()];
+    let path = apply_prefix(commitment_prefix, vec![ics_path.to_string()])?;
+
+    if !verify_membership(&proof, &specs, root, &path, value, 0)? {
+        return Err(StdError::generic_err(
+            "proof membership verification failed (invalid proof)",
+        ));
+    }
+
+    Ok(())
+}
+
+// verify_consensus_state_final will ensure that the consensus state wasn't created by an optimistic update,
+// unless its challenge period has passed without the update being reverted
+fn verify_consensus_state_final(
+    storage: &dyn Storage,
+    height: Height,
+    env: &Env,
+) -> Result<(), StdError> {
+    let update = match get_provisional_update(storage, height)? {
+        Some(update) => update,
+        None => return Ok(()),
+    };
+
+    if update.reverted {
+        return Err(StdError::generic_err(
+            "consensus state has been reverted by a conflicting header",
+        ));
+    }
+
+    let challenge_deadline = Timestamp::from_nanos(update.challenge_deadline);
+    let current_timestamp = block_timestamp(env);
+    if challenge_deadline > current_timestamp {
+        return Err(StdError::generic_err(format!(
+            "consensus state is provisional until time: {}, current time: {}",
+            challenge_deadline, current_timestamp
+        )));
+    }
+
+    Ok(())
+}
+
 // verify_delay_period_passed will ensure that at least delayPeriod amount of time has passed since consensus state was submitted
 // before allowing verification to continue
 fn verify_delay_period_passed(
@@ -1082,8 +1412,17 @@ fn verify_delay_period_passed(
     current_timestamp: u64,
     delay_period: u64,
 ) -> Result<(), StdError> {
+    // NOTE: IBC passes the current time and the delay period in nanoseconds
+    let current_timestamp = Timestamp::from_nanos(current_timestamp);
     let processed_time = get_processed_time(deps.storage, proof_height)?;
-    let valid_time = processed_time + delay_period;
+    let valid_time = match processed_time.checked_add(Duration::from_nanos(delay_period)) {
+        Some(valid_time) => valid_time,
+        None => {
+            return Err(StdError::generic_err(
+                "delay period overflows processed time",
+            ))
+        }
+    };
 
     if valid_time > current_timestamp {
         return Err(StdError::generic_err(format!(
@@ -1095,6 +1434,15 @@ fn verify_delay_period_passed(
     Ok(())
 }
 
+fn get_latest_height(me: &ClientState) -> Result<Height, StdError> {
+    match me.latest_height {
+        Some(height) => Ok(height),
+        None => Err(StdError::generic_err(
+            "client state is missing latest height",
+        )),
+    }
+}
+
 fn construct_upgrade_merkle_path(
     upgrade_path: &Vec<String>,
     client_upgrade_path: ibc::ics24_host::ClientUpgradePath,
@@ -1110,12 +1458,37 @@ fn construct_upgrade_merkle_path(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::contract::types::ibc::MerklePrefix;
-    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use crate::bls::test_utils::{generate_validators, sign_aggregated_seal};
+    use crate::contract::types::ibc::{Counterparty, MerklePrefix};
+    use crate::contract::types::state::LIGHT_CLIENT_STATE_ENCODING_VERSION;
+    use crate::contract::types::storage::{IbcStorageLayout, PathKeyEncoding};
+    use crate::contract::types::wasm::ProofOfPossession;
+    use crate::serialization::rlp::rlp_begin_versioned;
+    use crate::traits::{FromRlp, ToJson};
+    use crate::trie::{keccak, Trie};
+    use crate::types::bitmap::ValidatorBitmap;
+    use crate::types::header::Hash;
+    use crate::types::istanbul::{IstanbulAggregatedSeal, IstanbulExtra, IstanbulExtraVanity};
+    use crate::types::test_utils::arb_address;
+    use bls_crypto::PrivateKey;
+    use crate::contract::store::legacy_processed_time_key;
+    use cosmwasm_std::from_binary;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_storage::singleton;
     use ics23::{
         calculate_existence_root, CommitmentProof, ExistenceProof, HashOp, InnerOp, LeafOp,
         LengthOp,
     };
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    // LightClientState encoded before the versioning was introduced
+    const LIGHT_CLIENT_STATE_UNVERSIONED: &str =
+        "e48243800580d9877570677261646590757067726164656449424353746174650101018080";
+    const LIGHT_CLIENT_STATE_UNVERSIONED_WITH_LAYOUT: &str = "f8448243800580d9877570677261646590757067726164656449424353746174650101018080df941b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1b01808001020304050607";
+    // LightClientState encoded before the challenge period was introduced
+    const LIGHT_CLIENT_STATE_V1: &str =
+        "e701e58243800580d9877570677261646590757067726164656449424353746174650101018080c0";
 
     #[test]
     fn test_verify_client_consensus_state() {
@@ -1166,9 +1539,714 @@ mod tests {
         assert_eq!(response.is_err(), false);
     }
 
+    #[test]
+    fn test_init_without_latest_height() {
+        let mut deps = mock_dependencies(&[]);
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+
+        let mut client_state = get_example_client_state(0, 5);
+        client_state.latest_height = None;
+        let consensus_state = get_example_consenus_state(vec![], new_height(0, 5));
+
+        let response = init_contract(deps.as_mut(), env, info, consensus_state, client_state);
+
+        assert_eq!(response.is_err(), true);
+    }
+
+    #[test]
+    fn test_verify_membership_without_spec() {
+        let (proof, root) = get_example_proof(b"key".to_vec(), b"value".to_vec());
+        let path = MerklePath {
+            key_path: vec![String::from("key")],
+        };
+
+        let response = verify_membership(&proof, &[], &root, &path, b"value".to_vec(), 0);
+
+        assert_eq!(response.is_err(), true);
+    }
+
+    #[test]
+    fn test_verify_membership_without_key() {
+        let specs = vec![ics23::iavl_spec(), ics23::tendermint_spec()];
+        let (proof, root) = get_example_proof(b"key".to_vec(), b"value".to_vec());
+        let path = MerklePath { key_path: vec![] };
+
+        let response = verify_membership(&proof, &specs, &root, &path, b"value".to_vec(), 0);
+
+        assert_eq!(response.is_err(), true);
+    }
+
+    #[test]
+    fn test_verify_packet_receipt_absence_with_empty_proof() {
+        let mut deps = mock_dependencies(&[]);
+        let env = mock_env();
+        let height = new_height(0, 5);
+
+        set_processed_time(deps.as_mut().storage, height, Timestamp::from_nanos(0)).unwrap();
+
+        let response = verify_packet_receipt_absence(
+            deps.as_mut(),
+            env,
+            get_example_client_state(0, 5),
+            height,
+            MerklePrefix {
+                key_prefix: base64::encode("prefix"),
+            },
+            base64::encode(to_vec(&MerkleProof { proofs: vec![] }).unwrap()),
+            String::from("transfer"),
+            String::from("channel-0"),
+            0,
+            0,
+            1,
+            get_example_consenus_state(vec![], height),
+        );
+
+        assert_eq!(response.is_err(), true);
+    }
+
+    #[test]
+    fn test_verify_delay_period_overflow() {
+        let mut deps = mock_dependencies(&[]);
+        let height = new_height(0, 5);
+
+        set_processed_time(deps.as_mut().storage, height, Timestamp::from_nanos(1)).unwrap();
+
+        let response = verify_delay_period_passed(deps.as_mut(), height, u64::MAX, u64::MAX);
+
+        assert_eq!(response.is_err(), true);
+    }
+
+    #[test]
+    fn test_legacy_processed_time_in_seconds() {
+        let mut deps = mock_dependencies(&[]);
+        let height = new_height(0, 5);
+
+        // processed time stored (in seconds) before the upgrade
+        let processed_secs: u64 = 1_600_000_000;
+        singleton(deps.as_mut().storage, &legacy_processed_time_key(height))
+            .save(&processed_secs)
+            .unwrap();
+        assert_eq!(
+            get_processed_time(deps.as_ref().storage, height).unwrap(),
+            Timestamp::from_secs(processed_secs)
+        );
+
+        let now = Timestamp::from_secs(processed_secs + 5).as_nanos().unwrap();
+        let delay_period = |secs: u64| Duration::from_secs(secs).as_nanos() as u64;
+        assert!(verify_delay_period_passed(deps.as_mut(), height, now, delay_period(10)).is_err());
+        assert!(verify_delay_period_passed(deps.as_mut(), height, now, delay_period(5)).is_ok());
+
+        let response: ProcessedTimeResponse = from_binary(
+            &query(deps.as_ref(), mock_env(), QueryMsg::ProcessedTime { height }).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(response.time, processed_secs);
+        assert_eq!(response.time_nanos, processed_secs * 1_000_000_000);
+
+        // the processed time stored after the upgrade takes precedence
+        set_processed_time(deps.as_mut().storage, height, Timestamp::from_nanos(1)).unwrap();
+        assert_eq!(
+            get_processed_time(deps.as_ref().storage, height).unwrap(),
+            Timestamp::from_nanos(1)
+        );
+    }
+
+    #[test]
+    fn test_verify_channel_state_with_storage_proof() {
+        let mut deps = mock_dependencies(&[]);
+        let height = new_height(0, 5);
+
+        let layout = get_example_storage_layout(ConsensusRoot::StateRoot);
+        let client_state = get_example_client_state_with_layout(&layout);
+        let (channel, state_trie, storage_trie) = get_example_host_state(&layout);
+
+        let slot = keccak(&layout.slot(&get_example_channel_path()).unwrap());
+        let proof = StorageProof {
+            account_proof: state_trie.proof(&keccak(&layout.host_address)),
+            storage_proof: storage_trie.proof(&slot),
+        };
+
+        let verify = |deps: DepsMut, channel_id: &str| {
+            verify_channel_state(
+                deps,
+                mock_env(),
+                client_state.clone(),
+                height,
+                MerklePrefix {
+                    key_prefix: base64::encode("prefix"),
+                },
+                base64::encode(to_vec(&proof).unwrap()),
+                String::from("transfer"),
+                String::from(channel_id),
+                channel.clone(),
+                get_example_consenus_state(state_trie.root_hash().to_vec(), height),
+            )
+        };
+
+        assert_eq!(verify(deps.as_mut(), "channel-0").is_err(), false);
+        assert_eq!(verify(deps.as_mut(), "channel-1").is_err(), true);
+    }
+
+    #[test]
+    fn test_verify_channel_state_with_host_storage_root() {
+        let mut deps = mock_dependencies(&[]);
+        let height = new_height(0, 5);
+
+        let layout = get_example_storage_layout(ConsensusRoot::HostStorageRoot);
+        let client_state = get_example_client_state_with_layout(&layout);
+        let light_client_state: LightClientState =
+            from_base64_rlp(&client_state.data, "light_client_state").unwrap();
+        let (channel, state_trie, storage_trie) = get_example_host_state(&layout);
+
+        // The update proves the host account, so that the storage root is stored as the root
+        let mut header = Header::new();
+        header.root = state_trie.root_hash();
+        let mut wasm_header = WasmHeader {
+            data: String::from(""),
+            height,
+            r#type: String::from("header"),
+            account_proof: state_trie
+                .proof(&keccak(&layout.host_address))
+                .iter()
+                .map(base64::encode)
+                .collect(),
+            proofs_of_possession: Vec::new(),
+        };
+
+        let root = get_consensus_root(&light_client_state, &header, &wasm_header).unwrap();
+        assert_eq!(root, storage_trie.root_hash().to_vec());
+
+        // Only the storage proof is needed to verify the path
+        let slot = keccak(&layout.slot(&get_example_channel_path()).unwrap());
+        let proof = StorageProof {
+            account_proof: vec![],
+            storage_proof: storage_trie.proof(&slot),
+        };
+
+        let response = verify_channel_state(
+            deps.as_mut(),
+            mock_env(),
+            client_state,
+            height,
+            MerklePrefix {
+                key_prefix: base64::encode("prefix"),
+            },
+            base64::encode(to_vec(&proof).unwrap()),
+            String::from("transfer"),
+            String::from("channel-0"),
+            channel,
+            get_example_consenus_state(root, height),
+        );
+        assert_eq!(response.is_err(), false);
+
+        // Missing account proof
+        wasm_header.account_proof = vec![];
+        assert_eq!(
+            get_consensus_root(&light_client_state, &header, &wasm_header).is_err(),
+            true
+        );
+    }
+
+    #[test]
+    fn test_optimistic_update_and_challenge() {
+        let mut deps = mock_dependencies(&[]);
+        let (keys, validators) = generate_validators(3);
+
+        let mut light_client_state = get_example_light_client_state();
+        light_client_state.verify_header_timestamp = false;
+        light_client_state.challenge_period = 3600;
+        let mut client_state = get_example_client_state(0, 5);
+        client_state.data = base64::encode(light_client_state.to_rlp());
+
+        let mut light_cs = LightConsensusState::new();
+        light_cs.number = 5;
+        light_cs.validators = validators.into();
+        let mut consensus_state = get_example_consenus_state(vec![], new_height(0, 5));
+        consensus_state.data = base64::encode(light_cs.to_rlp());
+
+        // The header is accepted without the seal
+        let height = new_height(0, 6);
+        let header = get_example_header(6, Hash::from([1; 32]), &[]);
+        let env = mock_env();
+        let response = check_header_and_update_state(
+            deps.as_mut(),
+            env.clone(),
+            client_state.clone(),
+            consensus_state.clone(),
+            get_example_wasm_header(&header, height),
+            UpdateMode::Regular,
+        );
+        assert_eq!(response.is_err(), false);
+
+        let update = get_provisional_update(deps.as_ref().storage, height)
+            .unwrap()
+            .unwrap();
+        assert_eq!(update.header_hash, header.hash().unwrap());
+        assert_eq!(update.validators_hash, light_cs.validators.hash());
+
+        // The consensus state can't be used until the challenge period has passed
+        let mut later_env = mock_env();
+        later_env.block.time += 3600;
+        assert_eq!(
+            verify_consensus_state_final(deps.as_ref().storage, height, &env).is_err(),
+            true
+        );
+        assert_eq!(
+            verify_consensus_state_final(deps.as_ref().storage, height, &later_env).is_err(),
+            false
+        );
+
+        let challenge = |deps: DepsMut, env: Env, header: &Header| {
+            challenge_optimistic_update(
+                deps,
+                env,
+                client_state.clone(),
+                consensus_state.clone(),
+                get_example_wasm_header(header, height),
+            )
+        };
+
+        // Neither the same header nor the unsealed one can revert the update
+        let unsealed = get_example_header(6, Hash::from([2; 32]), &[]);
+        assert_eq!(challenge(deps.as_mut(), env.clone(), &header).is_err(), true);
+        assert_eq!(challenge(deps.as_mut(), env.clone(), &unsealed).is_err(), true);
+
+        // The conflicting header must be submitted within the challenge period
+        let conflicting = get_example_header(6, Hash::from([2; 32]), &keys);
+        assert_eq!(
+            challenge(deps.as_mut(), later_env.clone(), &conflicting).is_err(),
+            true
+        );
+
+        let response = challenge(deps.as_mut(), env.clone(), &conflicting).unwrap();
+        let result: CheckMisbehaviourAndUpdateStateResult =
+            cosmwasm_std::from_binary(&response.data.unwrap()).unwrap();
+        assert_eq!(result.new_client_state.frozen, true);
+        assert_eq!(result.new_client_state.frozen_height, Some(height));
+
+        // The reverted consensus state can't be used anymore
+        assert_eq!(
+            verify_consensus_state_final(deps.as_ref().storage, height, &later_env).is_err(),
+            true
+        );
+        assert_eq!(challenge(deps.as_mut(), env, &conflicting).is_err(), true);
+    }
+
+    #[test]
+    fn test_optimistic_update_restrictions() {
+        let mut deps = mock_dependencies(&[]);
+        let (_, validators) = generate_validators(3);
+
+        let mut light_client_state = get_example_light_client_state();
+        light_client_state.verify_header_timestamp = false;
+        light_client_state.challenge_period = 3600;
+
+        let update = |deps: DepsMut, env: Env, trusted: u64, number: u64| {
+            let mut client_state = get_example_client_state(0, trusted);
+            client_state.data = base64::encode(light_client_state.to_rlp());
+
+            let mut light_cs = LightConsensusState::new();
+            light_cs.number = trusted;
+            light_cs.validators = validators.clone().into();
+            let mut consensus_state = get_example_consenus_state(vec![], new_height(0, trusted));
+            consensus_state.data = base64::encode(light_cs.to_rlp());
+
+            let header = get_example_header(number, Hash::from([1; 32]), &[]);
+            check_header_and_update_state(
+                deps,
+                env,
+                client_state,
+                consensus_state,
+                get_example_wasm_header(&header, new_height(0, number)),
+                UpdateMode::Regular,
+            )
+        };
+
+        // The header can't be beyond the epoch the trusted validator set is known to seal
+        let env = mock_env();
+        assert_eq!(update(deps.as_mut(), env.clone(), 5, 17281).is_err(), true);
+        assert_eq!(
+            update(deps.as_mut(), env.clone(), 17280, 34561).is_err(),
+            true
+        );
+        assert_eq!(update(deps.as_mut(), env.clone(), 5, 17280).is_err(), false);
+
+        // The provisional consensus state can't be updated until its challenge period has passed
+        let mut later_env = mock_env();
+        later_env.block.time += 3600;
+        assert_eq!(update(deps.as_mut(), env, 17280, 17281).is_err(), true);
+        assert_eq!(
+            update(deps.as_mut(), later_env, 17280, 17281).is_err(),
+            false
+        );
+    }
+
+    #[test]
+    fn test_expired_client_updates() {
+        let mut deps = mock_dependencies(&[]);
+        let env = mock_env();
+        let (keys, validators) = generate_validators(3);
+
+        let mut light_client_state = get_example_light_client_state();
+        light_client_state.verify_header_timestamp = false;
+        light_client_state.trusting_period = 100;
+        let client_state = |light_client_state: &LightClientState| {
+            let mut client_state = get_example_client_state(0, 5);
+            client_state.data = base64::encode(light_client_state.to_rlp());
+            client_state
+        };
+
+        // The latest trusted header is older than the trusting period
+        let mut light_cs = LightConsensusState::new();
+        light_cs.number = 5;
+        light_cs.timestamp = env.block.time - 1000;
+        light_cs.validators = validators.into();
+        let mut consensus_state = get_example_consenus_state(vec![], new_height(0, 5));
+        consensus_state.data = base64::encode(light_cs.to_rlp());
+
+        let header = get_example_header(6, Hash::from([1; 32]), &keys);
+        let wasm_header = get_example_wasm_header(&header, new_height(0, 6));
+
+        // The regular update is rejected...
+        let err = check_header_and_update_state(
+            deps.as_mut(),
+            env.clone(),
+            client_state(&light_client_state),
+            consensus_state.clone(),
+            wasm_header.clone(),
+            UpdateMode::Regular,
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string().contains("state has expired"), true);
+
+        // ...as well as the proposal, unless the config allows reviving the expired client
+        assert_eq!(
+            check_proposed_header(
+                deps.as_mut(),
+                env.clone(),
+                client_state(&light_client_state),
+                consensus_state.clone(),
+                wasm_header.clone(),
+            )
+            .is_err(),
+            true
+        );
+
+        light_client_state.allow_update_after_expiry = true;
+        let response = check_proposed_header(
+            deps.as_mut(),
+            env.clone(),
+            client_state(&light_client_state),
+            consensus_state.clone(),
+            wasm_header,
+        )
+        .unwrap();
+        let result: CheckHeaderAndUpdateStateResult = from_binary(&response.data.unwrap()).unwrap();
+        let updated: LightConsensusState =
+            from_base64_rlp(&result.new_consensus_state.data, "new_consensus_state").unwrap();
+        assert_eq!(updated.number, 6);
+
+        // The expired client can't be upgraded, while the unexpired one gets to the proofs
+        let (proof, root) = get_example_proof(b"key".to_vec(), b"value".to_vec());
+        let proof = base64::encode(to_vec(&proof).unwrap());
+        let mut last_height_consensus_state = consensus_state;
+        last_height_consensus_state.root = MerkleRoot {
+            hash: base64::encode(root),
+        };
+
+        let err = verify_upgrade_and_update_state(
+            deps.as_mut(),
+            env.clone(),
+            client_state(&light_client_state),
+            get_example_client_state(0, 6),
+            get_example_consenus_state(vec![], new_height(0, 6)),
+            proof.clone(),
+            proof.clone(),
+            last_height_consensus_state.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string().contains("cannot upgrade an expired client"),
+            true
+        );
+
+        light_cs.timestamp = env.block.time;
+        last_height_consensus_state.data = base64::encode(light_cs.to_rlp());
+        let err = verify_upgrade_and_update_state(
+            deps.as_mut(),
+            env,
+            client_state(&light_client_state),
+            get_example_client_state(0, 6),
+            get_example_consenus_state(vec![], new_height(0, 6)),
+            proof.clone(),
+            proof,
+            last_height_consensus_state,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string().contains("cannot upgrade an expired client"),
+            false
+        );
+    }
+
+    #[test]
+    fn test_light_client_state_rlp_compatibility() {
+        let mut light_client_state = get_example_light_client_state();
+
+        // states encoded before the versioning was introduced
+        let legacy = hex::decode(LIGHT_CLIENT_STATE_UNVERSIONED).unwrap();
+        assert_eq!(LightClientState::from_rlp(&legacy).unwrap(), light_client_state);
+
+        let v1 = hex::decode(LIGHT_CLIENT_STATE_V1).unwrap();
+        assert_eq!(LightClientState::from_rlp(&v1).unwrap(), light_client_state);
+
+        let encoded = light_client_state.to_rlp();
+        let rlp = rlp::Rlp::new(&encoded);
+        assert_eq!(rlp.val_at::<u64>(0).unwrap(), LIGHT_CLIENT_STATE_ENCODING_VERSION);
+        assert_eq!(rlp.at(1).unwrap().item_count().unwrap(), 11);
+        assert_eq!(LightClientState::from_rlp(&encoded).unwrap(), light_client_state);
+
+        light_client_state.ibc_storage_layout =
+            Some(get_example_storage_layout(ConsensusRoot::StateRoot));
+        let legacy = hex::decode(LIGHT_CLIENT_STATE_UNVERSIONED_WITH_LAYOUT).unwrap();
+        assert_eq!(LightClientState::from_rlp(&legacy).unwrap(), light_client_state);
+
+        light_client_state.ibc_storage_layout =
+            Some(get_example_storage_layout(ConsensusRoot::HostStorageRoot));
+        light_client_state.challenge_period = 3600;
+        let encoded = light_client_state.to_rlp();
+        assert_eq!(LightClientState::from_rlp(&encoded).unwrap(), light_client_state);
+
+        // unknown versions are rejected
+        let mut stream = rlp::RlpStream::new();
+        rlp_begin_versioned(&mut stream, LIGHT_CLIENT_STATE_ENCODING_VERSION + 1, 0);
+        assert_eq!(LightClientState::from_rlp(&stream.out()).is_err(), true);
+    }
+
+    #[test]
+    fn test_decode_header_from_rlp_or_json() {
+        let mut header = Header::new();
+        header.number = 5.into();
+        header.gas_used = 21000;
+
+        let from_rlp: Header =
+            from_base64_rlp_or_json(&base64::encode(header.to_rlp()), "header").unwrap();
+        let from_json: Header =
+            from_base64_rlp_or_json(&base64::encode(header.to_json()), "header").unwrap();
+
+        assert_eq!(from_rlp, header);
+        assert_eq!(from_json, header);
+        assert_eq!(
+            from_base64_rlp_or_json::<Header, _>(&base64::encode("{}"), "header").is_err(),
+            true
+        );
+    }
+
+    #[test]
+    fn test_decode_relayer_header_strictly() {
+        let mut header = Header::new();
+        header.number = 5.into();
+
+        let encoded = header.to_rlp();
+        let trailing = [&encoded[..], &[0x80]].concat();
+
+        let decoded: Header =
+            from_base64_rlp_or_json_strict(&base64::encode(&encoded), "header").unwrap();
+        assert_eq!(decoded, header);
+        assert_eq!(
+            from_base64_rlp_or_json::<Header, _>(&base64::encode(&trailing), "header").unwrap(),
+            header
+        );
+        assert_eq!(
+            from_base64_rlp_or_json_strict::<Header, _>(&base64::encode(&trailing), "header")
+                .is_err(),
+            true
+        );
+
+        // the JSON numbers must be canonical as well
+        let json = header.to_json();
+        let padded = json.replace("\"number\":\"0x5\"", "\"number\":\"0x05\"");
+        assert_ne!(padded, json);
+        assert_eq!(
+            from_base64_rlp_or_json_strict::<Header, _>(&base64::encode(&json), "header").unwrap(),
+            header
+        );
+        assert_eq!(
+            from_base64_rlp_or_json_strict::<Header, _>(&base64::encode(&padded), "header")
+                .is_err(),
+            true
+        );
+    }
+
+    fn arb_storage_layout() -> impl Strategy<Value = IbcStorageLayout> {
+        (
+            arb_address(),
+            prop_oneof![Just(PathKeyEncoding::String), Just(PathKeyEncoding::Keccak)],
+            prop_oneof![Just(ConsensusRoot::StateRoot), Just(ConsensusRoot::HostStorageRoot)],
+            vec(any::<u64>(), 8),
+        )
+            .prop_map(|(host_address, key_encoding, consensus_root, slots)| IbcStorageLayout {
+                host_address,
+                key_encoding,
+                consensus_root,
+                client_state_slot: slots[0],
+                consensus_state_slot: slots[1],
+                connection_slot: slots[2],
+                channel_slot: slots[3],
+                packet_commitment_slot: slots[4],
+                packet_ack_slot: slots[5],
+                packet_receipt_slot: slots[6],
+                next_sequence_recv_slot: slots[7],
+            })
+    }
+
+    fn arb_light_client_state() -> impl Strategy<Value = LightClientState> {
+        (
+            (any::<u64>(), any::<u64>(), any::<u64>(), vec(".*", 0..4)),
+            any::<[bool; 5]>(),
+            proptest::option::of(arb_storage_layout()),
+            any::<u64>(),
+        )
+            .prop_map(
+                |(
+                    (epoch_size, allowed_clock_skew, trusting_period, upgrade_path),
+                    flags,
+                    layout,
+                    challenge_period,
+                )| {
+                    LightClientState {
+                        epoch_size,
+                        allowed_clock_skew,
+                        trusting_period,
+                        upgrade_path,
+                        verify_epoch_headers: flags[0],
+                        verify_non_epoch_headers: flags[1],
+                        verify_header_timestamp: flags[2],
+                        allow_update_after_misbehavior: flags[3],
+                        allow_update_after_expiry: flags[4],
+                        ibc_storage_layout: layout,
+                        challenge_period,
+                    }
+                },
+            )
+    }
+
+    proptest! {
+        #[test]
+        fn light_client_state_rlp_round_trip(state in arb_light_client_state()) {
+            let decoded = LightClientState::from_rlp(&state.to_rlp()).unwrap();
+
+            prop_assert_eq!(&decoded, &state);
+        }
+    }
+
+    #[test]
+    fn test_get_proofs_of_possession() {
+        let mut wasm_header: WasmHeader =
+            serde_json::from_str(r#"{"data": "", "height": {}, "type": "header"}"#).unwrap();
+        assert_eq!(get_proofs_of_possession(&wasm_header).unwrap().len(), 0);
+
+        wasm_header.proofs_of_possession.push(ProofOfPossession {
+            address: base64::encode([1; 20]),
+            signature: base64::encode([2; 48]),
+        });
+        let proofs = get_proofs_of_possession(&wasm_header).unwrap();
+        assert_eq!(proofs.get(&Address::from([1; 20])), Some(&vec![2; 48]));
+
+        wasm_header.proofs_of_possession[0].address = base64::encode([1; 21]);
+        assert_eq!(get_proofs_of_possession(&wasm_header).is_err(), true);
+    }
+
+    fn get_example_storage_layout(consensus_root: ConsensusRoot) -> IbcStorageLayout {
+        IbcStorageLayout {
+            host_address: Address::from([0x1b; 20]),
+            key_encoding: PathKeyEncoding::Keccak,
+            consensus_root,
+            client_state_slot: 0,
+            consensus_state_slot: 1,
+            connection_slot: 2,
+            channel_slot: 3,
+            packet_commitment_slot: 4,
+            packet_ack_slot: 5,
+            packet_receipt_slot: 6,
+            next_sequence_recv_slot: 7,
+        }
+    }
+
+    fn get_example_client_state_with_layout(layout: &IbcStorageLayout) -> ClientState {
+        let mut light_client_state = get_example_light_client_state();
+        light_client_state.ibc_storage_layout = Some(layout.clone());
+
+        let mut client_state = get_example_client_state(0, 5);
+        client_state.data = base64::encode(light_client_state.to_rlp());
+
+        client_state
+    }
+
+    fn get_example_channel_path() -> IcsPath {
+        IcsPath::ChannelEnds(
+            PortId::from_str("transfer").unwrap(),
+            ChannelId::from_str("channel-0").unwrap(),
+        )
+    }
+
+    // Returns the channel end committed in the IBC host contract storage, along with the state
+    // and storage tries
+    fn get_example_host_state(layout: &IbcStorageLayout) -> (Channel, Trie, Trie) {
+        let channel = Channel {
+            state: 3,
+            ordering: 1,
+            counterparty: Counterparty {
+                client_id: String::from("07-tendermint-0"),
+                connection_id: String::from("connection-0"),
+                prefix: MerklePrefix {
+                    key_prefix: base64::encode("ibc"),
+                },
+            },
+            connection_hops: vec![String::from("connection-0")],
+            version: String::from("ics20-1"),
+        };
+
+        // IBC host contract stores keccak256 of the channel end under the path
+        let slot = layout.slot(&get_example_channel_path()).unwrap();
+        let mut storage_trie = Trie::new();
+        storage_trie.insert(
+            &keccak(&slot),
+            &rlp::encode(&keccak(&to_vec(&channel).unwrap()).to_vec()),
+        );
+
+        let mut account = rlp::RlpStream::new_list(4);
+        account.append(&1u64);
+        account.append(&0u64);
+        account.append(&storage_trie.root_hash().as_ref());
+        account.append(&keccak(b"code").as_ref());
+
+        let mut state_trie = Trie::new();
+        state_trie.insert(&keccak(&layout.host_address), &account.out());
+
+        (channel, state_trie, storage_trie)
+    }
+
+    fn get_example_light_client_state() -> LightClientState {
+        LightClientState {
+            epoch_size: 17280,
+            allowed_clock_skew: 5,
+            trusting_period: 0,
+            upgrade_path: vec![String::from("upgrade"), String::from("upgradedIBCState")],
+            verify_epoch_headers: true,
+            verify_non_epoch_headers: true,
+            verify_header_timestamp: true,
+            allow_update_after_misbehavior: false,
+            allow_update_after_expiry: false,
+            ibc_storage_layout: None,
+            challenge_period: 0,
+        }
+    }
+
     fn get_example_client_state(revision_number: u64, revision_height: u64) -> ClientState {
         ClientState {
-            data: String::from(""),
+            data: base64::encode(get_example_light_client_state().to_rlp()),
             code_id: String::from(""),
             frozen: false,
             frozen_height: None,
@@ -1197,6 +2275,45 @@ mod tests {
         }
     }
 
+    // Returns the header sealed by all the keys (or unsealed if there are none)
+    fn get_example_header(number: u64, root: Hash, keys: &[PrivateKey]) -> Header {
+        let extra = |aggregated_seal| {
+            IstanbulExtra {
+                added_validators: Vec::new(),
+                added_validators_public_keys: Vec::new(),
+                removed_validators: ValidatorBitmap::new(),
+                seal: Vec::new(),
+                aggregated_seal,
+                parent_aggregated_seal: IstanbulAggregatedSeal::new(),
+            }
+            .to_rlp(&IstanbulExtraVanity::default())
+        };
+
+        let mut header = Header::new();
+        header.number = number.into();
+        header.root = root;
+        header.extra = extra(IstanbulAggregatedSeal::new());
+
+        // aggregated seal is not a part of the header hash
+        if !keys.is_empty() {
+            let hash = header.hash().unwrap();
+            let bitmap = (1 << keys.len()) - 1;
+            header.extra = extra(sign_aggregated_seal(keys, hash, bitmap, 0));
+        }
+
+        header
+    }
+
+    fn get_example_wasm_header(header: &Header, height: Height) -> WasmHeader {
+        WasmHeader {
+            data: base64::encode(header.to_rlp()),
+            height,
+            r#type: String::from("header"),
+            account_proof: Vec::new(),
+            proofs_of_possession: Vec::new(),
+        }
+    }
+
     fn get_example_proof(key: Vec<u8>, value: Vec<u8>) -> (MerkleProof, Vec<u8>) {
         let leaf = LeafOp {
             hash: HashOp::Sha256.into(),
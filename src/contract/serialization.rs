@@ -1,4 +1,5 @@
-use crate::traits::FromRlp;
+use crate::errors::Error;
+use crate::traits::{FromJson, FromRlp, FromRlpStrict};
 use cosmwasm_std::{from_slice, StdError};
 use serde::de::DeserializeOwned;
 
@@ -48,3 +49,52 @@ where
 
     Ok(t)
 }
+
+// from_base64_rlp_or_json decodes either RLP or JSON encoded data. The JSON object is detected by
+// the leading '{' byte, which can't start an RLP encoded list.
+pub fn from_base64_rlp_or_json<T, S>(base64_data: &String, target_type: S) -> Result<T, StdError>
+where
+    T: FromRlp + FromJson,
+    S: Into<String> + Clone,
+{
+    decode_rlp_or_json(base64_data, target_type, T::from_rlp)
+}
+
+// from_base64_rlp_or_json_strict is like from_base64_rlp_or_json, but it rejects the non-canonical
+// RLP encoding (the JSON numbers are always decoded in the canonical form only). It's meant for
+// the data provided by the relayer, while the states stored by the contract itself may use the
+// older encoding versions.
+pub fn from_base64_rlp_or_json_strict<T, S>(
+    base64_data: &String,
+    target_type: S,
+) -> Result<T, StdError>
+where
+    T: FromRlpStrict + FromJson,
+    S: Into<String> + Clone,
+{
+    decode_rlp_or_json(base64_data, target_type, T::from_rlp_strict)
+}
+
+fn decode_rlp_or_json<T, S>(
+    base64_data: &String,
+    target_type: S,
+    from_rlp: fn(&[u8]) -> Result<T, Error>,
+) -> Result<T, StdError>
+where
+    T: FromJson,
+    S: Into<String> + Clone,
+{
+    let bytes = from_base64(&base64_data, target_type.clone())?;
+
+    let result = match bytes.first() {
+        Some(b'{') => T::from_json(bytes.as_slice()),
+        _ => from_rlp(bytes.as_slice()),
+    };
+
+    result.map_err(|e| {
+        StdError::parse_err(
+            target_type,
+            format!("Unable to decode (rlp or json) from base64 data. Error: {}", e),
+        )
+    })
+}
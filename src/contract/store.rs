@@ -1,4 +1,7 @@
+use crate::clock::Timestamp;
 use crate::contract::types::ibc::Height;
+use crate::contract::types::state::ProvisionalUpdate;
+use crate::contract::util::to_generic_err;
 
 use cosmwasm_std::{StdResult, Storage};
 use cosmwasm_storage::{singleton, singleton_read};
@@ -6,22 +9,69 @@ use cosmwasm_storage::{singleton, singleton_read};
 // processed_time_key returns the key under which the processed time will be stored in the client store
 pub fn processed_time_key(height: Height) -> Vec<u8> {
     // consensusStates/ path is defined in ICS 24
+    format!("consensusStates/{}/processedTimeNanos", height)
+        .as_bytes()
+        .to_owned()
+}
+
+// legacy_processed_time_key returns the key under which the processed time (in seconds) was stored
+// before switching to nanoseconds. It's only read, so that the consensus states created before the
+// upgrade keep their processed time.
+pub fn legacy_processed_time_key(height: Height) -> Vec<u8> {
     format!("consensusStates/{}/processedTime", height)
         .as_bytes()
         .to_owned()
 }
 
-// set_processed_time stores the time at which a header was processed and the corresponding consensus state was created.
+// set_processed_time stores the time (in nanoseconds) at which a header was processed and the corresponding consensus state was created.
 // This is useful when validating whether a packet has reached the specified delay period in the
 // tendermint client's verification functions
-pub fn set_processed_time(storage: &mut dyn Storage, height: Height, time: u64) -> StdResult<()> {
+pub fn set_processed_time(
+    storage: &mut dyn Storage,
+    height: Height,
+    time: Timestamp,
+) -> StdResult<()> {
     let key = processed_time_key(height);
-    singleton(storage, &key).save(&time)
+    let nanos = time.as_nanos().map_err(to_generic_err)?;
+    singleton(storage, &key).save(&nanos)
 }
 
-// get_processed_time gets the time (in nanoseconds) at which this chain recieved and processed a celo header.
+// get_processed_time gets the time at which this chain recieved and processed a celo header.
 // This is used to validate that a recieved packet has passed the delay period
-pub fn get_processed_time(storage: &dyn Storage, height: Height) -> StdResult<u64> {
+pub fn get_processed_time(storage: &dyn Storage, height: Height) -> StdResult<Timestamp> {
     let key = processed_time_key(height);
-    singleton_read(storage, &key).load()
+    if let Some(nanos) = singleton_read(storage, &key).may_load()? {
+        return Ok(Timestamp::from_nanos(nanos));
+    }
+
+    // fall back to the processed time stored (in seconds) before the upgrade
+    let key = legacy_processed_time_key(height);
+    singleton_read(storage, &key).load().map(Timestamp::from_secs)
+}
+
+// provisional_update_key returns the key under which the optimistic update of the consensus state will be stored in the client store
+pub fn provisional_update_key(height: Height) -> Vec<u8> {
+    format!("consensusStates/{}/provisionalUpdate", height)
+        .as_bytes()
+        .to_owned()
+}
+
+// set_provisional_update stores the optimistic update, that created the consensus state at the given height
+pub fn set_provisional_update(
+    storage: &mut dyn Storage,
+    height: Height,
+    update: &ProvisionalUpdate,
+) -> StdResult<()> {
+    let key = provisional_update_key(height);
+    singleton(storage, &key).save(update)
+}
+
+// get_provisional_update gets the optimistic update of the consensus state at the given height.
+// None is returned for the consensus states created with the seal verification.
+pub fn get_provisional_update(
+    storage: &dyn Storage,
+    height: Height,
+) -> StdResult<Option<ProvisionalUpdate>> {
+    let key = provisional_update_key(height);
+    singleton_read(storage, &key).may_load()
 }
@@ -150,14 +150,23 @@ pub fn verify_membership(
     for (i, commitment_proof) in proof.proofs.iter().skip(index).enumerate() {
         if let Some(ex) = get_exist_proof(commitment_proof) {
             subroot = ics23::calculate_existence_root(&ex).map_err(|e| to_generic_err(e))?;
-            let key = match keys.key_path.get(keys.key_path.len() - 1 - i) {
+            let key = match keys
+                .key_path
+                .len()
+                .checked_sub(1 + i)
+                .and_then(|index| keys.key_path.get(index))
+            {
                 Some(key) => key,
                 None => return Err(StdError::generic_err("could not retrieve key bytes")),
             };
+            let spec = match specs.get(i) {
+                Some(spec) => spec,
+                None => return Err(StdError::generic_err("could not retrieve proof spec")),
+            };
 
             if !ics23::verify_membership(
                 &commitment_proof,
-                &specs[i],
+                spec,
                 &subroot,
                 key.as_bytes(),
                 &value,
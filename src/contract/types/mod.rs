@@ -2,3 +2,4 @@ pub mod wasm;
 pub mod msg;
 pub mod ibc;
 pub mod state;
+pub mod storage;
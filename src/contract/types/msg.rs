@@ -36,6 +36,13 @@ pub enum HandleMsg {
         consensus_state_1: ConsensusState,
         consensus_state_2: ConsensusState,
     },
+    // Reverts the optimistic update with a conflicting header, sealed by the validators of
+    // the consensus state the update was applied on
+    ChallengeOptimisticUpdate {
+        me: ClientState,
+        header: WasmHeader,
+        consensus_state: ConsensusState,
+    },
     VerifyClientState {
         me: ClientState,
         height: Height,
@@ -209,7 +216,12 @@ pub enum QueryMsg {
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
 pub struct ProcessedTimeResponse {
+    // processed time in seconds, kept for the backward compatibility
     pub time: u64,
+
+    // processed time in nanoseconds
+    #[serde(default)]
+    pub time_nanos: u64,
 }
 
 impl ClientStateCallResponseResult {
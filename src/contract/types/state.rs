@@ -1,12 +1,20 @@
+use crate::contract::types::storage::IbcStorageLayout;
 use crate::errors::{Error, Kind};
+use crate::serialization::rlp::{rlp_begin_versioned, rlp_versioned_fields};
 use crate::traits::{FromRlp, ToRlp, StateConfig};
+use crate::types::header::Hash;
 use crate::types::state::Snapshot;
 
-use rlp_derive::{RlpEncodable, RlpDecodable};
+use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
 
 pub type LightConsensusState = Snapshot;
 
-#[derive(Serialize, Deserialize, RlpDecodable, RlpEncodable, Clone, PartialEq, Debug)]
+/// LIGHT_CLIENT_STATE_ENCODING_VERSION is the current version of the LightClientState RLP
+/// encoding. The older versions are upgraded on decoding, so that new fields can be added
+/// without re-creating the IBC clients.
+pub const LIGHT_CLIENT_STATE_ENCODING_VERSION: u64 = 2;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct LightClientState {
     pub epoch_size: u64,
     pub allowed_clock_skew: u64,
@@ -19,6 +27,121 @@ pub struct LightClientState {
 
     pub allow_update_after_misbehavior: bool,
     pub allow_update_after_expiry: bool,
+
+    /// Storage layout of the IBC host contract. If set, the IBC paths are proven with the
+    /// account/storage proofs against the Celo state root instead of the ICS-23 proofs.
+    #[serde(default)]
+    pub ibc_storage_layout: Option<IbcStorageLayout>,
+
+    /// Challenge period (in seconds) of the optimistic updates. If set, the headers are accepted
+    /// without the seal verification, and the consensus state stays provisional until the
+    /// period has passed. Zero disables the optimistic updates.
+    #[serde(default)]
+    pub challenge_period: u64,
+}
+
+impl Encodable for LightClientState {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        rlp_begin_versioned(s, LIGHT_CLIENT_STATE_ENCODING_VERSION, 11);
+
+        s.append(&self.epoch_size);
+        s.append(&self.allowed_clock_skew);
+        s.append(&self.trusting_period);
+        s.append_list::<String, String>(&self.upgrade_path);
+
+        s.append(&self.verify_epoch_headers);
+        s.append(&self.verify_non_epoch_headers);
+        s.append(&self.verify_header_timestamp);
+
+        s.append(&self.allow_update_after_misbehavior);
+        s.append(&self.allow_update_after_expiry);
+
+        // optional fields are encoded as a list of zero or one element
+        match &self.ibc_storage_layout {
+            Some(layout) => s.begin_list(1).append(layout),
+            None => s.begin_list(0),
+        };
+
+        s.append(&self.challenge_period);
+    }
+}
+
+impl Decodable for LightClientState {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        let (version, fields) = rlp_versioned_fields(rlp)?;
+
+        match version {
+            0 => LightClientState::decode_v0(&fields),
+            1 => LightClientState::decode_v1(&fields),
+            2 => LightClientState::decode_v2(&fields),
+            _ => Err(DecoderError::Custom("unsupported light client state encoding version")),
+        }
+    }
+}
+
+impl LightClientState {
+    /// Decodes the unversioned layout, where the storage layout was appended only if set
+    fn decode_v0(rlp: &Rlp) -> Result<Self, DecoderError> {
+        let ibc_storage_layout = match rlp.item_count()? {
+            9 => None,
+            10 => Some(rlp.val_at(9)?),
+            _ => return Err(DecoderError::RlpIncorrectListLen),
+        };
+
+        LightClientState::decode_fields(rlp, ibc_storage_layout, 0)
+    }
+
+    /// Decodes the layout without the challenge period
+    fn decode_v1(rlp: &Rlp) -> Result<Self, DecoderError> {
+        if rlp.item_count()? != 10 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+
+        let ibc_storage_layout = LightClientState::decode_storage_layout(&rlp.at(9)?)?;
+
+        LightClientState::decode_fields(rlp, ibc_storage_layout, 0)
+    }
+
+    fn decode_v2(rlp: &Rlp) -> Result<Self, DecoderError> {
+        if rlp.item_count()? != 11 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+
+        let ibc_storage_layout = LightClientState::decode_storage_layout(&rlp.at(9)?)?;
+
+        LightClientState::decode_fields(rlp, ibc_storage_layout, rlp.val_at(10)?)
+    }
+
+    fn decode_storage_layout(rlp: &Rlp) -> Result<Option<IbcStorageLayout>, DecoderError> {
+        match rlp.item_count()? {
+            0 => Ok(None),
+            1 => Ok(Some(rlp.val_at(0)?)),
+            _ => Err(DecoderError::RlpIncorrectListLen),
+        }
+    }
+
+    fn decode_fields(
+        rlp: &Rlp,
+        ibc_storage_layout: Option<IbcStorageLayout>,
+        challenge_period: u64,
+    ) -> Result<Self, DecoderError> {
+        Ok(LightClientState {
+            epoch_size: rlp.val_at(0)?,
+            allowed_clock_skew: rlp.val_at(1)?,
+            trusting_period: rlp.val_at(2)?,
+            upgrade_path: rlp.list_at(3)?,
+
+            verify_epoch_headers: rlp.val_at(4)?,
+            verify_non_epoch_headers: rlp.val_at(5)?,
+            verify_header_timestamp: rlp.val_at(6)?,
+
+            allow_update_after_misbehavior: rlp.val_at(7)?,
+            allow_update_after_expiry: rlp.val_at(8)?,
+
+            ibc_storage_layout,
+            challenge_period,
+        })
+    }
 }
 
 impl ToRlp for LightClientState {
@@ -39,8 +162,29 @@ impl FromRlp for LightClientState {
 impl StateConfig for LightClientState {
     fn epoch_size(&self) -> u64 { self.epoch_size }
     fn allowed_clock_skew(&self) -> u64 { self.allowed_clock_skew }
+    fn trusting_period(&self) -> u64 { self.trusting_period }
 
     fn verify_epoch_headers(&self) -> bool { self.verify_epoch_headers }
     fn verify_non_epoch_headers(&self) -> bool { self.verify_non_epoch_headers }
     fn verify_header_timestamp(&self) -> bool { self.verify_header_timestamp }
+
+    // the headers are provided by the untrusted relayers
+    fn strict_rlp_decoding(&self) -> bool { true }
+}
+
+/// ProvisionalUpdate describes the consensus state created by the optimistic update, that is
+/// without the seal verification. Until the challenge deadline, the update can be reverted by
+/// a conflicting header sealed by the validators of the consensus state it was applied on.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ProvisionalUpdate {
+    /// Number of the optimistically accepted header
+    pub number: u64,
+    /// Hash of the optimistically accepted header
+    pub header_hash: Hash,
+    /// Hash of the validator set, that was expected to seal the header
+    pub validators_hash: Hash,
+    /// Time (in nanoseconds) when the consensus state becomes final
+    pub challenge_deadline: u64,
+    /// Whether the update was reverted by a conflicting header
+    pub reverted: bool,
 }
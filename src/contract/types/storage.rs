@@ -0,0 +1,200 @@
+use crate::contract::types::ibc::Path as IcsPath;
+use crate::proof::{verify_account_proof, verify_storage_proof};
+use crate::slots::{mapping_slot, slot_from_u64, SlotKey};
+use crate::trie::keccak;
+use crate::types::header::{Address, Hash};
+use cosmwasm_std::StdError;
+use num_bigint::{BigInt as Integer, Sign};
+use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
+
+/// PathKeyEncoding defines how the ICS-24 path is used as the mapping key
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum PathKeyEncoding {
+    /// `mapping(string => bytes32)`, keyed by the path
+    String,
+
+    /// `mapping(bytes32 => bytes32)`, keyed by keccak256 of the path
+    Keccak,
+}
+
+/// ConsensusRoot defines which root is stored in the consensus state
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum ConsensusRoot {
+    /// Header state root, the proofs include the IBC host account proof
+    StateRoot,
+
+    /// IBC host contract storage root (proven on update), the proofs are the storage proofs only
+    HostStorageRoot,
+}
+
+impl Default for ConsensusRoot {
+    fn default() -> Self {
+        ConsensusRoot::StateRoot
+    }
+}
+
+/// IbcStorageLayout maps the ICS-24 paths to the storage slots of the IBC host contract on Celo.
+/// Each kind of path is kept in a separate mapping (declared at the given slot), holding
+/// keccak256 of the committed value.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct IbcStorageLayout {
+    pub host_address: Address,
+    pub key_encoding: PathKeyEncoding,
+
+    #[serde(default)]
+    pub consensus_root: ConsensusRoot,
+
+    pub client_state_slot: u64,
+    pub consensus_state_slot: u64,
+    pub connection_slot: u64,
+    pub channel_slot: u64,
+    pub packet_commitment_slot: u64,
+    pub packet_ack_slot: u64,
+    pub packet_receipt_slot: u64,
+    pub next_sequence_recv_slot: u64,
+}
+
+impl IbcStorageLayout {
+    /// Returns the storage slot of the value committed under the path
+    pub fn slot(&self, path: &IcsPath) -> Result<Hash, StdError> {
+        let position = match path {
+            IcsPath::ClientState(_) => self.client_state_slot,
+            IcsPath::ClientConsensusState { .. } => self.consensus_state_slot,
+            IcsPath::Connections(_) => self.connection_slot,
+            IcsPath::ChannelEnds(_, _) => self.channel_slot,
+            IcsPath::Commitments { .. } => self.packet_commitment_slot,
+            IcsPath::Acks { .. } => self.packet_ack_slot,
+            IcsPath::Receipts { .. } => self.packet_receipt_slot,
+            IcsPath::SeqRecvs(_, _) => self.next_sequence_recv_slot,
+            _ => {
+                return Err(StdError::generic_err(format!(
+                    "path {} is not supported by the storage layout",
+                    path
+                )))
+            }
+        };
+
+        let path = path.to_string();
+        let key = match self.key_encoding {
+            PathKeyEncoding::String => SlotKey::from_string(&path),
+            PathKeyEncoding::Keccak => SlotKey::Word(keccak(path.as_bytes())),
+        };
+
+        Ok(mapping_slot(&slot_from_u64(position), &key))
+    }
+
+    /// Returns the IBC host contract storage root, proven by the account proof against the
+    /// state root
+    pub fn host_storage_root(
+        &self,
+        state_root: &Hash,
+        account_proof: &[Vec<u8>],
+    ) -> Result<Hash, StdError> {
+        let account = verify_account_proof(state_root, &self.host_address, account_proof)
+            .map_err(|e| StdError::generic_err(format!("invalid account proof: {}", e)))?
+            .ok_or_else(|| StdError::generic_err("IBC host account doesn't exist"))?;
+
+        Ok(account.storage_root)
+    }
+
+    /// Verifies the proof of the value stored under the path against the consensus state root.
+    /// The value set to None expects the path to be empty (ie. absence of the packet receipt).
+    pub fn verify(
+        &self,
+        root: &[u8],
+        path: &IcsPath,
+        value: Option<&[u8]>,
+        proof: &StorageProof,
+    ) -> Result<(), StdError> {
+        let root = Hash::from_slice(root)
+            .map_err(|e| StdError::generic_err(format!("invalid root: {}", e)))?;
+
+        let storage_root = match self.consensus_root {
+            ConsensusRoot::StateRoot => self.host_storage_root(&root, &proof.account_proof)?,
+            ConsensusRoot::HostStorageRoot => root,
+        };
+
+        let stored = verify_storage_proof(&storage_root, &self.slot(path)?, &proof.storage_proof)
+            .map_err(|e| StdError::generic_err(format!("invalid storage proof: {}", e)))?;
+
+        let expected = match value {
+            Some(value) => Integer::from_bytes_be(Sign::Plus, &keccak(value)),
+            None => Integer::from(0),
+        };
+
+        if stored != expected {
+            return Err(StdError::generic_err(format!(
+                "storage proof verification failed, stored value doesn't match under path: {}",
+                path
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl Encodable for IbcStorageLayout {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(11);
+
+        s.append(&self.host_address);
+        s.append(&(self.key_encoding as u8));
+        s.append(&(self.consensus_root as u8));
+
+        s.append(&self.client_state_slot);
+        s.append(&self.consensus_state_slot);
+        s.append(&self.connection_slot);
+        s.append(&self.channel_slot);
+        s.append(&self.packet_commitment_slot);
+        s.append(&self.packet_ack_slot);
+        s.append(&self.packet_receipt_slot);
+        s.append(&self.next_sequence_recv_slot);
+    }
+}
+
+impl Decodable for IbcStorageLayout {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        if rlp.item_count()? != 11 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+
+        let key_encoding = match rlp.val_at::<u8>(1)? {
+            0 => PathKeyEncoding::String,
+            1 => PathKeyEncoding::Keccak,
+            _ => return Err(DecoderError::Custom("unknown path key encoding")),
+        };
+
+        let consensus_root = match rlp.val_at::<u8>(2)? {
+            0 => ConsensusRoot::StateRoot,
+            1 => ConsensusRoot::HostStorageRoot,
+            _ => return Err(DecoderError::Custom("unknown consensus root")),
+        };
+
+        Ok(IbcStorageLayout {
+            host_address: rlp.val_at(0)?,
+            key_encoding,
+            consensus_root,
+            client_state_slot: rlp.val_at(3)?,
+            consensus_state_slot: rlp.val_at(4)?,
+            connection_slot: rlp.val_at(5)?,
+            channel_slot: rlp.val_at(6)?,
+            packet_commitment_slot: rlp.val_at(7)?,
+            packet_ack_slot: rlp.val_at(8)?,
+            packet_receipt_slot: rlp.val_at(9)?,
+            next_sequence_recv_slot: rlp.val_at(10)?,
+        })
+    }
+}
+
+/// StorageProof holds the `eth_getProof` nodes proving the value stored under the path
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct StorageProof {
+    /// Proof of the IBC host account in the state trie (empty if the consensus root is the
+    /// host storage root)
+    #[serde(default, with = "crate::serialization::bytes::hexbytesvec")]
+    pub account_proof: Vec<Vec<u8>>,
+
+    /// Proof of the slot in the IBC host storage trie
+    #[serde(with = "crate::serialization::bytes::hexbytesvec")]
+    pub storage_proof: Vec<Vec<u8>>,
+}
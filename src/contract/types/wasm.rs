@@ -43,6 +43,21 @@ pub struct WasmHeader {
     pub data: String, // Go serializes []byte to base64 encoded string
     pub height: Height,
     pub r#type: String,
+
+    // Proof of the IBC host account (base64 encoded trie nodes), required if the storage root
+    // of the host contract is used as the consensus root
+    #[serde(default)]
+    pub account_proof: Vec<String>,
+
+    // Proofs-of-possession of the BLS keys added by the epoch header (optional)
+    #[serde(default)]
+    pub proofs_of_possession: Vec<ProofOfPossession>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct ProofOfPossession {
+    pub address: String,   // Go serializes []byte to base64 encoded string
+    pub signature: String, // Go serializes []byte to base64 encoded string
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
@@ -1,8 +1,10 @@
+use crate::clock::Timestamp;
 use byteorder::{BigEndian, ByteOrder};
 use cosmwasm_std::{attr, to_vec, Binary};
-use cosmwasm_std::{HandleResponse, StdError};
+use cosmwasm_std::{Env, HandleResponse, StdError};
 use serde::Serialize;
 use std::fmt::Display;
+use std::time::Duration;
 
 pub fn u64_to_big_endian(value: u64) -> Vec<u8> {
     let mut buf = [0; 8];
@@ -11,6 +13,11 @@ pub fn u64_to_big_endian(value: u64) -> Vec<u8> {
     buf.to_vec()
 }
 
+/// Returns the block time of the host chain
+pub fn block_timestamp(env: &Env) -> Timestamp {
+    Timestamp::from_secs(env.block.time).saturating_add(Duration::from_nanos(env.block.time_nanos))
+}
+
 pub fn wrap_response<T>(result: T, action: &'static str) -> Result<HandleResponse, StdError>
 where
     T: Serialize,
@@ -0,0 +1,74 @@
+use crate::errors::{Error, Kind};
+use crate::traits::SignerRecovery;
+use crate::types::header::{Address, Hash, ADDRESS_LENGTH, HASH_LENGTH};
+use secp256k1::recovery::{RecoverableSignature, RecoveryId};
+use secp256k1::{Message, PublicKey, Secp256k1};
+use sha3::{Digest, Keccak256};
+
+/// Secp256k1Recovery recovers the signers of the ECDSA seals with libsecp256k1, so that the
+/// engines of the ECDSA sealed IBFT chains can verify the committed seals with
+/// [`verify_committed_seals`](crate::verify_committed_seals)
+pub struct Secp256k1Recovery;
+
+impl SignerRecovery for Secp256k1Recovery {
+    fn recover(&self, hash: &Hash, signature: &[u8]) -> Result<Address, Error> {
+        let invalid = |_| Error::from(Kind::InvalidMessageSignature);
+        if signature.len() != 65 {
+            return Err(Kind::InvalidMessageSignature.into());
+        }
+
+        let recovery_id = RecoveryId::from_i32(i32::from(signature[64])).map_err(invalid)?;
+        let signature =
+            RecoverableSignature::from_compact(&signature[..64], recovery_id).map_err(invalid)?;
+        let message = Message::from_slice(hash).map_err(invalid)?;
+        let public_key = Secp256k1::verification_only()
+            .recover(&message, &signature)
+            .map_err(invalid)?;
+
+        Ok(pubkey_to_address(&public_key))
+    }
+}
+
+/// Returns the Ethereum address of the public key, that is the last 20 bytes of the keccak256
+/// hash of the uncompressed key (without the 0x04 prefix)
+pub fn pubkey_to_address(public_key: &PublicKey) -> Address {
+    let digest = Keccak256::digest(&public_key.serialize_uncompressed()[1..]);
+
+    let mut address = [0; ADDRESS_LENGTH];
+    address.copy_from_slice(&digest[HASH_LENGTH - ADDRESS_LENGTH..]);
+
+    Address::from(address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::certificate::test_utils::{secp256k1_validators, sign_hash};
+
+    #[test]
+    fn recovers_signer_address() {
+        let (keys, validators) = secp256k1_validators(2);
+        let hash = Hash::from([7; 32]);
+
+        let signature = sign_hash(&keys[1], &hash);
+        assert_eq!(
+            Secp256k1Recovery.recover(&hash, &signature).unwrap(),
+            validators[1].address
+        );
+
+        // a different hash recovers a different signer
+        let other = Secp256k1Recovery
+            .recover(&Hash::from([8; 32]), &signature)
+            .unwrap();
+        assert_ne!(other, validators[1].address);
+
+        for malformed in [
+            signature[..64].to_vec(),
+            [&signature[..64], &[4]].concat(),
+            vec![0; 65],
+        ] {
+            let err = Secp256k1Recovery.recover(&hash, &malformed).unwrap_err();
+            assert!(matches!(err.kind(), Kind::InvalidMessageSignature));
+        }
+    }
+}
@@ -0,0 +1,300 @@
+use crate::bls::{validate_aggregated_seal, BlsSealVerifier};
+use crate::errors::{Error, Kind};
+use crate::traits::{IstanbulEngine, SealVerifier, SignerRecovery};
+use crate::types::header::{Hash, Header};
+use crate::types::istanbul::{CommittedSeal, ConsensusExtra, IstanbulExtra, ValidatorSetChange};
+use crate::types::state::Validator;
+use crate::types::validator_set::ValidatorSet;
+
+/// The default engine of the State, verifying the seals with the BLS backend
+pub(crate) const CELO_ENGINE: CeloEngine<'static> = CeloEngine {
+    seal_verifier: &BlsSealVerifier,
+};
+
+/// CeloEngine follows the Celo flavour of IBFT: the istanbul extra-data carries the validator
+/// set diff and the validators commit to the block with the aggregated BLS signature
+#[derive(Clone, Copy)]
+pub struct CeloEngine<'a> {
+    /// Backend verifying the aggregated seals
+    pub seal_verifier: &'a dyn SealVerifier,
+}
+
+impl<'a> CeloEngine<'a> {
+    pub fn new(seal_verifier: &'a dyn SealVerifier) -> Self {
+        CeloEngine { seal_verifier }
+    }
+}
+
+impl Default for CeloEngine<'static> {
+    fn default() -> Self {
+        CELO_ENGINE
+    }
+}
+
+impl<'a> IstanbulEngine for CeloEngine<'a> {
+    fn decode_extra(&self, header: &Header, strict: bool) -> Result<ConsensusExtra, Error> {
+        let extra = if strict {
+            IstanbulExtra::from_rlp_strict(&header.extra)?
+        } else {
+            IstanbulExtra::from_rlp(&header.extra)?
+        };
+        extra.validate()?;
+
+        // convert istanbul validators into a Validator struct
+        if extra.added_validators.len() != extra.added_validators_public_keys.len() {
+            return Err(Kind::InvalidValidatorSetDiff {
+                msg: "error in combining addresses and public keys",
+            }
+            .into());
+        }
+
+        let added = extra
+            .added_validators
+            .iter()
+            .zip(extra.added_validators_public_keys.iter())
+            .map(|(address, public_key)| Validator {
+                address: *address,
+                public_key: *public_key,
+            })
+            .collect();
+
+        Ok(ConsensusExtra {
+            validator_set_change: ValidatorSetChange::Diff {
+                added,
+                removed: extra.removed_validators,
+            },
+            committed_seal: CommittedSeal::Aggregated(extra.aggregated_seal),
+        })
+    }
+
+    fn header_hash(&self, header: &Header) -> Result<Hash, Error> {
+        header.hash()
+    }
+
+    fn verify_seal(
+        &self,
+        header_hash: Hash,
+        validators: &ValidatorSet,
+        seal: &CommittedSeal,
+    ) -> Result<(), Error> {
+        match seal {
+            CommittedSeal::Aggregated(aggregated_seal) => {
+                // validated here rather than by the backend, that only checks the signature
+                validate_aggregated_seal(validators, aggregated_seal)?;
+
+                self.seal_verifier.verify_aggregated_signature(
+                    header_hash,
+                    validators,
+                    aggregated_seal,
+                )
+            }
+            CommittedSeal::Ecdsa(_) => Err(Kind::HeaderVerificationError {
+                msg: "celo headers must be sealed with the aggregated BLS signature",
+            }
+            .into()),
+        }
+    }
+
+    fn seal_signers(
+        &self,
+        _header_hash: Hash,
+        validators: &ValidatorSet,
+        seal: &CommittedSeal,
+    ) -> Result<Vec<Validator>, Error> {
+        match seal {
+            CommittedSeal::Aggregated(aggregated_seal) => Ok(validators
+                .signers(&aggregated_seal.bitmap)?
+                .into_iter()
+                .cloned()
+                .collect()),
+            CommittedSeal::Ecdsa(_) => Err(Kind::HeaderVerificationError {
+                msg: "celo headers must be sealed with the aggregated BLS signature",
+            }
+            .into()),
+        }
+    }
+}
+
+/// Verifies the ECDSA committed seals over the digest, as used by the IBFT chains that don't
+/// aggregate the seals. Every seal has to be signed by a distinct member of the validator set,
+/// and there have to be at least `quorum_size` of them.
+pub fn verify_committed_seals(
+    digest: &Hash,
+    validators: &ValidatorSet,
+    seals: &[Vec<u8>],
+    quorum_size: usize,
+    recovery: &dyn SignerRecovery,
+) -> Result<(), Error> {
+    if validators.is_empty() {
+        return Err(Kind::EmptyValidatorSet.into());
+    }
+
+    let mut seen = vec![false; validators.len()];
+    for seal in seals {
+        let signer = recovery.recover(digest, seal)?;
+        let position = match validators.position(&signer) {
+            Some(position) => position,
+            None => {
+                return Err(Kind::InvalidCommittedSeal {
+                    msg: "seal signer is not a validator",
+                }
+                .into())
+            }
+        };
+
+        if seen[position] {
+            return Err(Kind::InvalidCommittedSeal {
+                msg: "duplicated seal signer",
+            }
+            .into());
+        }
+        seen[position] = true;
+    }
+
+    if seals.len() < quorum_size {
+        return Err(Kind::MissingSeals {
+            current: seals.len(),
+            expected: quorum_size,
+        }
+        .into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bls::test_utils::{generate_validators, RecordingSealVerifier};
+    use crate::certificate::test_utils::{secp256k1_validators, sign_hash, Secp256k1Recovery};
+    use crate::types::bitmap::ValidatorBitmap;
+    use crate::types::header::Address;
+    use crate::types::istanbul::{
+        IstanbulAggregatedSeal, IstanbulExtraVanity, SerializedPublicKey,
+        AGGREGATED_SIGNATURE_LENGTH,
+    };
+    use num_bigint::BigInt as Integer;
+
+    #[test]
+    fn decodes_celo_extra() {
+        let validator = Validator {
+            address: Address::from([1; 20]),
+            public_key: SerializedPublicKey::from([2; 96]),
+        };
+        let mut extra = IstanbulExtra {
+            added_validators: vec![validator.address],
+            added_validators_public_keys: vec![validator.public_key],
+            removed_validators: ValidatorBitmap::from(0b10),
+            seal: Vec::new(),
+            aggregated_seal: IstanbulAggregatedSeal::new(),
+            parent_aggregated_seal: IstanbulAggregatedSeal::new(),
+        };
+
+        let mut header = Header::new();
+        header.extra = extra.to_rlp(&IstanbulExtraVanity::default());
+
+        let engine = CeloEngine::default();
+        for strict in [false, true] {
+            assert_eq!(
+                engine.decode_extra(&header, strict).unwrap(),
+                ConsensusExtra {
+                    validator_set_change: ValidatorSetChange::Diff {
+                        added: vec![validator.clone()],
+                        removed: ValidatorBitmap::from(0b10),
+                    },
+                    committed_seal: CommittedSeal::Aggregated(IstanbulAggregatedSeal::new()),
+                }
+            );
+        }
+
+        extra.added_validators_public_keys.clear();
+        header.extra = extra.to_rlp(&IstanbulExtraVanity::default());
+        let err = engine.decode_extra(&header, false).unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidValidatorSetDiff { .. }));
+    }
+
+    #[test]
+    fn verifies_celo_seals_with_the_seal_verifier() {
+        let verifier = RecordingSealVerifier::default();
+        let engine = CeloEngine::new(&verifier);
+        let (_, validators) = generate_validators(4);
+        let validators = ValidatorSet::from(validators);
+        let hash = Hash::from([1; 32]);
+        let aggregated_seal = |bitmap: u64| IstanbulAggregatedSeal {
+            bitmap: ValidatorBitmap::from(bitmap),
+            signature: vec![0; AGGREGATED_SIGNATURE_LENGTH],
+            round: Integer::default(),
+        };
+
+        let seal = CommittedSeal::Aggregated(aggregated_seal(0b0111));
+        engine.verify_seal(hash, &validators, &seal).unwrap();
+        assert_eq!(
+            verifier.seals.borrow()[..],
+            [(hash, aggregated_seal(0b0111))]
+        );
+
+        // the seal is validated before it gets to the verifier
+        let seal = CommittedSeal::Aggregated(aggregated_seal(0b0011));
+        let err = engine.verify_seal(hash, &validators, &seal).unwrap_err();
+        assert!(matches!(err.kind(), Kind::MissingSeals { .. }));
+
+        let seal = CommittedSeal::Aggregated(aggregated_seal(0b10111));
+        let err = engine.verify_seal(hash, &validators, &seal).unwrap_err();
+        assert!(matches!(err.kind(), Kind::BitmapOutOfRange { .. }));
+
+        let seal = CommittedSeal::Aggregated(IstanbulAggregatedSeal::new());
+        let err = engine.verify_seal(hash, &validators, &seal).unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidSignatureLength { .. }));
+
+        let err = engine
+            .verify_seal(hash, &ValidatorSet::default(), &seal)
+            .unwrap_err();
+        assert!(matches!(err.kind(), Kind::EmptyValidatorSet));
+        assert_eq!(verifier.seals.borrow().len(), 1);
+
+        let seal = CommittedSeal::Ecdsa(Vec::new());
+        let err = engine.verify_seal(hash, &validators, &seal).unwrap_err();
+        assert!(matches!(err.kind(), Kind::HeaderVerificationError { .. }));
+    }
+
+    #[test]
+    fn verifies_committed_seals() {
+        let (keys, validators) = secp256k1_validators(4);
+        let validators: ValidatorSet = validators.into();
+        let digest = Hash::from([1; 32]);
+        let seals: Vec<Vec<u8>> = keys.iter().map(|key| sign_hash(key, &digest)).collect();
+
+        let verify = |seals: &[Vec<u8>]| {
+            verify_committed_seals(&digest, &validators, seals, 3, &Secp256k1Recovery)
+        };
+
+        verify(&seals[..3]).unwrap();
+        verify(&seals[1..]).unwrap();
+
+        let err = verify(&seals[..2]).unwrap_err();
+        assert!(matches!(err.kind(), Kind::MissingSeals { .. }));
+
+        let duplicated = vec![seals[0].clone(), seals[1].clone(), seals[0].clone()];
+        let err = verify(&duplicated).unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidCommittedSeal { .. }));
+
+        let (outsiders, _) = secp256k1_validators(5);
+        let foreign = vec![
+            seals[0].clone(),
+            seals[1].clone(),
+            sign_hash(&outsiders[4], &digest),
+        ];
+        let err = verify(&foreign).unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidCommittedSeal { .. }));
+
+        let err = verify_committed_seals(
+            &digest,
+            &ValidatorSet::default(),
+            &seals,
+            0,
+            &Secp256k1Recovery,
+        )
+        .unwrap_err();
+        assert!(matches!(err.kind(), Kind::EmptyValidatorSet));
+    }
+}
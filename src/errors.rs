@@ -11,6 +11,12 @@ pub enum Kind {
     #[error("invalid data length while converting slice to fixed-size array type ({current} != {expected}")]
     InvalidDataLength { current: usize, expected: usize },
 
+    #[error("invalid hex string")]
+    InvalidHexString,
+
+    #[error("address doesn't match its EIP-55 checksum")]
+    InvalidAddressChecksum,
+
     #[error("rlp decode error")]
     RlpDecodeError,
 
@@ -32,9 +38,84 @@ pub enum Kind {
     #[error("BLS invalid public key")]
     BlsInvalidPublicKey,
 
+    #[error("BLS invalid proof of possession")]
+    BlsInvalidProofOfPossession,
+
+    #[error("BLS proof of possession is missing")]
+    BlsMissingProofOfPossession,
+
+    #[error("state has expired, the latest trusted header is older than the trusting period ({trusting_period}s)")]
+    StateExpired { trusting_period: u64 },
+
+    #[error("invalid trust threshold {numerator}/{denominator}, it must be within [1/3, 1]")]
+    InvalidTrustThreshold { numerator: u64, denominator: u64 },
+
+    #[error("not enough signers from the trusted validator set ({current} out of {trusted})")]
+    NotEnoughTrustedSigners { current: usize, trusted: usize },
+
     #[error("header verification failed: {msg}")]
     HeaderVerificationError { msg: &'static str },
 
+    #[error("block number does not fit into u64")]
+    InvalidBlockNumber,
+
+    #[error("validator set is empty")]
+    EmptyValidatorSet,
+
+    #[error("arithmetic overflow: {msg}")]
+    ArithmeticOverflow { msg: &'static str },
+
+    #[error("bitmap has bits set beyond the validator set size ({bits} > {validators})")]
+    BitmapOutOfRange { bits: u64, validators: usize },
+
+    #[error("invalid aggregated seal signature length ({current} != {expected})")]
+    InvalidSignatureLength { current: usize, expected: usize },
+
+    #[error("invalid proposer seal length ({current} != {expected})")]
+    InvalidSealLength { current: usize, expected: usize },
+
+    #[error("istanbul extra-data is too short to contain vanity ({current} < {expected})")]
+    InvalidExtraVanity { current: usize, expected: usize },
+
+    #[error("istanbul extra-data is too large ({current} > {max})")]
+    ExtraDataTooLarge { current: usize, max: usize },
+
+    #[error("aggregated seal round is out of bounds (max: {max})")]
+    InvalidRound { max: u64 },
+
+    #[error("invalid header range: {msg}")]
+    InvalidHeaderRange { msg: &'static str },
+
+    #[error("invalid double sign evidence: {msg}")]
+    InvalidEvidence { msg: &'static str },
+
+    #[error("unexpected consensus message code ({current} != {expected})")]
+    UnexpectedMessageCode { current: u64, expected: u64 },
+
+    #[error("consensus message signature doesn't match the sender")]
+    InvalidMessageSignature,
+
+    #[error("invalid certificate: {msg}")]
+    InvalidCertificate { msg: &'static str },
+
+    #[error("invalid committed seal: {msg}")]
+    InvalidCommittedSeal { msg: &'static str },
+
+    #[error("transactions root doesn't match the header")]
+    TxRootMismatch,
+
+    #[error("receipts root doesn't match the header")]
+    ReceiptRootMismatch,
+
+    #[error("invalid merkle proof: {msg}")]
+    InvalidProof { msg: &'static str },
+
+    #[error("contract is not registered in the registry")]
+    ContractNotRegistered,
+
+    #[error("json decode error: {msg}")]
+    JsonDecodeError { msg: &'static str },
+
     #[error("unkown error occurred")]
     Unknown,
 }
@@ -0,0 +1,342 @@
+use crate::bls::verify_aggregated_seal;
+use crate::errors::{Error, Kind};
+use crate::traits::{FromRlp, ToRlp};
+use crate::types::bitmap::ValidatorBitmap;
+use crate::types::header::{Address, Hash, Header};
+use crate::types::istanbul::{IstanbulAggregatedSeal, IstanbulExtra};
+use crate::types::state::Validator;
+use crate::types::validator_set::ValidatorSet;
+use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
+
+/// DoubleSignEvidence proves that a group of validators committed two different blocks
+/// at the same height
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct DoubleSignEvidence {
+    /// Block number at which the validators equivocated
+    pub number: u64,
+
+    /// Hash of the first block
+    pub hash_1: Hash,
+
+    /// Aggregated seal of the first block
+    pub aggregated_seal_1: IstanbulAggregatedSeal,
+
+    /// Hash of the second block
+    pub hash_2: Hash,
+
+    /// Aggregated seal of the second block
+    pub aggregated_seal_2: IstanbulAggregatedSeal,
+
+    /// Validators that signed both of the blocks
+    pub offenders: Vec<Address>,
+}
+
+impl DoubleSignEvidence {
+    /// Verifies both aggregated seals against the validator set and checks that the
+    /// offenders list matches the seals bitmaps
+    pub fn verify(&self, validators: &ValidatorSet) -> Result<(), Error> {
+        if self.hash_1 == self.hash_2 {
+            return Err(Kind::InvalidEvidence {
+                msg: "block hashes are the same",
+            }
+            .into());
+        }
+
+        verify_aggregated_seal(self.hash_1, validators, &self.aggregated_seal_1)?;
+        verify_aggregated_seal(self.hash_2, validators, &self.aggregated_seal_2)?;
+
+        let offenders = double_signers(
+            validators,
+            &self.aggregated_seal_1.bitmap,
+            &self.aggregated_seal_2.bitmap,
+        );
+        if offenders != self.offenders {
+            return Err(Kind::InvalidEvidence {
+                msg: "offenders don't match the seals",
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+}
+
+impl Encodable for DoubleSignEvidence {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(6);
+
+        s.append(&self.number);
+        s.append(&self.hash_1);
+        s.append(&self.aggregated_seal_1);
+        s.append(&self.hash_2);
+        s.append(&self.aggregated_seal_2);
+        s.append_list(&self.offenders);
+    }
+}
+
+impl Decodable for DoubleSignEvidence {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        Ok(DoubleSignEvidence {
+            number: rlp.val_at(0)?,
+            hash_1: rlp.val_at(1)?,
+            aggregated_seal_1: rlp.val_at(2)?,
+            hash_2: rlp.val_at(3)?,
+            aggregated_seal_2: rlp.val_at(4)?,
+            offenders: rlp.list_at(5)?,
+        })
+    }
+}
+
+impl ToRlp for DoubleSignEvidence {
+    fn to_rlp(&self) -> Vec<u8> {
+        rlp::encode(self)
+    }
+}
+
+impl FromRlp for DoubleSignEvidence {
+    fn from_rlp(bytes: &[u8]) -> Result<Self, Error> {
+        rlp::decode(&bytes).map_err(|e| Kind::RlpDecodeError.context(e).into())
+    }
+}
+
+/// Looks for validators that committed both of the headers. The headers must have the same
+/// height, different hashes and both must be verifiable against the given validator set.
+pub fn detect_double_sign(
+    validators: &ValidatorSet,
+    header_1: &Header,
+    header_2: &Header,
+) -> Result<DoubleSignEvidence, Error> {
+    let evidence = extract_double_sign(validators, header_1, header_2)?;
+    evidence.verify(validators)?;
+
+    Ok(evidence)
+}
+
+/// Builds the double sign evidence out of the headers, whose seals have been already verified
+/// against the given validator set (ie. by the State), so that the pairings aren't computed
+/// twice. The seals are NOT verified here, see [`detect_double_sign`] for the untrusted headers.
+pub fn extract_double_sign(
+    validators: &ValidatorSet,
+    header_1: &Header,
+    header_2: &Header,
+) -> Result<DoubleSignEvidence, Error> {
+    let number = header_1.number_u64()?;
+    if number != header_2.number_u64()? {
+        return Err(Kind::InvalidEvidence {
+            msg: "block numbers differ",
+        }
+        .into());
+    }
+
+    let extra_1 = IstanbulExtra::from_rlp(&header_1.extra)?;
+    extra_1.validate()?;
+    let extra_2 = IstanbulExtra::from_rlp(&header_2.extra)?;
+    extra_2.validate()?;
+
+    let hash_1 = header_1.hash()?;
+    let hash_2 = header_2.hash()?;
+    if hash_1 == hash_2 {
+        return Err(Kind::InvalidEvidence {
+            msg: "block hashes are the same",
+        }
+        .into());
+    }
+
+    let offenders = double_signers(
+        validators,
+        &extra_1.aggregated_seal.bitmap,
+        &extra_2.aggregated_seal.bitmap,
+    );
+
+    Ok(DoubleSignEvidence {
+        number,
+        hash_1,
+        aggregated_seal_1: extra_1.aggregated_seal,
+        hash_2,
+        aggregated_seal_2: extra_2.aggregated_seal,
+        offenders,
+    })
+}
+
+fn double_signers(
+    validators: &[Validator],
+    bitmap_1: &ValidatorBitmap,
+    bitmap_2: &ValidatorBitmap,
+) -> Vec<Address> {
+    bitmap_1
+        .intersection(bitmap_2)
+        .iter()
+        .filter_map(|i| validators.get(i))
+        .map(|validator| validator.address)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bls::test_utils::{generate_validators, sign_aggregated_seal};
+    use crate::types::istanbul::IstanbulExtraVanity;
+    use crate::types::test_utils::{arb_address, arb_aggregated_seal, arb_hash};
+    use bls_crypto::PrivateKey;
+    use num_bigint::BigInt as Integer;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    #[test]
+    fn finds_double_signers() {
+        let (keys, validators) = generate_validators(4);
+        let validators = ValidatorSet::from(validators);
+        let header_1 = signed_header(&keys, 5, Hash::from([1; 32]), 0b0111);
+        let header_2 = signed_header(&keys, 5, Hash::from([2; 32]), 0b1110);
+
+        let evidence = detect_double_sign(&validators, &header_1, &header_2).unwrap();
+
+        assert_eq!(evidence.number, 5);
+        assert_ne!(evidence.hash_1, evidence.hash_2);
+        assert_eq!(
+            evidence.offenders,
+            vec![Address::from([1; 20]), Address::from([2; 20])]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_header_pairs() {
+        let (keys, validators) = generate_validators(4);
+        let validators = ValidatorSet::from(validators);
+
+        // different heights
+        let err = detect_double_sign(
+            &validators,
+            &signed_header(&keys, 5, Hash::from([1; 32]), 0b0111),
+            &signed_header(&keys, 6, Hash::from([2; 32]), 0b0111),
+        )
+        .unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidEvidence { .. }));
+
+        // same header
+        let header_1 = signed_header(&keys, 5, Hash::from([1; 32]), 0b0111);
+        let err = detect_double_sign(&validators, &header_1, &header_1).unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidEvidence { .. }));
+
+        // missing quorum
+        let err = detect_double_sign(
+            &validators,
+            &signed_header(&keys, 5, Hash::from([1; 32]), 0b0111),
+            &signed_header(&keys, 5, Hash::from([2; 32]), 0b0011),
+        )
+        .unwrap_err();
+        assert!(matches!(err.kind(), Kind::MissingSeals { .. }));
+
+        // signed by a different validator set
+        let (other_keys, _) = generate_validators(5);
+        let err = detect_double_sign(
+            &validators,
+            &signed_header(&keys, 5, Hash::from([1; 32]), 0b0111),
+            &signed_header(&other_keys[1..], 5, Hash::from([2; 32]), 0b0111),
+        )
+        .unwrap_err();
+        assert!(matches!(err.kind(), Kind::BlsVerifyError));
+    }
+
+    #[test]
+    fn extracts_evidence_without_verifying_seals() {
+        let (keys, validators) = generate_validators(4);
+        let validators = ValidatorSet::from(validators);
+
+        // the seals are assumed to be verified, so the missing quorum goes unnoticed
+        let header_1 = signed_header(&keys, 5, Hash::from([1; 32]), 0b0111);
+        let header_2 = signed_header(&keys, 5, Hash::from([2; 32]), 0b0011);
+        let evidence = extract_double_sign(&validators, &header_1, &header_2).unwrap();
+        assert_eq!(
+            evidence.offenders,
+            vec![Address::from([0; 20]), Address::from([1; 20])]
+        );
+        assert!(evidence.verify(&validators).is_err());
+
+        // while the header pair is still checked
+        let err = extract_double_sign(&validators, &header_1, &header_1).unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidEvidence { .. }));
+    }
+
+    #[test]
+    fn encodes_evidence() {
+        let (keys, validators) = generate_validators(4);
+        let validators = ValidatorSet::from(validators);
+        let evidence = detect_double_sign(
+            &validators,
+            &signed_header(&keys, 5, Hash::from([1; 32]), 0b0111),
+            &signed_header(&keys, 5, Hash::from([2; 32]), 0b1110),
+        )
+        .unwrap();
+
+        let decoded = DoubleSignEvidence::from_rlp(&evidence.to_rlp()).unwrap();
+        assert_eq!(decoded, evidence);
+
+        let json = serde_json::to_string(&evidence).unwrap();
+        let decoded: DoubleSignEvidence = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, evidence);
+
+        // offenders list must match the seals
+        let mut forged = evidence;
+        forged.offenders.push(Address::from([0; 20]));
+        let err = forged.verify(&validators).unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidEvidence { .. }));
+    }
+
+    fn signed_header(keys: &[PrivateKey], number: u64, root: Hash, bitmap: u64) -> Header {
+        let mut header = Header::new();
+        header.number = Integer::from(number);
+        header.root = root;
+        header.extra = extra(IstanbulAggregatedSeal::new());
+
+        // aggregated seal is not a part of the header hash
+        let hash = header.hash().unwrap();
+        header.extra = extra(sign_aggregated_seal(keys, hash, bitmap, 0));
+
+        header
+    }
+
+    fn extra(aggregated_seal: IstanbulAggregatedSeal) -> Vec<u8> {
+        IstanbulExtra {
+            added_validators: Vec::new(),
+            added_validators_public_keys: Vec::new(),
+            removed_validators: ValidatorBitmap::new(),
+            seal: Vec::new(),
+            aggregated_seal,
+            parent_aggregated_seal: IstanbulAggregatedSeal::new(),
+        }
+        .to_rlp(&IstanbulExtraVanity::default())
+    }
+
+    fn arb_evidence() -> impl Strategy<Value = DoubleSignEvidence> {
+        (
+            any::<u64>(),
+            arb_hash(),
+            arb_aggregated_seal(),
+            arb_hash(),
+            arb_aggregated_seal(),
+            vec(arb_address(), 0..8),
+        )
+            .prop_map(
+                |(number, hash_1, aggregated_seal_1, hash_2, aggregated_seal_2, offenders)| {
+                    DoubleSignEvidence {
+                        number,
+                        hash_1,
+                        aggregated_seal_1,
+                        hash_2,
+                        aggregated_seal_2,
+                        offenders,
+                    }
+                },
+            )
+    }
+
+    proptest! {
+        #[test]
+        fn evidence_rlp_round_trip(evidence in arb_evidence()) {
+            let decoded = DoubleSignEvidence::from_rlp(&evidence.to_rlp()).unwrap();
+
+            prop_assert_eq!(&decoded, &evidence);
+        }
+    }
+}
@@ -1,7 +1,8 @@
 use crate::errors::Error;
-use crate::traits::FromBytes;
 use crate::types::header::Header;
-use crate::types::istanbul::{IstanbulAggregatedSeal, IstanbulExtra, IstanbulExtraVanity};
+use crate::types::istanbul::{
+    IstanbulAggregatedSeal, IstanbulExtra, IstanbulExtraVanity, ISTANBUL_EXTRA_VANITY_LENGTH,
+};
 
 // Retrieves the block number within an epoch. The return value will be 1-based.
 // There is a special case if the number == 0. It is basically the last block of the 0th epoch,
@@ -53,7 +54,11 @@ pub fn istanbul_filtered_header(header: &Header, keep_seal: bool) -> Result<Head
     }
     extra.aggregated_seal = IstanbulAggregatedSeal::new();
 
-    let payload = extra.to_rlp(IstanbulExtraVanity::from_bytes(&new_header.extra)?);
+    // NOTE: the extra-data is at least vanity long, otherwise it wouldn't decode
+    let mut vanity = IstanbulExtraVanity::default();
+    vanity.copy_from_slice(&new_header.extra[..ISTANBUL_EXTRA_VANITY_LENGTH]);
+
+    let payload = extra.to_rlp(&vanity);
     new_header.extra = payload;
 
     Ok(new_header)
@@ -66,17 +71,48 @@ pub fn is_last_block_of_epoch(number: u64, epoch_size: u64) -> bool {
 pub fn min_quorum_size(total_validators: usize) -> usize {
     // non-float equivalent of:
     //  ((2.0*(total_validators as f64) / 3.0) as f64).ceil() as usize
-    ((2 * total_validators) - 1 + 3) / 3
+    //
+    // NOTE: the formula is written so that it doesn't underflow for an empty validator set
+    (2 * total_validators + 2) / 3
+}
+
+/// Returns the 2F+1 quorum of the legacy IBFT chains, where F = (N-1)/3 is the number of the
+/// faulty validators tolerated by the set of N validators
+pub fn byzantine_quorum_size(total_validators: usize) -> usize {
+    match total_validators {
+        0 => 0,
+        n => 2 * ((n - 1) / 3) + 1,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn validates_byzantine_quorum_size_math() {
+        for (validator_set_size, expected_quorum_size) in [
+            (0, 0),
+            (1, 1),
+            (2, 1),
+            (3, 1),
+            (4, 3),
+            (5, 3),
+            (6, 3),
+            (7, 5),
+        ] {
+            assert_eq!(
+                byzantine_quorum_size(validator_set_size),
+                expected_quorum_size
+            );
+        }
+    }
+
     #[test]
     fn validates_quorum_size_math() {
         for (validator_set_size, expected_min_quorum_size) in vec![
-            (1 as usize, 1 as usize),
+            (0 as usize, 0 as usize),
+            (1, 1),
             (2, 2),
             (3, 2),
             (4, 3),
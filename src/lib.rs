@@ -7,8 +7,19 @@
 mod types;
 mod serialization;
 mod state;
+mod clock;
+mod engine;
 mod istanbul;
 mod bls;
+mod uptime;
+mod evidence;
+mod certificate;
+#[cfg(any(test, feature = "ecdsa"))]
+mod ecdsa;
+mod trie;
+mod proof;
+mod registry;
+mod slots;
 mod traits;
 mod macros;
 mod errors;
@@ -25,29 +36,82 @@ extern crate anomaly;
 extern crate thiserror;
 
 pub use types::{
+    bitmap::ValidatorBitmap,
     header::Header,
     header::Address,
     header::Hash,
+    header::Bloom,
     istanbul::SerializedPublicKey,
     istanbul::IstanbulExtra,
+    istanbul::IstanbulAggregatedSeal,
+    istanbul::ConsensusExtra,
+    istanbul::ValidatorSetChange,
+    istanbul::CommittedSeal,
+    istanbul::IstanbulMsg,
+    consensus::View,
+    consensus::Subject,
+    consensus::CommittedSubject,
+    consensus::Proposal,
+    consensus::Message,
+    consensus::PreparedCertificate,
+    consensus::RoundChangeCertificate,
+    consensus::PrePrepare,
+    consensus::RoundChange,
     state::Validator,
     state::Snapshot,
-    state::Config
+    state::Config,
+    state::TrustThreshold,
+    validator_set::ValidatorSet
 };
 pub use istanbul::{
     get_epoch_number,
     get_epoch_first_block_number,
     get_epoch_last_block_number,
+    min_quorum_size,
+    byzantine_quorum_size,
 };
 pub use state::State;
+pub use clock::{FixedClock, SystemClock, Timestamp};
+pub use engine::{verify_committed_seals, CeloEngine};
+#[cfg(feature = "ecdsa")]
+pub use ecdsa::{pubkey_to_address, Secp256k1Recovery};
 pub use errors::{Error, Kind};
 pub use traits::{
-    FromBytes,
-    DefaultFrom,
     ToRlp,
-    FromRlp
+    FromRlp,
+    FromRlpStrict,
+    ToJson,
+    FromJson,
+    Clock,
+    SealVerifier,
+    IstanbulEngine,
+    SignerRecovery
 };
-pub use bls::verify_aggregated_seal;
+pub use bls::{
+    validate_aggregated_seal,
+    verify_aggregated_seal,
+    verify_aggregated_signature,
+    BlsSealVerifier,
+};
+pub use certificate::{
+    verify_prepared_certificate,
+    verify_round_change_certificate,
+    verify_preprepare,
+};
+pub use slots::{
+    add_to_slot,
+    array_element_slot,
+    dynamic_data_slot,
+    mapping_slot,
+    nested_mapping_slot,
+    slot_from_u64,
+    SlotKey,
+};
+pub use registry::{registry_slot, resolve_contract_address, REGISTRY_ADDRESS};
+pub use proof::{verify_account_proof, verify_proof, verify_storage_proof, Account};
+pub use trie::{derive_sha, verify_block_body, Trie};
+pub use evidence::{detect_double_sign, extract_double_sign, DoubleSignEvidence};
+pub use uptime::{uptime_report, UptimeReport, ValidatorUptime};
 
 #[cfg(feature = "wasm-contract")]
 pub mod contract;
@@ -1,28 +1,178 @@
-/// Returns a reference to the elements of `$slice` as an array, verifying that
-/// the slice is of length `$len`.
+/// Declares a fixed-size byte array newtype (ie. Hash, Address) along with the conversions
+/// from/to bytes, the 0x prefixed hex formatting and the serde/RLP codecs.
 ///
-/// source: https://github.com/rust-lang/rfcs/issues/1833
+/// The `Display`/`FromStr` implementations use the lowercase hex string, unless the type is
+/// declared with the `@custom_display` marker, in which case it has to provide its own.
 #[macro_export]
-macro_rules! slice_as_array_ref {
-    ($slice:expr, $len:expr) => {
-        {
-            use crate::errors::{Error, Kind};
-
-            fn slice_as_array_ref<T>(slice: &[T]) -> Result<&[T; $len], Error> {
-                if slice.len() != $len {
-                    return Err(
-                        Kind::InvalidDataLength {
-                            expected: $len,
-                            current: slice.len(),
-                        }
-                        .into()
-                    );
+#[doc(hidden)]
+macro_rules! fixed_bytes {
+    (@custom_display $(#[$attr:meta])* pub struct $name:ident($len:expr);) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name(pub [u8; $len]);
+
+        impl $name {
+            /// Number of bytes of the value
+            pub const LENGTH: usize = $len;
+
+            /// Creates the value from the slice, that must be exactly `LENGTH` bytes long
+            pub fn from_slice(data: &[u8]) -> Result<Self, $crate::Error> {
+                if data.len() != $len {
+                    return Err($crate::Kind::InvalidDataLength {
+                        current: data.len(),
+                        expected: $len,
+                    }
+                    .into());
+                }
+
+                let mut value = Self::default();
+                value.0.copy_from_slice(data);
+
+                Ok(value)
+            }
+
+            /// Parses the hex string (case insensitive, with an optional 0x prefix)
+            pub fn from_hex(value: &str) -> Result<Self, $crate::Error> {
+                let digits = value.strip_prefix("0x").unwrap_or(value);
+                let data = hex::decode(digits)
+                    .map_err(|e| $crate::Kind::InvalidHexString.context(e))?;
+
+                Self::from_slice(&data)
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                $name([0; $len])
+            }
+        }
+
+        impl From<[u8; $len]> for $name {
+            fn from(data: [u8; $len]) -> Self {
+                $name(data)
+            }
+        }
+
+        impl From<$name> for [u8; $len] {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl std::convert::TryFrom<&[u8]> for $name {
+            type Error = $crate::Error;
+
+            fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+                Self::from_slice(data)
+            }
+        }
+
+        impl AsRef<[u8]> for $name {
+            fn as_ref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        impl AsMut<[u8]> for $name {
+            fn as_mut(&mut self) -> &mut [u8] {
+                &mut self.0
+            }
+        }
+
+        impl std::ops::Deref for $name {
+            type Target = [u8];
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl std::ops::DerefMut for $name {
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self.0
+            }
+        }
+
+        impl std::fmt::LowerHex for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                if f.alternate() {
+                    f.write_str("0x")?;
+                }
+                for byte in self.0.iter() {
+                    write!(f, "{:02x}", byte)?;
+                }
+
+                Ok(())
+            }
+        }
+
+        impl std::fmt::Debug for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                std::fmt::Display::fmt(self, f)
+            }
+        }
+
+        // NOTE: serialized as the lowercase hex string (the format of the Celo JSON-RPC API),
+        // while on deserialization the case doesn't matter
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(&format!("{:#x}", self))
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                use serde::de::Error;
+
+                let s: &str = serde::Deserialize::deserialize(deserializer)?;
+                if !s.starts_with("0x") {
+                    return Err(D::Error::custom(format!(
+                        "hex string should start with '0x', got: {}",
+                        s
+                    )));
                 }
-                Ok(unsafe {
-                    &*(slice.as_ptr() as *const [T; $len])
+
+                Self::from_hex(s).map_err(D::Error::custom)
+            }
+        }
+
+        impl rlp::Encodable for $name {
+            fn rlp_append(&self, s: &mut rlp::RlpStream) {
+                s.encoder().encode_value(&self.0);
+            }
+        }
+
+        impl rlp::Decodable for $name {
+            fn decode(rlp: &rlp::Rlp) -> Result<Self, rlp::DecoderError> {
+                rlp.decoder().decode_value(|data| match data.len() {
+                    len if len < $len => Err(rlp::DecoderError::RlpIsTooShort),
+                    len if len > $len => Err(rlp::DecoderError::RlpIsTooBig),
+                    _ => {
+                        let mut value = Self::default();
+                        value.0.copy_from_slice(data);
+
+                        Ok(value)
+                    }
                 })
             }
-            slice_as_array_ref($slice)
         }
-    }
+    };
+
+    ($(#[$attr:meta])* pub struct $name:ident($len:expr);) => {
+        $crate::fixed_bytes!(@custom_display $(#[$attr])* pub struct $name($len););
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{:#x}", self)
+            }
+        }
+
+        impl std::str::FromStr for $name {
+            type Err = $crate::Error;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Self::from_hex(s)
+            }
+        }
+    };
 }
@@ -0,0 +1,290 @@
+use crate::errors::{Error, Kind};
+use crate::serialization::rlp::rlp_to_big_int;
+use crate::traits::FromRlp;
+use crate::trie::{keccak, to_nibbles};
+use crate::types::header::{Address, Hash, HASH_LENGTH};
+use num_bigint::{BigInt as Integer, Sign};
+use rlp::{Decodable, DecoderError, Rlp};
+
+/// Account is the state of an account stored in the state trie
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Account {
+    pub nonce: u64,
+
+    #[serde(with = "crate::serialization::bytes::hexbigint")]
+    pub balance: Integer,
+
+    /// Root of the account storage trie
+    pub storage_root: Hash,
+
+    pub code_hash: Hash,
+}
+
+impl Decodable for Account {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        if rlp.item_count()? != 4 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+
+        Ok(Account {
+            nonce: rlp.val_at(0)?,
+            balance: rlp_to_big_int(rlp, 1)?,
+            storage_root: rlp.val_at(2)?,
+            code_hash: rlp.val_at(3)?,
+        })
+    }
+}
+
+impl FromRlp for Account {
+    fn from_rlp(bytes: &[u8]) -> Result<Self, Error> {
+        rlp::decode(&bytes).map_err(|e| Kind::RlpDecodeError.context(e).into())
+    }
+}
+
+/// Verifies the Merkle-Patricia proof (list of RLP encoded nodes, starting from the root) of the
+/// key against the trie root. Returns the stored value or None if the proof shows the key is absent.
+pub fn verify_proof(root: &Hash, key: &[u8], proof: &[Vec<u8>]) -> Result<Option<Vec<u8>>, Error> {
+    // the empty trie doesn't contain any nodes
+    if root == &keccak(&rlp::NULL_RLP) {
+        return Ok(None);
+    }
+
+    let path = to_nibbles(key);
+    let mut nodes = proof.iter();
+    let mut node = next_hashed_node(root, &mut nodes)?.clone();
+    let mut depth = 0;
+
+    loop {
+        let rlp = Rlp::new(&node);
+        let item_count = rlp
+            .item_count()
+            .map_err(|e| Kind::RlpDecodeError.context(e))?;
+
+        let child = match item_count {
+            17 => {
+                let nibble = match path.get(depth) {
+                    Some(nibble) => *nibble as usize,
+                    None => return decode_value(&rlp_at(&rlp, 16)?),
+                };
+
+                depth += 1;
+                rlp_at(&rlp, nibble)?
+            }
+            2 => {
+                let (partial_path, leaf) = decode_hex_prefix(rlp_data(&rlp_at(&rlp, 0)?)?)?;
+                let remaining = &path[depth..];
+
+                if leaf {
+                    if remaining == partial_path.as_slice() {
+                        return decode_value(&rlp_at(&rlp, 1)?);
+                    }
+
+                    return Ok(None);
+                }
+
+                if !remaining.starts_with(&partial_path) {
+                    return Ok(None);
+                }
+
+                depth += partial_path.len();
+                rlp_at(&rlp, 1)?
+            }
+            _ => {
+                return Err(Kind::InvalidProof {
+                    msg: "unexpected trie node",
+                }
+                .into())
+            }
+        };
+
+        node = if child.is_list() {
+            // nodes shorter than 32 bytes are embedded in the parent
+            child.as_raw().to_vec()
+        } else {
+            let data = rlp_data(&child)?;
+            if data.is_empty() {
+                return Ok(None);
+            }
+
+            if data.len() != HASH_LENGTH {
+                return Err(Kind::InvalidProof {
+                    msg: "invalid node reference",
+                }
+                .into());
+            }
+
+            let mut hash = Hash::default();
+            hash.copy_from_slice(data);
+            next_hashed_node(&hash, &mut nodes)?.clone()
+        };
+    }
+}
+
+/// Verifies the account proof against the state root. Returns None for non-existing account.
+pub fn verify_account_proof(
+    state_root: &Hash,
+    address: &Address,
+    proof: &[Vec<u8>],
+) -> Result<Option<Account>, Error> {
+    match verify_proof(state_root, &keccak(address), proof)? {
+        Some(value) => Ok(Some(Account::from_rlp(&value)?)),
+        None => Ok(None),
+    }
+}
+
+/// Verifies the storage proof against the account storage root. Returns the value stored under
+/// the slot, where unset slots are zero.
+pub fn verify_storage_proof(
+    storage_root: &Hash,
+    slot: &Hash,
+    proof: &[Vec<u8>],
+) -> Result<Integer, Error> {
+    match verify_proof(storage_root, &keccak(slot), proof)? {
+        Some(value) => {
+            let data: Vec<u8> = rlp::decode(&value).map_err(|e| Kind::RlpDecodeError.context(e))?;
+            if data.len() > HASH_LENGTH {
+                return Err(Kind::InvalidProof {
+                    msg: "storage value is longer than 32 bytes",
+                }
+                .into());
+            }
+
+            Ok(Integer::from_bytes_be(Sign::Plus, &data))
+        }
+        None => Ok(Integer::from(0)),
+    }
+}
+
+fn next_hashed_node<'a>(
+    hash: &Hash,
+    nodes: &mut impl Iterator<Item = &'a Vec<u8>>,
+) -> Result<&'a Vec<u8>, Error> {
+    let node = match nodes.next() {
+        Some(node) => node,
+        None => {
+            return Err(Kind::InvalidProof {
+                msg: "missing proof node",
+            }
+            .into())
+        }
+    };
+
+    if &keccak(node) != hash {
+        return Err(Kind::InvalidProof {
+            msg: "proof node hash mismatch",
+        }
+        .into());
+    }
+
+    Ok(node)
+}
+
+fn decode_value(rlp: &Rlp) -> Result<Option<Vec<u8>>, Error> {
+    let value = rlp_data(rlp)?;
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(value.to_vec()))
+}
+
+fn decode_hex_prefix(data: &[u8]) -> Result<(Vec<u8>, bool), Error> {
+    let (flag, nibbles) = match to_nibbles(data).split_first() {
+        Some((flag, nibbles)) => (*flag, nibbles.to_vec()),
+        None => {
+            return Err(Kind::InvalidProof {
+                msg: "empty node path",
+            }
+            .into())
+        }
+    };
+
+    let leaf = flag & 0x2 != 0;
+    let odd = flag & 0x1 != 0;
+    if flag > 3 || (!odd && nibbles.first() != Some(&0)) {
+        return Err(Kind::InvalidProof {
+            msg: "invalid node path encoding",
+        }
+        .into());
+    }
+
+    if odd {
+        Ok((nibbles, leaf))
+    } else {
+        Ok((nibbles[1..].to_vec(), leaf))
+    }
+}
+
+fn rlp_at<'a>(rlp: &Rlp<'a>, index: usize) -> Result<Rlp<'a>, Error> {
+    rlp.at(index)
+        .map_err(|e| Kind::RlpDecodeError.context(e).into())
+}
+
+fn rlp_data<'a>(rlp: &Rlp<'a>) -> Result<&'a [u8], Error> {
+    rlp.data()
+        .map_err(|e| Kind::RlpDecodeError.context(e).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trie::Trie;
+
+    #[test]
+    fn verifies_proofs() {
+        let mut trie = Trie::new();
+        for i in 0..200u32 {
+            trie.insert(&keccak(&i.to_be_bytes()), &rlp::encode(&u64::from(i + 1)));
+        }
+        let root = trie.root_hash();
+
+        for i in 0..200u32 {
+            let key = keccak(&i.to_be_bytes());
+            let value = verify_proof(&root, &key, &trie.proof(&key)).unwrap();
+
+            assert_eq!(value, Some(rlp::encode(&u64::from(i + 1))));
+        }
+
+        // absence proof
+        let key = keccak(&1000u32.to_be_bytes());
+        assert_eq!(verify_proof(&root, &key, &trie.proof(&key)).unwrap(), None);
+
+        // proof of another key
+        let key = keccak(&1u32.to_be_bytes());
+        let other_key = keccak(&2u32.to_be_bytes());
+        assert!(verify_proof(&root, &key, &trie.proof(&other_key)).is_err());
+
+        // tampered proof
+        let mut proof = trie.proof(&key);
+        let last = proof.len() - 1;
+        proof[last][3] ^= 1;
+        let err = verify_proof(&root, &key, &proof).unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidProof { .. }));
+
+        // missing nodes
+        let err = verify_proof(&root, &key, &proof[..1]).unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidProof { .. }));
+    }
+
+    #[test]
+    fn verifies_short_keys() {
+        // short keys produce nodes embedded in the parent node
+        let mut trie = Trie::new();
+        trie.insert(b"do", b"verb");
+        trie.insert(b"dog", b"puppy");
+        trie.insert(b"doge", b"coin");
+        trie.insert(b"horse", b"stallion");
+        let root = trie.root_hash();
+
+        for (key, value) in &[("do", "verb"), ("dog", "puppy"), ("doge", "coin")] {
+            let proof = trie.proof(key.as_bytes());
+            assert_eq!(
+                verify_proof(&root, key.as_bytes(), &proof).unwrap(),
+                Some(value.as_bytes().to_vec())
+            );
+        }
+
+        let proof = trie.proof(b"dogs");
+        assert_eq!(verify_proof(&root, b"dogs", &proof).unwrap(), None);
+    }
+}
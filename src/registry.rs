@@ -0,0 +1,135 @@
+use crate::errors::{Error, Kind};
+use crate::proof::{verify_account_proof, verify_storage_proof};
+use crate::slots::{mapping_slot, slot_from_u64, SlotKey};
+use crate::trie::keccak;
+use crate::types::header::{Address, Hash, Header, ADDRESS_LENGTH};
+use num_traits::Zero;
+
+/// REGISTRY_ADDRESS is the fixed address of the Celo Registry (proxy) contract
+pub const REGISTRY_ADDRESS: Address = Address([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0xce, 0x10,
+]);
+
+/// REGISTRY_MAPPING_SLOT is the storage slot of the `mapping(bytes32 => address) registry`.
+/// Slot 0 is shared by the Ownable owner and the Initializable flag.
+pub const REGISTRY_MAPPING_SLOT: u64 = 1;
+
+/// Returns the storage slot that holds the address of the contract registered under the name
+pub fn registry_slot(name: &str) -> Hash {
+    mapping_slot(
+        &slot_from_u64(REGISTRY_MAPPING_SLOT),
+        &SlotKey::Word(keccak(name.as_bytes())),
+    )
+}
+
+/// Resolves the Celo core contract name (ie. "Election") into its address. The account proof of
+/// the Registry is verified against the (already verified) header state root, then the storage
+/// proof of the registry slot is verified against the Registry storage root.
+pub fn resolve_contract_address(
+    header: &Header,
+    name: &str,
+    account_proof: &[Vec<u8>],
+    storage_proof: &[Vec<u8>],
+) -> Result<Address, Error> {
+    let account = match verify_account_proof(&header.root, &REGISTRY_ADDRESS, account_proof)? {
+        Some(account) => account,
+        None => {
+            return Err(Kind::InvalidProof {
+                msg: "registry account doesn't exist",
+            }
+            .into())
+        }
+    };
+
+    let value = verify_storage_proof(&account.storage_root, &registry_slot(name), storage_proof)?;
+    if value.is_zero() {
+        return Err(Kind::ContractNotRegistered.into());
+    }
+
+    let (_, bytes) = value.to_bytes_be();
+    if bytes.len() > ADDRESS_LENGTH {
+        return Err(Kind::InvalidProof {
+            msg: "registry value is not an address",
+        }
+        .into());
+    }
+
+    let mut address = Address::default();
+    address[ADDRESS_LENGTH - bytes.len()..].copy_from_slice(&bytes);
+
+    Ok(address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trie::Trie;
+    use crate::types::header::HASH_LENGTH;
+    use rlp::RlpStream;
+
+    const ELECTION_ADDRESS: Address = Address([0x8d; ADDRESS_LENGTH]);
+
+    #[test]
+    fn derives_registry_slot() {
+        // keccak256(abi.encode(keccak256("Election"), 1))
+        let mut expected = keccak(b"Election").to_vec();
+        expected.extend_from_slice(&[0; 31]);
+        expected.push(1);
+
+        assert_eq!(registry_slot("Election"), keccak(&expected));
+    }
+
+    #[test]
+    fn resolves_contract_address() {
+        let (header, account_proof, storage_trie) = registry_state();
+
+        let storage_proof = storage_trie.proof(&keccak(&registry_slot("Election")));
+        let address =
+            resolve_contract_address(&header, "Election", &account_proof, &storage_proof).unwrap();
+        assert_eq!(address, ELECTION_ADDRESS);
+
+        // the proof doesn't match the name
+        assert!(
+            resolve_contract_address(&header, "Accounts", &account_proof, &storage_proof).is_err()
+        );
+
+        // absent name
+        let storage_proof = storage_trie.proof(&keccak(&registry_slot("Accounts")));
+        let err = resolve_contract_address(&header, "Accounts", &account_proof, &storage_proof)
+            .unwrap_err();
+        assert!(matches!(err.kind(), Kind::ContractNotRegistered));
+
+        // account proof of different state
+        let mut header = header;
+        header.root = Hash::from([1; HASH_LENGTH]);
+        let err = resolve_contract_address(&header, "Election", &account_proof, &storage_proof)
+            .unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidProof { .. }));
+    }
+
+    fn registry_state() -> (Header, Vec<Vec<u8>>, Trie) {
+        let mut storage_trie = Trie::new();
+        storage_trie.insert(
+            &keccak(&registry_slot("Election")),
+            &rlp::encode(&ELECTION_ADDRESS.to_vec()),
+        );
+        storage_trie.insert(&keccak(&[0; HASH_LENGTH]), &rlp::encode(&vec![0xff; 21]));
+
+        let mut account = RlpStream::new_list(4);
+        account.append(&1u64);
+        account.append(&0u64);
+        account.append(&storage_trie.root_hash().as_ref());
+        account.append(&keccak(b"code").as_ref());
+
+        let mut state_trie = Trie::new();
+        state_trie.insert(&keccak(&REGISTRY_ADDRESS), &account.out());
+        state_trie.insert(&keccak(&[1; ADDRESS_LENGTH]), &[0xc0]);
+
+        let mut header = Header::new();
+        header.root = state_trie.root_hash();
+
+        let account_proof = state_trie.proof(&keccak(&REGISTRY_ADDRESS));
+        (header, account_proof, storage_trie)
+    }
+}
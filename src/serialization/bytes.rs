@@ -93,53 +93,38 @@ pub(crate) mod hexbigint {
     }
 }
 
-pub(crate) mod hexvec {
-    use crate::traits::FromBytes;
+#[cfg(feature = "wasm-contract")]
+pub(crate) mod hexbytesvec {
     use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
 
-    /// Deserialize vector into Vec<T>
-    pub(crate) fn deserialize<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+    /// Deserialize vector of strings into Vec<Vec<u8>>
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Vec<Vec<u8>>, D::Error>
     where
         D: Deserializer<'de>,
-        T: FromBytes + Clone,
     {
         let items: Vec<String> = Deserialize::deserialize(deserializer)?;
-        let mut out: Vec<T> = Vec::new();
 
-        for item in items {
-            if item.len() <= 2 || !item.starts_with("0x") {
-                return Err(D::Error::custom(format!(
-                    "hex string should start with '0x', got: {}",
-                    item
-                )));
-            }
-
-            match hex::decode(&item[2..]) {
-                Ok(decoded) => match T::from_bytes(&decoded) {
-                    Ok(concrete) => out.push(concrete.to_owned()),
-                    Err(e) => {
-                        return Err(D::Error::custom(format!(
-                            "failed to call from_bytes, got: {}",
-                            e
-                        )))
-                    }
-                },
-                Err(e) => {
+        items
+            .iter()
+            .map(|item| {
+                if item.len() < 2 || !item.starts_with("0x") {
                     return Err(D::Error::custom(format!(
-                        "failed to decode hex data, got: {}",
-                        e
-                    )))
+                        "hex string should start with '0x', got: {}",
+                        item
+                    )));
                 }
-            }
-        }
-        Ok(out)
+
+                hex::decode(&item[2..]).map_err(|e| {
+                    D::Error::custom(format!("failed to decode hex data, got: {}", e))
+                })
+            })
+            .collect()
     }
 
-    /// Serialize from &[T] into vector of strings
-    pub(crate) fn serialize<S, T>(value: &[T], serializer: S) -> Result<S::Ok, S::Error>
+    /// Serialize from &[Vec<u8>] into vector of strings
+    pub(crate) fn serialize<S>(value: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
-        T: AsRef<[u8]>,
     {
         value
             .iter()
@@ -0,0 +1,622 @@
+use crate::errors::{Error, Kind};
+use num_bigint::{BigInt as Integer, BigUint};
+use num_traits::Num;
+use std::convert::TryFrom;
+
+// The module implements a minimal JSON codec, that doesn't rely on serde. CosmWasm rejects
+// binaries with floating point operations, and serde JSON deserializers pull them in even if the
+// decoded types don't contain any floats (see: notes in `contract/mod.rs`).
+//
+// Numbers are never converted to floats, instead they're kept as text and parsed as integers
+// on demand.
+
+/// MAX_DEPTH limits the nesting of the arrays and objects
+const MAX_DEPTH: usize = 16;
+
+/// JsonValue is a parsed JSON document
+#[derive(Clone, PartialEq, Debug)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+/// JsonCodec converts the type from/to the JSON document
+pub(crate) trait JsonCodec: Sized {
+    fn to_json_value(&self) -> JsonValue;
+    fn from_json_value(value: &JsonValue) -> Result<Self, Error>;
+}
+
+impl JsonValue {
+    pub fn parse(input: &[u8]) -> Result<Self, Error> {
+        let mut parser = Parser { input, pos: 0 };
+
+        let value = parser.parse_value(0)?;
+        parser.skip_whitespace();
+        if parser.pos != input.len() {
+            return Err(json_error("unexpected trailing characters"));
+        }
+
+        Ok(value)
+    }
+
+    pub fn encode(&self) -> String {
+        let mut out = String::new();
+        self.write(&mut out);
+
+        out
+    }
+
+    pub fn object(fields: Vec<(&str, JsonValue)>) -> Self {
+        JsonValue::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+        )
+    }
+
+    /// Creates the 0x prefixed hex string, where empty data is encoded as empty string
+    pub fn hex(data: &[u8]) -> Self {
+        if data.is_empty() {
+            return JsonValue::String(String::new());
+        }
+
+        JsonValue::String(format!("0x{}", hex::encode(data)))
+    }
+
+    pub fn hex_u64(value: u64) -> Self {
+        JsonValue::String(format!("0x{:x}", value))
+    }
+
+    pub fn hex_big_int(value: &Integer) -> Self {
+        JsonValue::String(format!("0x{:x}", value))
+    }
+
+    pub fn hex_big_uint(value: &BigUint) -> Self {
+        JsonValue::String(format!("0x{:x}", value))
+    }
+
+    pub fn u64(value: u64) -> Self {
+        JsonValue::Number(value.to_string())
+    }
+
+    pub fn field(&self, name: &'static str) -> Result<&JsonValue, Error> {
+        match self {
+            JsonValue::Object(fields) => fields
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value)
+                .ok_or_else(|| {
+                    Kind::JsonDecodeError {
+                        msg: "missing field",
+                    }
+                    .context(name)
+                    .into()
+                }),
+            _ => Err(json_error("expected object")),
+        }
+    }
+
+    pub fn as_str(&self) -> Result<&str, Error> {
+        match self {
+            JsonValue::String(value) => Ok(value),
+            _ => Err(json_error("expected string")),
+        }
+    }
+
+    pub fn as_array(&self) -> Result<&[JsonValue], Error> {
+        match self {
+            JsonValue::Array(values) => Ok(values),
+            _ => Err(json_error("expected array")),
+        }
+    }
+
+    /// Parses the non-negative integer number
+    pub fn as_u64(&self) -> Result<u64, Error> {
+        let text = match self {
+            JsonValue::Number(text) => text,
+            _ => return Err(json_error("expected number")),
+        };
+
+        if !text.bytes().all(|c| c.is_ascii_digit()) {
+            return Err(json_error("expected unsigned integer"));
+        }
+
+        text.parse().map_err(|e| {
+            Kind::JsonDecodeError {
+                msg: "invalid integer",
+            }
+            .context(e)
+            .into()
+        })
+    }
+
+    /// Decodes the hex string, where both empty string and "0x" stand for empty data
+    pub fn as_hex(&self) -> Result<Vec<u8>, Error> {
+        let value = self.as_str()?;
+        if value.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        hex::decode(strip_hex_prefix(value)?).map_err(|e| {
+            Kind::JsonDecodeError {
+                msg: "invalid hex string",
+            }
+            .context(e)
+            .into()
+        })
+    }
+
+    /// Decodes the hex string into the fixed size type (ie. Hash)
+    pub fn as_hex_bytes<T>(&self) -> Result<T, Error>
+    where
+        T: for<'a> TryFrom<&'a [u8], Error = Error>,
+    {
+        T::try_from(&self.as_hex()?)
+    }
+
+    pub fn as_hex_u64(&self) -> Result<u64, Error> {
+        u64::from_str_radix(strip_hex_quantity(self.as_str()?)?, 16)
+            .map_err(|_| json_error("invalid hex number"))
+    }
+
+    pub fn as_hex_big_int(&self) -> Result<Integer, Error> {
+        Integer::from_str_radix(strip_hex_quantity(self.as_str()?)?, 16)
+            .map_err(|_| json_error("invalid hex number"))
+    }
+
+    pub fn as_hex_big_uint(&self) -> Result<BigUint, Error> {
+        BigUint::from_str_radix(strip_hex_quantity(self.as_str()?)?, 16)
+            .map_err(|_| json_error("invalid hex number"))
+    }
+
+    fn write(&self, out: &mut String) {
+        match self {
+            JsonValue::Null => out.push_str("null"),
+            JsonValue::Bool(true) => out.push_str("true"),
+            JsonValue::Bool(false) => out.push_str("false"),
+            JsonValue::Number(text) => out.push_str(text),
+            JsonValue::String(value) => write_string(value, out),
+            JsonValue::Array(values) => {
+                out.push('[');
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    value.write(out);
+                }
+                out.push(']');
+            }
+            JsonValue::Object(fields) => {
+                out.push('{');
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    write_string(key, out);
+                    out.push(':');
+                    value.write(out);
+                }
+                out.push('}');
+            }
+        }
+    }
+}
+
+impl<T: JsonCodec> JsonCodec for Vec<T> {
+    fn to_json_value(&self) -> JsonValue {
+        JsonValue::Array(self.iter().map(|item| item.to_json_value()).collect())
+    }
+
+    fn from_json_value(value: &JsonValue) -> Result<Self, Error> {
+        value.as_array()?.iter().map(T::from_json_value).collect()
+    }
+}
+
+/// Encodes the list of fixed size types as the array of hex strings
+pub(crate) fn hex_vec_to_json<T: AsRef<[u8]>>(items: &[T]) -> JsonValue {
+    JsonValue::Array(
+        items
+            .iter()
+            .map(|item| JsonValue::hex(item.as_ref()))
+            .collect(),
+    )
+}
+
+/// Decodes the array of hex strings into the list of fixed size types
+pub(crate) fn hex_vec_from_json<T>(value: &JsonValue) -> Result<Vec<T>, Error>
+where
+    T: for<'a> TryFrom<&'a [u8], Error = Error>,
+{
+    value
+        .as_array()?
+        .iter()
+        .map(|item| item.as_hex_bytes())
+        .collect()
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn parse_value(&mut self, depth: usize) -> Result<JsonValue, Error> {
+        if depth > MAX_DEPTH {
+            return Err(json_error("document is nested too deeply"));
+        }
+
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.parse_object(depth),
+            Some(b'[') => self.parse_array(depth),
+            Some(b'"') => Ok(JsonValue::String(self.parse_string()?)),
+            Some(b't') => self.parse_literal("true", JsonValue::Bool(true)),
+            Some(b'f') => self.parse_literal("false", JsonValue::Bool(false)),
+            Some(b'n') => self.parse_literal("null", JsonValue::Null),
+            Some(b'-') | Some(b'0'..=b'9') => self.parse_number(),
+            Some(_) => Err(json_error("unexpected character")),
+            None => Err(json_error("unexpected end of input")),
+        }
+    }
+
+    fn parse_object(&mut self, depth: usize) -> Result<JsonValue, Error> {
+        self.expect(b'{')?;
+        let mut fields: Vec<(String, JsonValue)> = Vec::new();
+
+        self.skip_whitespace();
+        if self.consume(b'}') {
+            return Ok(JsonValue::Object(fields));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            if fields.iter().any(|(existing, _)| existing == &key) {
+                return Err(json_error("duplicate object key"));
+            }
+
+            self.skip_whitespace();
+            self.expect(b':')?;
+            let value = self.parse_value(depth + 1)?;
+            fields.push((key, value));
+
+            self.skip_whitespace();
+            if self.consume(b'}') {
+                return Ok(JsonValue::Object(fields));
+            }
+            self.expect(b',')?;
+        }
+    }
+
+    fn parse_array(&mut self, depth: usize) -> Result<JsonValue, Error> {
+        self.expect(b'[')?;
+        let mut values = Vec::new();
+
+        self.skip_whitespace();
+        if self.consume(b']') {
+            return Ok(JsonValue::Array(values));
+        }
+
+        loop {
+            values.push(self.parse_value(depth + 1)?);
+
+            self.skip_whitespace();
+            if self.consume(b']') {
+                return Ok(JsonValue::Array(values));
+            }
+            self.expect(b',')?;
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, Error> {
+        self.expect(b'"')?;
+        let mut out: Vec<u8> = Vec::new();
+
+        loop {
+            let c = self
+                .next_byte()
+                .ok_or_else(|| json_error("unterminated string"))?;
+            match c {
+                b'"' => break,
+                b'\\' => {
+                    let escaped = self
+                        .next_byte()
+                        .ok_or_else(|| json_error("unterminated string"))?;
+                    let c = match escaped {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.parse_unicode_escape()?,
+                        _ => return Err(json_error("invalid escape sequence")),
+                    };
+
+                    let mut buf = [0; 4];
+                    out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                }
+                0x00..=0x1f => return Err(json_error("control character in string")),
+                _ => out.push(c),
+            }
+        }
+
+        String::from_utf8(out).map_err(|e| {
+            Kind::JsonDecodeError {
+                msg: "invalid utf-8",
+            }
+            .context(e)
+            .into()
+        })
+    }
+
+    fn parse_unicode_escape(&mut self) -> Result<char, Error> {
+        let high = self.parse_hex4()?;
+        let code = match high {
+            0xd800..=0xdbff => {
+                // surrogate pair
+                if !(self.consume(b'\\') && self.consume(b'u')) {
+                    return Err(json_error("unpaired surrogate"));
+                }
+
+                let low = self.parse_hex4()?;
+                if !(0xdc00..=0xdfff).contains(&low) {
+                    return Err(json_error("unpaired surrogate"));
+                }
+
+                0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+            }
+            0xdc00..=0xdfff => return Err(json_error("unpaired surrogate")),
+            _ => high,
+        };
+
+        std::char::from_u32(code).ok_or_else(|| json_error("invalid unicode escape"))
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, Error> {
+        let digits = self
+            .input
+            .get(self.pos..self.pos + 4)
+            .ok_or_else(|| json_error("invalid unicode escape"))?;
+
+        let mut code = 0;
+        for digit in digits {
+            let value = (*digit as char)
+                .to_digit(16)
+                .ok_or_else(|| json_error("invalid unicode escape"))?;
+            code = code << 4 | value;
+        }
+        self.pos += 4;
+
+        Ok(code)
+    }
+
+    fn parse_number(&mut self) -> Result<JsonValue, Error> {
+        let start = self.pos;
+
+        self.consume(b'-');
+        match self.next_byte() {
+            Some(b'0') => {}
+            Some(b'1'..=b'9') => {
+                self.skip_digits();
+            }
+            _ => return Err(json_error("invalid number")),
+        }
+
+        if self.consume(b'.') && self.skip_digits() == 0 {
+            return Err(json_error("invalid number"));
+        }
+
+        if self.consume(b'e') || self.consume(b'E') {
+            if !self.consume(b'+') {
+                self.consume(b'-');
+            }
+            if self.skip_digits() == 0 {
+                return Err(json_error("invalid number"));
+            }
+        }
+
+        // NOTE: the number consists of ASCII characters only
+        let text = String::from_utf8_lossy(&self.input[start..self.pos]).into_owned();
+
+        Ok(JsonValue::Number(text))
+    }
+
+    fn parse_literal(&mut self, literal: &str, value: JsonValue) -> Result<JsonValue, Error> {
+        if !self.input[self.pos..].starts_with(literal.as_bytes()) {
+            return Err(json_error("unexpected character"));
+        }
+        self.pos += literal.len();
+
+        Ok(value)
+    }
+
+    fn skip_digits(&mut self) -> usize {
+        let count = self.input[self.pos..]
+            .iter()
+            .take_while(|c| c.is_ascii_digit())
+            .count();
+        self.pos += count;
+
+        count
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn next_byte(&mut self) -> Option<u8> {
+        let c = self.peek()?;
+        self.pos += 1;
+
+        Some(c)
+    }
+
+    fn consume(&mut self, expected: u8) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            return true;
+        }
+
+        false
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), Error> {
+        if !self.consume(expected) {
+            return Err(json_error("unexpected character"));
+        }
+
+        Ok(())
+    }
+}
+
+fn write_string(value: &str, out: &mut String) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn strip_hex_prefix(value: &str) -> Result<&str, Error> {
+    if value.len() <= 2 || !value.starts_with("0x") {
+        return Err(json_error("hex string should start with '0x'"));
+    }
+
+    Ok(&value[2..])
+}
+
+// the numbers are accepted in the canonical form only (lowercase, without the sign and the
+// leading zeros), so that the strictly decoded values have a single encoding
+fn strip_hex_quantity(value: &str) -> Result<&str, Error> {
+    let digits = strip_hex_prefix(value)?;
+    if !digits
+        .bytes()
+        .all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+    {
+        return Err(json_error("invalid hex number"));
+    }
+    if digits.len() > 1 && digits.starts_with('0') {
+        return Err(json_error("hex number should not have leading zeros"));
+    }
+
+    Ok(digits)
+}
+
+fn json_error(msg: &'static str) -> Error {
+    Kind::JsonDecodeError { msg }.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_encodes_documents() {
+        let input =
+            r#" {"a": [1, -2.5e3, true, false, null], "b": {"c": "x\"\\\/\n\u00e9\ud83d\ude00"}} "#;
+        let value = JsonValue::parse(input.as_bytes()).unwrap();
+
+        let a = value.field("a").unwrap().as_array().unwrap();
+        assert_eq!(a[0].as_u64().unwrap(), 1);
+        assert_eq!(a[1], JsonValue::Number(String::from("-2.5e3")));
+        assert_eq!(
+            value
+                .field("b")
+                .unwrap()
+                .field("c")
+                .unwrap()
+                .as_str()
+                .unwrap(),
+            "x\"\\/\n\u{e9}\u{1f600}"
+        );
+
+        let encoded = value.encode();
+        assert_eq!(JsonValue::parse(encoded.as_bytes()).unwrap(), value);
+        assert_eq!(
+            encoded,
+            r#"{"a":[1,-2.5e3,true,false,null],"b":{"c":"x\"\\/\né😀"}}"#
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_documents() {
+        let deeply_nested = "[".repeat(MAX_DEPTH + 2);
+
+        for input in vec![
+            "",
+            "{",
+            "[1,]",
+            "{\"a\":1,}",
+            "{\"a\":1,\"a\":2}",
+            "01",
+            "1.",
+            "\"\\ud800\"",
+            "\"\u{1}\"",
+            "[] []",
+            deeply_nested.as_str(),
+        ] {
+            assert!(JsonValue::parse(input.as_bytes()).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn decodes_integers_without_floats() {
+        for (input, expected) in vec![("0", Some(0)), ("18446744073709551615", Some(u64::MAX))] {
+            let value = JsonValue::parse(input.as_bytes()).unwrap();
+            assert_eq!(value.as_u64().ok(), expected);
+        }
+
+        for input in vec!["-1", "1.0", "1e3", "18446744073709551616"] {
+            let value = JsonValue::parse(input.as_bytes()).unwrap();
+            assert!(value.as_u64().is_err());
+        }
+
+        let value = JsonValue::hex_big_int(&Integer::from(0x1234));
+        assert_eq!(value.as_hex_big_int().unwrap(), Integer::from(0x1234));
+        assert_eq!(value.as_hex_big_uint().unwrap(), BigUint::from(0x1234u64));
+        let value = JsonValue::hex_big_int(&Integer::from(-0x1234));
+        assert!(value.as_hex_big_uint().is_err());
+        assert_eq!(JsonValue::hex_u64(0).as_hex_u64().unwrap(), 0);
+        assert!(JsonValue::hex(&[]).as_hex_u64().is_err());
+        assert_eq!(JsonValue::hex(&[]).as_hex().unwrap(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn rejects_non_canonical_hex_numbers() {
+        for input in ["0x0001", "0x00", "0x+1", "0x-1", "0xAB", "0x 1", "0x1_0"] {
+            let value = JsonValue::String(input.to_string());
+            assert!(value.as_hex_u64().is_err(), "{}", input);
+            assert!(value.as_hex_big_int().is_err(), "{}", input);
+            assert!(value.as_hex_big_uint().is_err(), "{}", input);
+        }
+
+        for (input, expected) in [("0x0", 0), ("0x1", 1), ("0xab", 0xab), ("0x100", 0x100)] {
+            let value = JsonValue::String(input.to_string());
+            assert_eq!(value.as_hex_u64().unwrap(), expected);
+            assert_eq!(value.as_hex_big_int().unwrap(), Integer::from(expected));
+            assert_eq!(value.as_hex_big_uint().unwrap(), BigUint::from(expected));
+        }
+    }
+}
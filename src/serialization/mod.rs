@@ -1,2 +1,3 @@
 pub mod bytes;
+pub mod json;
 pub mod rlp;
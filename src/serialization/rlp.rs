@@ -1,35 +1,62 @@
-use crate::traits::FromBytes;
 use num_bigint::{BigInt as Integer, Sign};
 use num_traits::Zero;
-use rlp::{DecoderError, Rlp};
+use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
 
-pub fn rlp_list_field_from_bytes<T>(rlp: &Rlp, index: usize) -> Result<T, DecoderError>
-where
-    T: FromBytes + Clone,
-{
+pub fn rlp_to_big_int(rlp: &Rlp, index: usize) -> Result<Integer, DecoderError> {
     rlp.at(index)?
         .decoder()
-        .decode_value(|data| match T::from_bytes(data) {
-            Ok(field) => Ok(field.to_owned()),
-            Err(_) => Err(DecoderError::Custom("invalid length data")),
-        })
+        .decode_value(|bytes| Ok(Integer::from_bytes_be(Sign::Plus, bytes)))
 }
 
-pub fn rlp_field_from_bytes<T>(rlp: &Rlp) -> Result<T, DecoderError>
+/// Decodes the value only if the bytes are its canonical encoding. Two different byte strings
+/// never decode to the same value, that is trailing data, integers with leading zeros and
+/// lists with extra items are rejected.
+pub fn rlp_decode_strict<T>(bytes: &[u8]) -> Result<T, DecoderError>
 where
-    T: FromBytes + Clone,
+    T: Decodable + Encodable,
 {
-    rlp.decoder()
-        .decode_value(|data| match T::from_bytes(data) {
-            Ok(field) => Ok(field.to_owned()),
-            Err(_) => Err(DecoderError::Custom("invalid length data")),
-        })
+    rlp_check_single_item(bytes)?;
+
+    let value: T = rlp::decode(bytes)?;
+    rlp_check_canonical(bytes, &rlp::encode(&value))?;
+
+    Ok(value)
 }
 
-pub fn rlp_to_big_int(rlp: &Rlp, index: usize) -> Result<Integer, DecoderError> {
-    rlp.at(index)?
-        .decoder()
-        .decode_value(|bytes| Ok(Integer::from_bytes_be(Sign::Plus, bytes)))
+/// Errors out if there is data after the first RLP item
+pub fn rlp_check_single_item(bytes: &[u8]) -> Result<(), DecoderError> {
+    if Rlp::new(bytes).payload_info()?.total() != bytes.len() {
+        return Err(DecoderError::Custom("trailing data after rlp item"));
+    }
+
+    Ok(())
+}
+
+/// Errors out if the decoded value re-encodes to different bytes than the ones it was decoded
+/// from (ie. the encoding isn't canonical)
+pub fn rlp_check_canonical(bytes: &[u8], encoded: &[u8]) -> Result<(), DecoderError> {
+    if bytes != encoded {
+        return Err(DecoderError::Custom("non-canonical rlp encoding"));
+    }
+
+    Ok(())
+}
+
+/// Begins the versioned encoding, that is `[version, [fields...]]`
+pub fn rlp_begin_versioned(s: &mut RlpStream, version: u64, fields: usize) {
+    s.begin_list(2);
+    s.append(&version);
+    s.begin_list(fields);
+}
+
+/// Returns the version and the list of fields of the versioned encoding. Legacy (unversioned)
+/// encodings are positional lists of more than two fields, these are returned as version 0.
+pub fn rlp_versioned_fields<'a>(rlp: &Rlp<'a>) -> Result<(u64, Rlp<'a>), DecoderError> {
+    if rlp.item_count()? == 2 {
+        return Ok((rlp.val_at(0)?, rlp.at(1)?));
+    }
+
+    Ok((0, rlp.clone()))
 }
 
 pub fn big_int_to_rlp_compat_bytes(val: &Integer) -> Vec<u8> {
@@ -46,6 +73,7 @@ pub fn big_int_to_rlp_compat_bytes(val: &Integer) -> Vec<u8> {
 mod tests {
     use super::*;
     use num_traits::Num;
+    use rlp_derive::{RlpDecodable, RlpEncodable};
 
     #[test]
     fn parses_big_int() {
@@ -71,6 +99,39 @@ mod tests {
         );
     }
 
+    #[derive(RlpEncodable, RlpDecodable, PartialEq, Debug)]
+    struct Item {
+        number: u64,
+        timestamp: u64,
+    }
+
+    #[test]
+    fn strictly_decodes_canonical_encoding() {
+        let mut s = RlpStream::new();
+        s.begin_list(2);
+        s.append(&1u64);
+        s.append(&5u64);
+        let canonical = s.out();
+
+        assert_eq!(
+            rlp_decode_strict::<Item>(&canonical).unwrap(),
+            Item {
+                number: 1,
+                timestamp: 5
+            }
+        );
+
+        // trailing data
+        let trailing = [&canonical[..], &[0x80]].concat();
+        assert!(rlp::decode::<Item>(&trailing).is_ok());
+        assert!(rlp_decode_strict::<Item>(&trailing).is_err());
+
+        // extra list item
+        let extra_item = hex::decode("c3010580").unwrap();
+        assert!(rlp::decode::<Item>(&extra_item).is_ok());
+        assert!(rlp_decode_strict::<Item>(&extra_item).is_err());
+    }
+
     fn bytes_to_rlp_list(bytes: &[u8]) -> Vec<u8> {
         let mut r = rlp::RlpStream::new();
         r.begin_list(1);
@@ -0,0 +1,144 @@
+use crate::trie::keccak;
+use crate::types::header::{Address, Hash, ADDRESS_LENGTH, HASH_LENGTH};
+
+/// SlotKey is a key of the Solidity mapping
+#[derive(Clone, PartialEq, Debug)]
+pub enum SlotKey {
+    /// Value types (ie. uint256, address, bytes32), left padded to 32 bytes
+    Word(Hash),
+
+    /// Dynamically-sized types (string, bytes), used as-is
+    Bytes(Vec<u8>),
+}
+
+impl SlotKey {
+    pub fn from_u64(value: u64) -> Self {
+        SlotKey::Word(slot_from_u64(value))
+    }
+
+    pub fn from_address(address: &Address) -> Self {
+        let mut word = Hash::default();
+        word[HASH_LENGTH - ADDRESS_LENGTH..].copy_from_slice(address);
+
+        SlotKey::Word(word)
+    }
+
+    pub fn from_string(value: &str) -> Self {
+        SlotKey::Bytes(value.as_bytes().to_vec())
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        match self {
+            SlotKey::Word(word) => word,
+            SlotKey::Bytes(bytes) => bytes,
+        }
+    }
+}
+
+/// Returns the storage slot for the state variable declared at the given position
+pub fn slot_from_u64(position: u64) -> Hash {
+    let mut slot = Hash::default();
+    slot[HASH_LENGTH - 8..].copy_from_slice(&position.to_be_bytes());
+
+    slot
+}
+
+/// Returns the slot of the mapping value, that is keccak256(key . slot)
+pub fn mapping_slot(slot: &Hash, key: &SlotKey) -> Hash {
+    keccak(&[key.as_bytes(), &slot[..]].concat())
+}
+
+/// Returns the slot of the nested mapping value (ie. `mapping(a => mapping(b => c))`), where the
+/// keys are given from the outermost mapping
+pub fn nested_mapping_slot(slot: &Hash, keys: &[SlotKey]) -> Hash {
+    keys.iter()
+        .fold(*slot, |slot, key| mapping_slot(&slot, key))
+}
+
+/// Returns the first slot of the dynamic array element, where the array elements are laid out
+/// starting from keccak256(slot) and each element occupies `element_size` slots
+pub fn array_element_slot(slot: &Hash, index: u64, element_size: u64) -> Hash {
+    let offset = u128::from(index) * u128::from(element_size);
+
+    add_to_slot(&dynamic_data_slot(slot), offset)
+}
+
+/// Returns the slot where the data of dynamic array, or long (31+ bytes) string/bytes starts
+pub fn dynamic_data_slot(slot: &Hash) -> Hash {
+    keccak(slot)
+}
+
+/// Adds the offset to the slot, wrapping around 2^256 as the EVM does
+pub fn add_to_slot(slot: &Hash, offset: u128) -> Hash {
+    let mut result = *slot;
+    let mut carry = offset;
+
+    for byte in result.iter_mut().rev() {
+        if carry == 0 {
+            break;
+        }
+
+        let sum = u128::from(*byte) + (carry & 0xff);
+        *byte = sum as u8;
+        carry = (carry >> 8) + (sum >> 8);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derives_mapping_slots() {
+        // keccak256(abi.encode(uint256(1), uint256(0)))
+        assert_eq!(
+            hex::encode(mapping_slot(&slot_from_u64(0), &SlotKey::from_u64(1))),
+            "ada5013122d395ba3c54772283fb069b10426056ef8ca54750cb9bb552a59e7d"
+        );
+
+        // string keys are not padded
+        let slot = slot_from_u64(2);
+        assert_eq!(
+            mapping_slot(&slot, &SlotKey::from_string("ports/transfer")),
+            keccak(&[&b"ports/transfer"[..], &slot[..]].concat())
+        );
+
+        let address = Address::from([0xaa; ADDRESS_LENGTH]);
+        let keys = vec![SlotKey::from_address(&address), SlotKey::from_u64(7)];
+        assert_eq!(
+            nested_mapping_slot(&slot, &keys),
+            mapping_slot(&mapping_slot(&slot, &keys[0]), &keys[1])
+        );
+        assert_eq!(nested_mapping_slot(&slot, &[]), slot);
+    }
+
+    #[test]
+    fn derives_array_slots() {
+        // keccak256(uint256(0))
+        let data_slot = "290decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3e563";
+
+        assert_eq!(
+            hex::encode(array_element_slot(&slot_from_u64(0), 0, 1)),
+            data_slot
+        );
+        assert_eq!(
+            hex::encode(array_element_slot(&slot_from_u64(0), 3, 2)),
+            "290decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3e569"
+        );
+    }
+
+    #[test]
+    fn adds_to_slot_with_carry() {
+        let mut slot = Hash::from([0xff; HASH_LENGTH]);
+        assert_eq!(add_to_slot(&slot, 1), Hash::default());
+
+        slot[HASH_LENGTH - 1] = 0xfe;
+        slot[0] = 0;
+        let mut expected = Hash::default();
+        expected[0] = 1;
+        expected[HASH_LENGTH - 1] = 0x01;
+        assert_eq!(add_to_slot(&slot, 3), expected);
+    }
+}
@@ -1,24 +1,49 @@
-use crate::bls::verify_aggregated_seal;
+use crate::bls::{validate_public_key, verify_proof_of_possession};
+use crate::engine::CELO_ENGINE;
 use crate::errors::{Error, Kind};
-use crate::istanbul::is_last_block_of_epoch;
-use crate::traits::StateConfig;
+use crate::traits::{Clock, IstanbulEngine, StateConfig};
+use crate::types::bitmap::ValidatorBitmap;
 use crate::types::header::{Address, Header};
-use crate::types::istanbul::IstanbulExtra;
+use crate::types::istanbul::{
+    CommittedSeal, ConsensusExtra, SerializedPublicKey, ValidatorSetChange,
+};
 use crate::types::state::{Snapshot, Validator};
-use num::cast::ToPrimitive;
-use num_bigint::BigInt as Integer;
+use crate::types::validator_set::ValidatorSet;
 use num_traits::Zero;
 use std::collections::HashMap;
+use std::time::Duration;
 
 /// State takes care of managing the IBFT consensus state
 pub struct State<'a> {
     snapshot: Snapshot,
     config: &'a dyn StateConfig,
+    clock: &'a dyn Clock,
+    engine: &'a dyn IstanbulEngine,
+    proofs_of_possession: HashMap<Address, Vec<u8>>,
 }
 
 impl<'a> State<'a> {
-    pub fn new(snapshot: Snapshot, config: &'a dyn StateConfig) -> Self {
-        State { snapshot, config }
+    pub fn new(snapshot: Snapshot, config: &'a dyn StateConfig, clock: &'a dyn Clock) -> Self {
+        State {
+            snapshot,
+            config,
+            clock,
+            engine: &CELO_ENGINE,
+            proofs_of_possession: HashMap::new(),
+        }
+    }
+
+    /// Sets the engine defining the extra-data format, the seal scheme and the validator set
+    /// updates of the chain. Celo with the BLS seal verification is used by default.
+    pub fn set_engine(&mut self, engine: &'a dyn IstanbulEngine) {
+        self.engine = engine;
+    }
+
+    /// Sets the proofs-of-possession of the validators BLS keys, indexed by the validator address.
+    /// The keys added by epoch headers are checked against the proofs if available, the skipping
+    /// verification requires them for all the keys that aren't trusted yet.
+    pub fn set_proofs_of_possession(&mut self, proofs: HashMap<Address, Vec<u8>>) {
+        self.proofs_of_possession = proofs;
     }
 
     pub fn snapshot(&self) -> &Snapshot {
@@ -26,50 +51,31 @@ impl<'a> State<'a> {
     }
 
     pub fn add_validators(&mut self, validators: Vec<Validator>) -> bool {
-        let mut new_address_map: HashMap<Address, bool> = HashMap::new();
-
-        for validator in validators.iter() {
-            new_address_map.insert(validator.address, true);
-        }
-
-        // Verify that the validators to add is not already in the valset
-        for v in self.snapshot.validators.iter() {
-            if new_address_map.contains_key(&v.address) {
-                return false;
-            }
-        }
-
-        self.snapshot.validators.extend(validators);
-
-        return true;
+        self.snapshot.validators.add(validators)
     }
 
-    pub fn remove_validators(&mut self, removed_validators: &Integer) -> bool {
-        if removed_validators.bits() == 0 {
-            return true;
-        }
-
-        if removed_validators.bits() > self.snapshot.validators.len() as u64 {
-            return false;
-        }
+    pub fn remove_validators(&mut self, removed_validators: &ValidatorBitmap) -> bool {
+        self.snapshot.validators.remove(removed_validators)
+    }
 
-        let filtered_validators: Vec<Validator> = self
-            .snapshot
-            .validators
-            .iter()
-            .enumerate()
-            .filter(|(i, _)| removed_validators.bit(*i as u64) == false)
-            .map(|(_, v)| v.to_owned())
-            .collect();
+    /// Whether the latest trusted snapshot is older than the trusting period, in which case
+    /// the state doesn't accept any more headers
+    pub fn is_expired(&self) -> bool {
+        self.snapshot
+            .is_expired(self.config.trusting_period(), self.clock.now())
+    }
 
-        self.snapshot.validators = filtered_validators;
+    pub fn verify_header(&self, header: &Header) -> Result<(), Error> {
+        self.check_expiration()?;
+        self.verify_header_fields(header)?;
 
-        return true;
+        self.verify_header_seal(&header)
     }
 
-    pub fn verify_header(&self, header: &Header, current_timestamp: u64) -> Result<(), Error> {
+    /// Verifies the header height and timestamp against the state, but not the seal
+    pub fn verify_header_fields(&self, header: &Header) -> Result<(), Error> {
         // assert header height is newer than any we know
-        if !(header.number.to_u64().unwrap() > self.snapshot.number) {
+        if !(header.number_u64()? > self.snapshot.number) {
             return Err(Kind::HeaderVerificationError {
                 msg: "header height should be greater than the last one stored in state",
             }
@@ -86,7 +92,8 @@ impl<'a> State<'a> {
             }
 
             // don't waste time checking blocks from the future
-            if header.time > current_timestamp + self.config.allowed_clock_skew() {
+            let allowed_clock_skew = Duration::from_secs(self.config.allowed_clock_skew());
+            if header.timestamp() > self.clock.now().saturating_add(allowed_clock_skew) {
                 return Err(Kind::HeaderVerificationError {
                     msg: "header timestamp is set too far in the future",
                 }
@@ -94,108 +101,310 @@ impl<'a> State<'a> {
             }
         }
 
-        self.verify_header_seal(&header)
+        Ok(())
     }
 
     pub fn verify_header_seal(&self, header: &Header) -> Result<(), Error> {
-        let header_hash = header.hash()?;
-        let extra = IstanbulExtra::from_rlp(&header.extra)?;
+        let header_hash = self.engine.header_hash(header)?;
+        let extra = self.decode_extra(header)?;
 
-        verify_aggregated_seal(
+        self.engine.verify_seal(
             header_hash,
             &self.snapshot.validators,
-            &extra.aggregated_seal,
+            &extra.committed_seal,
         )
     }
 
-    pub fn insert_header(&mut self, header: &Header, current_timestamp: u64) -> Result<(), Error> {
-        let block_num = header.number.to_u64().unwrap();
+    pub fn insert_header(&mut self, header: &Header) -> Result<(), Error> {
+        // the headers can't be trusted even if the verification is disabled
+        self.check_expiration()?;
+
+        let block_num = header.number_u64()?;
 
-        if is_last_block_of_epoch(block_num, self.config.epoch_size()) {
-            // The validator set is about to be updated with epoch header
-            self.store_epoch_header(header, current_timestamp)
+        if self
+            .engine
+            .changes_validator_set(block_num, self.config.epoch_size())
+        {
+            // The validator set is about to be updated (ie. with epoch header)
+            self.store_epoch_header(header)
         } else {
             // Validator set is not being updated
-            self.store_non_epoch_header(header, current_timestamp)
+            self.store_non_epoch_header(header)
         }
     }
 
-    fn store_non_epoch_header(
+    /// Inserts the epoch header that may be several epochs ahead of the state at once. The
+    /// validator set that sealed the header isn't known to the state, it's derived from the
+    /// trusted one by applying the changesets of the intermediate epoch headers (in order). The
+    /// header is accepted if:
+    /// * each of the intermediate epoch headers is sealed by a quorum of the set derived so far,
+    /// * it's sealed by a quorum of the derived validator set,
+    /// * the signers that are also members of the trusted validator set make up more than the
+    ///   trust threshold (see [`StateConfig::trust_threshold`]) of the trusted set.
+    ///
+    /// The BLS keys that aren't trusted yet must come with the proofs-of-possession (see
+    /// [`State::set_proofs_of_possession`]).
+    pub fn insert_header_skipping(
         &mut self,
         header: &Header,
-        current_timestamp: u64,
+        intermediate_headers: &[Header],
     ) -> Result<(), Error> {
+        self.check_expiration()?;
+
+        let trust_threshold = match self.config.trust_threshold() {
+            Some(trust_threshold) => trust_threshold,
+            None => {
+                return Err(Kind::HeaderVerificationError {
+                    msg: "skipping verification is disabled",
+                }
+                .into())
+            }
+        };
+        self.verify_header_fields(header)?;
+
+        // the validator set is proven by the chain of the epoch headers, none can be left out
+        let mut number = self.snapshot.number;
+        let mut validators = self.snapshot.validators.clone();
+        for epoch_header in intermediate_headers.iter() {
+            number = self.next_epoch_header_number(epoch_header, number)?;
+
+            let header_hash = self.engine.header_hash(epoch_header)?;
+            let extra = self.decode_extra(epoch_header)?;
+            self.engine
+                .verify_seal(header_hash, &validators, &extra.committed_seal)?;
+
+            validators = self.apply_untrusted_change(&validators, &extra)?;
+        }
+        self.next_epoch_header_number(header, number)?;
+
+        let header_hash = self.engine.header_hash(header)?;
+        let extra = self.decode_extra(header)?;
+
+        // the header must be committed by its own validator set...
+        self.engine
+            .verify_seal(header_hash, &validators, &extra.committed_seal)?;
+
+        // ...and enough of the trusted validators have to vouch for it
+        let trusted_signers = self
+            .engine
+            .seal_signers(header_hash, &validators, &extra.committed_seal)?
+            .iter()
+            .filter(|validator| self.is_trusted(validator))
+            .count();
+        let trusted = self.snapshot.validators.len();
+        if !trust_threshold.is_exceeded_by(trusted_signers, trusted) {
+            return Err(Kind::NotEnoughTrustedSigners {
+                current: trusted_signers,
+                trusted,
+            }
+            .into());
+        }
+
+        let snapshot = Snapshot {
+            number: header.number_u64()?,
+            timestamp: header.time,
+            validators: self.apply_untrusted_change(&validators, &extra)?,
+            hash: header_hash,
+            aggregated_seal: extra.aggregated_seal(),
+        };
+
+        self.update_state_snapshot(snapshot)
+    }
+
+    fn store_non_epoch_header(&mut self, header: &Header) -> Result<(), Error> {
         // genesis block is valid dead end
         if self.config.verify_non_epoch_headers() && !header.number.is_zero() {
-            self.verify_header(&header, current_timestamp)?
+            self.verify_header(&header)?
         }
 
-        let extra = IstanbulExtra::from_rlp(&header.extra)?;
+        let extra = self.decode_extra(header)?;
+
         let snapshot = Snapshot {
             // The validator state stays unchanged (ONLY updated with epoch header)
             validators: self.snapshot.validators.clone(),
 
             // Update the header related fields
-            number: header.number.to_u64().unwrap(),
+            number: header.number_u64()?,
             timestamp: header.time,
-            hash: header.hash()?,
-            aggregated_seal: extra.aggregated_seal.clone(),
+            hash: self.engine.header_hash(header)?,
+            aggregated_seal: extra.aggregated_seal(),
         };
 
         self.update_state_snapshot(snapshot)
     }
 
-    fn store_epoch_header(&mut self, header: &Header, current_timestamp: u64) -> Result<(), Error> {
+    fn store_epoch_header(&mut self, header: &Header) -> Result<(), Error> {
         // genesis block is valid dead end
         if self.config.verify_epoch_headers() && !header.number.is_zero() {
-            self.verify_header(&header, current_timestamp)?
+            self.verify_header(&header)?
         }
 
-        let header_hash = header.hash()?;
-        let extra = IstanbulExtra::from_rlp(&header.extra)?;
+        let header_hash = self.engine.header_hash(header)?;
+        let extra = self.decode_extra(header)?;
 
-        // convert istanbul validators into a Validator struct
-        let mut validators: Vec<Validator> = Vec::new();
-        if extra.added_validators.len() != extra.added_validators_public_keys.len() {
-            return Err(Kind::InvalidValidatorSetDiff {
-                msg: "error in combining addresses and public keys",
+        // apply the header's changeset (on a copy, so that the state stays intact on failure)
+        let validator_set = self.apply_change(&self.snapshot.validators, &extra)?;
+
+        let snapshot = Snapshot {
+            number: header.number_u64()?,
+            timestamp: header.time,
+            validators: validator_set,
+            hash: header_hash,
+            aggregated_seal: extra.aggregated_seal(),
+        };
+
+        self.update_state_snapshot(snapshot)
+    }
+
+    fn check_expiration(&self) -> Result<(), Error> {
+        if self.is_expired() {
+            return Err(Kind::StateExpired {
+                trusting_period: self.config.trusting_period(),
             }
             .into());
         }
 
-        for i in 0..extra.added_validators.len() {
-            validators.push(Validator {
-                address: extra.added_validators[i].clone(),
-                public_key: extra.added_validators_public_keys[i].clone(),
-            })
+        Ok(())
+    }
+
+    /// Returns the number of the epoch header, if it's the first header after the given block
+    /// that changes the validator set
+    fn next_epoch_header_number(&self, header: &Header, previous: u64) -> Result<u64, Error> {
+        let number = header.number_u64()?;
+        let epoch_size = self.config.epoch_size();
+
+        // NOTE: the search stops at the first header changing the validator set
+        let next = (previous.saturating_add(1)..=number)
+            .find(|number| self.engine.changes_validator_set(*number, epoch_size));
+        if next != Some(number) {
+            return Err(Kind::HeaderVerificationError {
+                msg: "the epoch headers must follow each other",
+            }
+            .into());
+        }
+
+        Ok(number)
+    }
+
+    /// Applies the changeset of the epoch header that hasn't been inserted in order, the keys
+    /// added by it must come with the proofs-of-possession unless they're already trusted
+    fn apply_untrusted_change(
+        &self,
+        validators: &ValidatorSet,
+        extra: &ConsensusExtra,
+    ) -> Result<ValidatorSet, Error> {
+        // the keys only get aggregated for the aggregated seals, where an unproven key could be
+        // crafted to cancel out the others
+        if let (CommittedSeal::Aggregated(_), ValidatorSetChange::Diff { added, .. }) =
+            (&extra.committed_seal, &extra.validator_set_change)
+        {
+            let unproven = added.iter().find(|validator| {
+                !self.is_trusted(validator)
+                    && !self.proofs_of_possession.contains_key(&validator.address)
+            });
+            if let Some(validator) = unproven {
+                return Err(Kind::BlsMissingProofOfPossession
+                    .context(format!("validator {}", validator.address))
+                    .into());
+            }
+        }
+
+        // the proofs are verified along with the changeset
+        self.apply_change(validators, extra)
+    }
+
+    fn is_trusted(&self, validator: &Validator) -> bool {
+        self.snapshot.validators.get_by_address(&validator.address) == Some(validator)
+    }
+
+    fn validate_public_keys(&self, validators: &[Validator]) -> Result<(), Error> {
+        for validator in validators.iter() {
+            match self.proofs_of_possession.get(&validator.address) {
+                Some(proof) => verify_proof_of_possession(validator, proof)?,
+                None => {
+                    validate_public_key(&validator.public_key)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn apply_change(
+        &self,
+        validators: &ValidatorSet,
+        extra: &ConsensusExtra,
+    ) -> Result<ValidatorSet, Error> {
+        match &extra.validator_set_change {
+            ValidatorSetChange::Diff { added, removed } => {
+                self.apply_diff(validators, added, removed)
+            }
+            ValidatorSetChange::Replace(addresses) => {
+                // the replaced validators come without the BLS keys, the aggregated seals of
+                // the next epoch couldn't be verified against them
+                if let CommittedSeal::Aggregated(_) = extra.committed_seal {
+                    return Err(Kind::InvalidValidatorSetDiff {
+                        msg: "the validator set can't be replaced without the BLS public keys",
+                    }
+                    .into());
+                }
+
+                let validators = addresses
+                    .iter()
+                    .map(|address| Validator {
+                        address: *address,
+                        public_key: SerializedPublicKey::default(),
+                    })
+                    .collect();
+
+                let mut validator_set = ValidatorSet::default();
+                if !validator_set.add(validators) {
+                    return Err(Kind::InvalidValidatorSetDiff {
+                        msg: "error in replacing the validator set",
+                    }
+                    .into());
+                }
+
+                Ok(validator_set)
+            }
         }
+    }
 
-        // apply the header's changeset
-        let result_remove = self.remove_validators(&extra.removed_validators);
-        if !result_remove {
+    fn apply_diff(
+        &self,
+        validators: &ValidatorSet,
+        added: &[Validator],
+        removed: &ValidatorBitmap,
+    ) -> Result<ValidatorSet, Error> {
+        // reject the invalid keys before they get aggregated with the others
+        self.validate_public_keys(added)?;
+
+        let mut validator_set = validators.clone();
+        if !validator_set.remove(removed) {
             return Err(Kind::InvalidValidatorSetDiff {
                 msg: "error in removing the header's removed_validators",
             }
             .into());
         }
 
-        let result_add = self.add_validators(validators);
-        if !result_add {
+        if !validator_set.add(added.to_vec()) {
             return Err(Kind::InvalidValidatorSetDiff {
                 msg: "error in adding the header's added_validators",
             }
             .into());
         }
 
-        let snapshot = Snapshot {
-            number: header.number.to_u64().unwrap(),
-            timestamp: header.time,
-            validators: self.snapshot.validators.clone(),
-            hash: header_hash,
-            aggregated_seal: extra.aggregated_seal,
-        };
+        // keep the aggregated public key cached, if it was computed for the previous set
+        if validators.aggregated_public_key().is_some() {
+            validator_set.compute_aggregated_public_key()?;
+        }
 
-        self.update_state_snapshot(snapshot)
+        Ok(validator_set)
+    }
+
+    fn decode_extra(&self, header: &Header) -> Result<ConsensusExtra, Error> {
+        self.engine
+            .decode_extra(header, self.config.strict_rlp_decoding())
     }
 
     fn update_state_snapshot(&mut self, snapshot: Snapshot) -> Result<(), Error> {
@@ -212,13 +421,28 @@ impl<'a> State<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::traits::{DefaultFrom, FromBytes, FromRlp};
+    use crate::bls::test_utils::{
+        generate_validators, identity_public_key, rogue_public_key, sign_aggregated_seal,
+        sign_proof_of_possession, RecordingSealVerifier,
+    };
+    use crate::bls::verify_aggregated_seal;
+    use crate::certificate::test_utils::{secp256k1_validators, sign_hash, Secp256k1Recovery};
+    use crate::clock::{FixedClock, Timestamp};
+    use crate::engine::{verify_committed_seals, CeloEngine};
+    use crate::istanbul::byzantine_quorum_size;
+    use crate::traits::{FromRlp, SignerRecovery};
     use crate::types::header::{Hash, ADDRESS_LENGTH};
-    use crate::types::istanbul::{IstanbulAggregatedSeal, SerializedPublicKey};
-    use crate::types::state::Config;
+    use crate::types::istanbul::{
+        CommittedSeal, IstanbulAggregatedSeal, IstanbulExtra, IstanbulExtraVanity,
+        AGGREGATED_SIGNATURE_LENGTH,
+    };
+    use crate::types::state::{Config, TrustThreshold};
+    use bls_crypto::PrivateKey;
+    use num_bigint::BigInt as Integer;
+    use rlp::{Rlp, RlpStream};
     use secp256k1::{rand::rngs::OsRng, PublicKey, Secp256k1, SecretKey};
     use sha3::{Digest, Keccak256};
-    use std::{cmp, cmp::Ordering};
+    use std::{cmp, cmp::Ordering, slice};
 
     macro_rules! string_vec {
         ($($x:expr),*) => (vec![$($x.to_string()),*]);
@@ -239,14 +463,99 @@ mod tests {
         pub accounts: HashMap<String, (SecretKey, PublicKey)>,
     }
 
+    /// Engine of an ECDSA sealed chain, whose extra-data is the RLP list of the validator set
+    /// addresses and the committed seals over the hash of the rest of the header
+    struct EcdsaEngine;
+
+    impl EcdsaEngine {
+        fn extra(validators: &[Validator], seals: &[Vec<u8>]) -> Vec<u8> {
+            let addresses: Vec<Address> = validators.iter().map(|v| v.address).collect();
+
+            let mut stream = RlpStream::new_list(2);
+            stream.append_list(&addresses);
+            stream.append_list::<Vec<u8>, _>(seals);
+            stream.out().to_vec()
+        }
+    }
+
+    impl IstanbulEngine for EcdsaEngine {
+        fn decode_extra(&self, header: &Header, _strict: bool) -> Result<ConsensusExtra, Error> {
+            let rlp = Rlp::new(&header.extra);
+            let decode_error = |e| Error::from(Kind::RlpDecodeError.context(e));
+
+            Ok(ConsensusExtra {
+                validator_set_change: ValidatorSetChange::Replace(
+                    rlp.list_at(0).map_err(decode_error)?,
+                ),
+                committed_seal: CommittedSeal::Ecdsa(rlp.list_at(1).map_err(decode_error)?),
+            })
+        }
+
+        fn header_hash(&self, header: &Header) -> Result<Hash, Error> {
+            let validators = Rlp::new(&header.extra).at(0).unwrap().as_raw().to_vec();
+            let digest =
+                Keccak256::digest(&[&header.number_u64()?.to_be_bytes()[..], &validators].concat());
+
+            Hash::from_slice(&digest)
+        }
+
+        fn verify_seal(
+            &self,
+            header_hash: Hash,
+            validators: &ValidatorSet,
+            seal: &CommittedSeal,
+        ) -> Result<(), Error> {
+            match seal {
+                CommittedSeal::Ecdsa(seals) => verify_committed_seals(
+                    &header_hash,
+                    validators,
+                    seals,
+                    byzantine_quorum_size(validators.len()),
+                    &Secp256k1Recovery,
+                ),
+                CommittedSeal::Aggregated(_) => Err(Kind::BlsVerifyError.into()),
+            }
+        }
+
+        fn seal_signers(
+            &self,
+            header_hash: Hash,
+            validators: &ValidatorSet,
+            seal: &CommittedSeal,
+        ) -> Result<Vec<Validator>, Error> {
+            match seal {
+                CommittedSeal::Ecdsa(seals) => seals
+                    .iter()
+                    .map(|seal| {
+                        let signer = Secp256k1Recovery.recover(&header_hash, seal)?;
+                        validators.get_by_address(&signer).cloned().ok_or_else(|| {
+                            Kind::InvalidCommittedSeal {
+                                msg: "seal signer is not a validator",
+                            }
+                            .into()
+                        })
+                    })
+                    .collect(),
+                CommittedSeal::Aggregated(_) => Err(Kind::BlsVerifyError.into()),
+            }
+        }
+
+        // the validator set is changed by the votes, that is in any block
+        fn changes_validator_set(&self, _number: u64, _epoch_size: u64) -> bool {
+            true
+        }
+    }
+
     fn state_config() -> Config {
         Config {
             epoch_size: 123,
             allowed_clock_skew: 123,
+            trusting_period: 0,
 
             verify_epoch_headers: true,
             verify_non_epoch_headers: true,
             verify_header_timestamp: true,
+            trust_threshold: None,
         }
     }
 
@@ -282,7 +591,8 @@ mod tests {
                     address: Address::default(),
                     public_key: SerializedPublicKey::default(),
                 },
-            ],
+            ]
+            .into(),
             timestamp: 123456,
             number: 456,
             hash: Hash::default(),
@@ -295,11 +605,645 @@ mod tests {
         assert_eq!(snapshot, decoded);
     }
 
+    #[test]
+    fn test_decode_malformed_validators() {
+        for bytes in vec![vec![0x83, 0x01], vec![0x01, 0x02], vec![0xc2, 0x01, 0x02]] {
+            assert!(Vec::<Validator>::from_rlp(&bytes).is_err());
+        }
+    }
+
+    #[test]
+    fn test_rejects_block_number_overflow() {
+        let config = state_config();
+        let clock = FixedClock::default();
+        let mut state = State::new(Snapshot::new(), &config, &clock);
+
+        let mut header = Header::new();
+        header.number = Integer::from(u64::MAX) + 1;
+
+        match state.insert_header(&header).unwrap_err().kind() {
+            Kind::InvalidBlockNumber => {}
+            kind => panic!("unexpected error kind: {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn test_verify_header_with_max_timestamp() {
+        let config = state_config();
+        let clock = FixedClock(Timestamp::from_secs(u64::MAX));
+        let state = State::new(Snapshot::new(), &config, &clock);
+
+        let mut header = Header::new();
+        header.number = Integer::from(1);
+        header.time = 1;
+        header.extra = IstanbulExtra {
+            added_validators: Vec::new(),
+            added_validators_public_keys: Vec::new(),
+            removed_validators: ValidatorBitmap::new(),
+            seal: Vec::new(),
+            aggregated_seal: IstanbulAggregatedSeal::new(),
+            parent_aggregated_seal: IstanbulAggregatedSeal::new(),
+        }
+        .to_rlp(&IstanbulExtraVanity::default());
+
+        // the clock skew check must not overflow, the header is then rejected by seal verification
+        match state.verify_header(&header).unwrap_err().kind() {
+            Kind::EmptyValidatorSet => {}
+            kind => panic!("unexpected error kind: {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn test_rejects_header_from_the_future() {
+        let config = state_config();
+        let clock = FixedClock(Timestamp::from_secs(1_600_000_000));
+        let state = State::new(Snapshot::new(), &config, &clock);
+
+        let mut header = Header::new();
+        header.number = Integer::from(1);
+        header.time = 1_600_000_000 + config.allowed_clock_skew + 1;
+
+        match state.verify_header(&header).unwrap_err().kind() {
+            Kind::HeaderVerificationError { msg } => {
+                assert_eq!(*msg, "header timestamp is set too far in the future")
+            }
+            kind => panic!("unexpected error kind: {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn test_rejects_replaced_validator_set_without_bls_keys() {
+        let config = state_config();
+        let clock = FixedClock::default();
+        let state = State::new(Snapshot::new(), &config, &clock);
+
+        let addresses = vec![
+            Address::from([1; ADDRESS_LENGTH]),
+            Address::from([2; ADDRESS_LENGTH]),
+        ];
+        let mut extra = ConsensusExtra {
+            validator_set_change: ValidatorSetChange::Replace(addresses.clone()),
+            committed_seal: CommittedSeal::Ecdsa(Vec::new()),
+        };
+
+        let validators = state
+            .apply_change(&ValidatorSet::default(), &extra)
+            .unwrap();
+        let replaced: Vec<Address> = validators.iter().map(|v| v.address).collect();
+        assert_eq!(replaced, addresses);
+
+        // the aggregated seals couldn't be verified against the zeroed BLS keys
+        extra.committed_seal = CommittedSeal::Aggregated(IstanbulAggregatedSeal::new());
+        match state
+            .apply_change(&ValidatorSet::default(), &extra)
+            .unwrap_err()
+            .kind()
+        {
+            Kind::InvalidValidatorSetDiff { msg } => assert_eq!(
+                *msg,
+                "the validator set can't be replaced without the BLS public keys"
+            ),
+            kind => panic!("unexpected error kind: {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn test_rejects_headers_on_expired_state() {
+        let config = Config {
+            trusting_period: 100,
+            verify_header_timestamp: false,
+            ..state_config()
+        };
+        let mut snapshot = Snapshot::new();
+        snapshot.timestamp = 1_600_000_000;
+
+        let mut header = Header::new();
+        header.number = Integer::from(1);
+
+        let clock = FixedClock(Timestamp::from_secs(1_600_000_099));
+        let state = State::new(snapshot.clone(), &config, &clock);
+        assert!(!state.is_expired());
+        // the header passes the expiration check, but fails on the missing extra-data
+        match state.verify_header(&header).unwrap_err().kind() {
+            Kind::InvalidExtraVanity { .. } => {}
+            kind => panic!("unexpected error kind: {:?}", kind),
+        }
+
+        let clock = FixedClock(Timestamp::from_secs(1_600_000_100));
+        let mut state = State::new(snapshot.clone(), &config, &clock);
+        assert!(state.is_expired());
+        for err in [
+            state.verify_header(&header).unwrap_err(),
+            state.insert_header(&header).unwrap_err(),
+        ] {
+            match err.kind() {
+                Kind::StateExpired { trusting_period } => assert_eq!(*trusting_period, 100),
+                kind => panic!("unexpected error kind: {:?}", kind),
+            }
+        }
+        assert_eq!(state.snapshot(), &snapshot);
+    }
+
+    #[test]
+    fn test_verifies_seals_with_the_seal_verifier() {
+        let config = Config {
+            epoch_size: 123,
+            allowed_clock_skew: 0,
+            trusting_period: 0,
+
+            verify_epoch_headers: true,
+            verify_non_epoch_headers: true,
+            verify_header_timestamp: false,
+            trust_threshold: None,
+        };
+        let clock = FixedClock::default();
+        let (_, validators) = generate_validators(3);
+
+        // the verifier doesn't compute the pairings, so the signature doesn't matter
+        let aggregated_seal = IstanbulAggregatedSeal {
+            bitmap: ValidatorBitmap::from(0b011),
+            signature: vec![0; AGGREGATED_SIGNATURE_LENGTH],
+            round: Integer::default(),
+        };
+        let mut header = Header::new();
+        header.number = Integer::from(1);
+        header.extra = IstanbulExtra {
+            added_validators: Vec::new(),
+            added_validators_public_keys: Vec::new(),
+            removed_validators: ValidatorBitmap::new(),
+            seal: Vec::new(),
+            aggregated_seal: aggregated_seal.clone(),
+            parent_aggregated_seal: IstanbulAggregatedSeal::new(),
+        }
+        .to_rlp(&IstanbulExtraVanity::default());
+
+        let mut snapshot = Snapshot::new();
+        snapshot.validators = validators.into();
+
+        // the rejection of the verifier is propagated and the state is left untouched
+        let verifier = RecordingSealVerifier {
+            reject: true,
+            ..Default::default()
+        };
+        let engine = CeloEngine::new(&verifier);
+        let mut state = State::new(snapshot.clone(), &config, &clock);
+        state.set_engine(&engine);
+        let err = state.insert_header(&header).unwrap_err();
+        assert!(matches!(err.kind(), Kind::BlsVerifyError));
+        assert_eq!(state.snapshot(), &snapshot);
+
+        let verifier = RecordingSealVerifier::default();
+        let engine = CeloEngine::new(&verifier);
+        state.set_engine(&engine);
+        state.insert_header(&header).unwrap();
+        assert_eq!(state.snapshot().number, 1);
+        assert_eq!(
+            verifier.seals.borrow()[..],
+            [(header.hash().unwrap(), aggregated_seal)]
+        );
+    }
+
+    #[test]
+    fn test_follows_ecdsa_sealed_chain() {
+        let config = Config {
+            epoch_size: 2,
+            allowed_clock_skew: 0,
+            trusting_period: 0,
+
+            verify_epoch_headers: true,
+            verify_non_epoch_headers: true,
+            verify_header_timestamp: false,
+            trust_threshold: None,
+        };
+        let clock = FixedClock::default();
+        let (keys, validators) = secp256k1_validators(5);
+
+        let header = |number: u64, validators: &[Validator], signers: &[SecretKey]| {
+            let mut header = Header::new();
+            header.number = Integer::from(number);
+            header.extra = EcdsaEngine::extra(validators, &[]);
+
+            let hash = EcdsaEngine.header_hash(&header).unwrap();
+            let seals: Vec<Vec<u8>> = signers.iter().map(|key| sign_hash(key, &hash)).collect();
+            header.extra = EcdsaEngine::extra(validators, &seals);
+
+            header
+        };
+
+        let mut snapshot = Snapshot::new();
+        snapshot.validators = validators[..4].to_vec().into();
+        let mut state = State::new(snapshot, &config, &clock);
+        state.set_engine(&EcdsaEngine);
+
+        // the 2F+1 quorum of the 4 validators is 3
+        let err = state
+            .insert_header(&header(1, &validators[..4], &keys[..2]))
+            .unwrap_err();
+        assert!(matches!(err.kind(), Kind::MissingSeals { .. }));
+        state
+            .insert_header(&header(1, &validators[..4], &keys[..3]))
+            .unwrap();
+        assert_eq!(state.snapshot().number, 1);
+
+        // the epoch header replaces the validator set
+        state
+            .insert_header(&header(2, &validators[1..], &keys[1..4]))
+            .unwrap();
+        assert_eq!(state.snapshot().validators[..], validators[1..]);
+        assert!(state.snapshot().aggregated_seal.is_empty());
+
+        let err = state
+            .insert_header(&header(3, &validators[1..], &keys[..3]))
+            .unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidCommittedSeal { .. }));
+        // as well as the non-epoch header, once the votes change the validator set
+        state
+            .insert_header(&header(3, &validators[2..], &keys[2..]))
+            .unwrap();
+        assert_eq!(state.snapshot().validators[..], validators[2..]);
+
+        let err = state
+            .insert_header(&header(4, &validators[2..], &keys[1..3]))
+            .unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidCommittedSeal { .. }));
+    }
+
+    #[test]
+    fn test_validates_added_validator_keys() {
+        let config = Config {
+            epoch_size: 1,
+            allowed_clock_skew: 0,
+            trusting_period: 0,
+
+            verify_epoch_headers: false,
+            verify_non_epoch_headers: false,
+            verify_header_timestamp: false,
+            trust_threshold: None,
+        };
+        let (keys, validators) = generate_validators(3);
+
+        let epoch_header = |public_keys: Vec<SerializedPublicKey>| {
+            let mut header = Header::new();
+            header.number = Integer::from(1);
+            header.extra = IstanbulExtra {
+                added_validators: validators.iter().map(|v| v.address).collect(),
+                added_validators_public_keys: public_keys,
+                removed_validators: ValidatorBitmap::new(),
+                seal: Vec::new(),
+                aggregated_seal: IstanbulAggregatedSeal::new(),
+                parent_aggregated_seal: IstanbulAggregatedSeal::new(),
+            }
+            .to_rlp(&IstanbulExtraVanity::default());
+
+            header
+        };
+
+        let mut public_keys: Vec<SerializedPublicKey> =
+            validators.iter().map(|v| v.public_key).collect();
+        let header = epoch_header(public_keys.clone());
+
+        // the identity key is rejected and the state is left untouched
+        public_keys[2] = identity_public_key();
+        let clock = FixedClock::default();
+        let mut state = State::new(Snapshot::new(), &config, &clock);
+        let err = state.insert_header(&epoch_header(public_keys)).unwrap_err();
+        assert!(matches!(err.kind(), Kind::BlsInvalidPublicKey));
+        assert_eq!(state.snapshot(), &Snapshot::new());
+
+        // proof-of-possession is checked if available
+        let mut proofs = HashMap::new();
+        proofs.insert(
+            validators[1].address,
+            sign_proof_of_possession(&keys[0], &validators[0].address),
+        );
+        state.set_proofs_of_possession(proofs.clone());
+        let err = state.insert_header(&header).unwrap_err();
+        assert!(matches!(err.kind(), Kind::BlsInvalidProofOfPossession));
+
+        proofs.insert(
+            validators[1].address,
+            sign_proof_of_possession(&keys[1], &validators[1].address),
+        );
+        state.set_proofs_of_possession(proofs);
+        state.insert_header(&header).unwrap();
+        assert_eq!(state.snapshot().validators[..], validators[..]);
+    }
+
+    #[test]
+    fn test_applies_validator_set_diff_atomically() {
+        let config = Config {
+            epoch_size: 1,
+            allowed_clock_skew: 0,
+            trusting_period: 0,
+
+            verify_epoch_headers: false,
+            verify_non_epoch_headers: false,
+            verify_header_timestamp: false,
+            trust_threshold: None,
+        };
+        let (_, validators) = generate_validators(4);
+
+        let epoch_header = |added: &Validator, removed: u64| {
+            let mut header = Header::new();
+            header.number = Integer::from(1);
+            header.extra = IstanbulExtra {
+                added_validators: vec![added.address],
+                added_validators_public_keys: vec![added.public_key],
+                removed_validators: ValidatorBitmap::from(removed),
+                seal: Vec::new(),
+                aggregated_seal: IstanbulAggregatedSeal::new(),
+                parent_aggregated_seal: IstanbulAggregatedSeal::new(),
+            }
+            .to_rlp(&IstanbulExtraVanity::default());
+
+            header
+        };
+
+        let mut snapshot = Snapshot::new();
+        snapshot.validators = validators[..3].to_vec().into();
+        snapshot.validators.compute_aggregated_public_key().unwrap();
+        let clock = FixedClock::default();
+        let mut state = State::new(snapshot.clone(), &config, &clock);
+
+        // the removal succeeds but the addition doesn't, nothing is applied
+        let err = state
+            .insert_header(&epoch_header(&validators[1], 0b001))
+            .unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidValidatorSetDiff { .. }));
+        assert_eq!(state.snapshot(), &snapshot);
+
+        state
+            .insert_header(&epoch_header(&validators[3], 0b001))
+            .unwrap();
+        assert_eq!(state.snapshot().validators[..], validators[1..]);
+        let validator_set = &state.snapshot().validators;
+        assert_eq!(validator_set.position(&validators[3].address), Some(2));
+        assert!(validator_set.aggregated_public_key().is_some());
+    }
+
+    fn skipping_config() -> Config {
+        Config {
+            epoch_size: 10,
+            allowed_clock_skew: 0,
+            trusting_period: 0,
+
+            verify_epoch_headers: true,
+            verify_non_epoch_headers: true,
+            verify_header_timestamp: false,
+            trust_threshold: Some(TrustThreshold::ONE_THIRD),
+        }
+    }
+
+    fn epoch_extra(
+        added: &[Validator],
+        removed: u64,
+        aggregated_seal: IstanbulAggregatedSeal,
+    ) -> Vec<u8> {
+        IstanbulExtra {
+            added_validators: added.iter().map(|v| v.address).collect(),
+            added_validators_public_keys: added.iter().map(|v| v.public_key).collect(),
+            removed_validators: ValidatorBitmap::from(removed),
+            seal: Vec::new(),
+            aggregated_seal,
+            parent_aggregated_seal: IstanbulAggregatedSeal::new(),
+        }
+        .to_rlp(&IstanbulExtraVanity::default())
+    }
+
+    /// Creates the epoch header sealed by the keys selected by bitmap (keys are in the order of
+    /// the validator set)
+    fn sealed_epoch_header(
+        number: u64,
+        added: &[Validator],
+        removed: u64,
+        keys: &[PrivateKey],
+        bitmap: u64,
+    ) -> Header {
+        let mut header = Header::new();
+        header.number = Integer::from(number);
+        header.extra = epoch_extra(added, removed, IstanbulAggregatedSeal::new());
+        let hash = header.hash().unwrap();
+        header.extra = epoch_extra(added, removed, sign_aggregated_seal(keys, hash, bitmap, 0));
+
+        header
+    }
+
+    #[test]
+    fn test_skips_to_epoch_header_signed_by_trusted_validators() {
+        let config = skipping_config();
+        let (keys, validators) = generate_validators(6);
+
+        // validators 1 and 2 are still in the set, when 0 has left and 3, 4 have joined
+        let intermediate = vec![
+            sealed_epoch_header(20, &validators[3..4], 0, &keys[..3], 0b111),
+            sealed_epoch_header(30, &[], 0b0001, &keys[..4], 0b0111),
+            sealed_epoch_header(40, &validators[4..5], 0, &keys[1..4], 0b011),
+        ];
+        let epoch_header =
+            |keys: &[PrivateKey], bitmap: u64| sealed_epoch_header(50, &[], 0b0001, keys, bitmap);
+
+        let mut snapshot = Snapshot::new();
+        snapshot.number = 10;
+        snapshot.validators = validators[..3].to_vec().into();
+        let clock = FixedClock::default();
+        let mut state = State::new(snapshot.clone(), &config, &clock);
+
+        let mut proofs = HashMap::new();
+        for i in [3, 4] {
+            proofs.insert(
+                validators[i].address,
+                sign_proof_of_possession(&keys[i], &validators[i].address),
+            );
+        }
+        state.set_proofs_of_possession(proofs.clone());
+
+        // only the epoch headers can be skipped to
+        let mut header = epoch_header(&keys[1..5], 0b0111);
+        header.number = Integer::from(49);
+        let err = state
+            .insert_header_skipping(&header, &intermediate)
+            .unwrap_err();
+        assert!(matches!(err.kind(), Kind::HeaderVerificationError { .. }));
+
+        // none of the intermediate epoch headers can be left out
+        let err = state
+            .insert_header_skipping(
+                &epoch_header(&keys[1..5], 0b0111),
+                &[intermediate[0].clone(), intermediate[2].clone()],
+            )
+            .unwrap_err();
+        assert!(matches!(err.kind(), Kind::HeaderVerificationError { .. }));
+
+        // the quorum of the derived set is required
+        let err = state
+            .insert_header_skipping(&epoch_header(&keys[1..5], 0b0011), &intermediate)
+            .unwrap_err();
+        assert!(matches!(err.kind(), Kind::MissingSeals { .. }));
+
+        // a third of the trusted set is not enough
+        let err = state
+            .insert_header_skipping(&epoch_header(&keys[1..5], 0b1110), &intermediate)
+            .unwrap_err();
+        match err.kind() {
+            Kind::NotEnoughTrustedSigners { current, trusted } => {
+                assert_eq!((*current, *trusted), (1, 3))
+            }
+            kind => panic!("unexpected error kind: {:?}", kind),
+        }
+
+        // the trusted address with a different key doesn't count as trusted
+        let impostor = Validator {
+            address: validators[1].address,
+            public_key: validators[5].public_key,
+        };
+        let mut impostors = intermediate.clone();
+        impostors[2] = sealed_epoch_header(
+            40,
+            &[validators[4].clone(), impostor],
+            0b001,
+            &keys[1..4],
+            0b011,
+        );
+        let mut impostor_proofs = proofs.clone();
+        impostor_proofs.insert(
+            validators[1].address,
+            sign_proof_of_possession(&keys[5], &validators[1].address),
+        );
+        state.set_proofs_of_possession(impostor_proofs);
+        let err = state
+            .insert_header_skipping(&epoch_header(&keys[2..6], 0b1111), &impostors)
+            .unwrap_err();
+        assert!(matches!(err.kind(), Kind::NotEnoughTrustedSigners { .. }));
+        assert_eq!(state.snapshot(), &snapshot);
+        state.set_proofs_of_possession(proofs);
+
+        // the skipping verification has to be enabled
+        let disabled = Config {
+            trust_threshold: None,
+            ..config.clone()
+        };
+        let header = epoch_header(&keys[1..5], 0b0111);
+        let mut disabled_state = State::new(snapshot, &disabled, &clock);
+        let err = disabled_state
+            .insert_header_skipping(&header, &intermediate)
+            .unwrap_err();
+        assert!(matches!(err.kind(), Kind::HeaderVerificationError { .. }));
+
+        // the header's changeset is applied to the derived set
+        state
+            .insert_header_skipping(&header, &intermediate)
+            .unwrap();
+        assert_eq!(state.snapshot().number, 50);
+        assert_eq!(state.snapshot().hash, header.hash().unwrap());
+        assert_eq!(state.snapshot().validators[..], validators[2..5]);
+    }
+
+    #[test]
+    fn test_rejects_unproven_validator_set_when_skipping() {
+        let config = skipping_config();
+        let (keys, validators) = generate_validators(7);
+
+        let mut snapshot = Snapshot::new();
+        snapshot.number = 10;
+        snapshot.validators = validators[..3].to_vec().into();
+        let clock = FixedClock::default();
+        let mut state = State::new(snapshot.clone(), &config, &clock);
+
+        // the rogue key cancels out the trusted keys, so that the attacker alone can forge the
+        // seal that appears to be signed by the whole (trusted) validator set
+        let rogue = Validator {
+            address: validators[6].address,
+            public_key: rogue_public_key(&keys[6], &validators[..3]),
+        };
+        let mut rogue_validators = validators[..3].to_vec();
+        rogue_validators.push(rogue.clone());
+
+        let mut forged = Header::new();
+        forged.number = Integer::from(30);
+        forged.extra = epoch_extra(&[], 0, IstanbulAggregatedSeal::new());
+        let hash = forged.hash().unwrap();
+        let forged_seal = sign_aggregated_seal(&keys[6..], hash, 0b1, 0);
+        forged.extra = epoch_extra(&[], 0, forged_seal.clone());
+        let aggregated_seal = IstanbulAggregatedSeal {
+            bitmap: ValidatorBitmap::from(0b1111),
+            ..forged_seal
+        };
+        verify_aggregated_seal(hash, &rogue_validators.into(), &aggregated_seal).unwrap();
+        forged.extra = epoch_extra(&[], 0, aggregated_seal);
+
+        // the rogue key can't get into the set without a quorum of the trusted validators...
+        let forged_adding_rogue =
+            sealed_epoch_header(20, slice::from_ref(&rogue), 0, &keys[6..], 0b111);
+        let err = state
+            .insert_header_skipping(&forged, &[forged_adding_rogue])
+            .unwrap_err();
+        assert!(matches!(err.kind(), Kind::BlsVerifyError));
+
+        // ...and without the proof-of-possession
+        let adding_rogue = sealed_epoch_header(20, slice::from_ref(&rogue), 0, &keys[..3], 0b111);
+        let err = state
+            .insert_header_skipping(&forged, slice::from_ref(&adding_rogue))
+            .unwrap_err();
+        assert!(matches!(err.kind(), Kind::BlsMissingProofOfPossession));
+
+        let mut proofs = HashMap::new();
+        proofs.insert(
+            rogue.address,
+            sign_proof_of_possession(&keys[6], &rogue.address),
+        );
+        state.set_proofs_of_possession(proofs.clone());
+        let err = state
+            .insert_header_skipping(&forged, &[adding_rogue])
+            .unwrap_err();
+        assert!(matches!(err.kind(), Kind::BlsInvalidProofOfPossession));
+
+        // the genuine epoch header padded with the attacker's validator doesn't match the seal
+        let genuine = sealed_epoch_header(20, &validators[3..4], 0, &keys[..3], 0b111);
+        let mut padded = genuine.clone();
+        padded.extra = epoch_extra(
+            &[validators[3].clone(), validators[6].clone()],
+            0,
+            IstanbulExtra::from_rlp(&genuine.extra)
+                .unwrap()
+                .aggregated_seal,
+        );
+        for i in [3, 6] {
+            proofs.insert(
+                validators[i].address,
+                sign_proof_of_possession(&keys[i], &validators[i].address),
+            );
+        }
+        state.set_proofs_of_possession(proofs);
+
+        let header = sealed_epoch_header(30, &[], 0, &keys[..4], 0b0111);
+        let err = state
+            .insert_header_skipping(&header, &[padded])
+            .unwrap_err();
+        assert!(matches!(err.kind(), Kind::BlsVerifyError));
+
+        // nor can the attacker's epoch header be slipped in between
+        let err = state
+            .insert_header_skipping(
+                &header,
+                &[
+                    genuine.clone(),
+                    sealed_epoch_header(25, &validators[6..], 0, &keys[..4], 0b1111),
+                ],
+            )
+            .unwrap_err();
+        assert!(matches!(err.kind(), Kind::HeaderVerificationError { .. }));
+        assert_eq!(state.snapshot(), &snapshot);
+
+        state.insert_header_skipping(&header, &[genuine]).unwrap();
+        assert_eq!(state.snapshot().validators[..], validators[..4]);
+    }
+
     #[test]
     fn test_add_remove() {
         let snapshot = Snapshot::new();
         let config = state_config();
-        let mut state = State::new(snapshot, &config);
+        let clock = FixedClock::default();
+        let mut state = State::new(snapshot, &config, &clock);
         let mut result = state.add_validators(vec![Validator {
             address: bytes_to_address(&vec![0x3 as u8]),
             public_key: SerializedPublicKey::default(),
@@ -336,17 +1280,17 @@ mod tests {
         assert_eq!(current_addresses, expecected_addresses);
 
         // remove first validator
-        result = state.remove_validators(&Integer::from(1));
+        result = state.remove_validators(&ValidatorBitmap::from(1));
         assert_eq!(result, true);
         assert_eq!(state.snapshot.validators.len(), 2);
 
         // remove second validator
-        result = state.remove_validators(&Integer::from(2));
+        result = state.remove_validators(&ValidatorBitmap::from(2));
         assert_eq!(result, true);
         assert_eq!(state.snapshot.validators.len(), 1);
 
         // remove third validator
-        result = state.remove_validators(&Integer::from(1));
+        result = state.remove_validators(&ValidatorBitmap::from(1));
         assert_eq!(result, true);
         assert_eq!(state.snapshot.validators.len(), 0);
     }
@@ -410,8 +1354,9 @@ mod tests {
         for test in tests {
             let snapshot = Snapshot::new();
             let config = state_config();
+            let clock = FixedClock::default();
             let mut accounts = AccountPool::new();
-            let mut state = State::new(snapshot, &config);
+            let mut state = State::new(snapshot, &config, &clock);
 
             let validators = convert_val_names_to_validators(&mut accounts, test.validators);
             state.add_validators(validators.clone());
@@ -430,7 +1375,10 @@ mod tests {
             }
 
             let results = convert_val_names_to_validators(&mut accounts, test.results);
-            assert_eq!(compare(state.snapshot.validators, results), Ordering::Equal);
+            assert_eq!(
+                compare(state.snapshot.validators.to_vec(), results),
+                Ordering::Equal
+            );
         }
     }
 
@@ -453,14 +1401,14 @@ mod tests {
         let pub_bytes = p.serialize_uncompressed();
         let digest = &Keccak256::digest(&pub_bytes[1..])[12..];
 
-        Address::from_bytes(digest).unwrap().to_owned()
+        Address::from_slice(digest).unwrap()
     }
 
     fn bytes_to_address(bytes: &[u8]) -> Address {
         let mut v = vec![0x0; ADDRESS_LENGTH - bytes.len()];
         v.extend_from_slice(bytes);
 
-        Address::from_bytes(&v).unwrap().to_owned()
+        Address::from_slice(&v).unwrap()
     }
 
     fn generate_key() -> (SecretKey, PublicKey) {
@@ -487,12 +1435,12 @@ mod tests {
         accounts: &mut AccountPool,
         old_validators: &[Validator],
         val_names: Vec<String>,
-    ) -> Integer {
-        let mut bitmap = Integer::from(0);
+    ) -> ValidatorBitmap {
+        let mut bitmap = ValidatorBitmap::new();
         for v in val_names {
             for j in 0..old_validators.len() {
                 if &accounts.address(v.to_string()) == &old_validators.get(j).unwrap().address {
-                    bitmap.set_bit(j as u64, true);
+                    bitmap.set(j);
                 }
             }
         }
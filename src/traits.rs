@@ -1,14 +1,10 @@
+use crate::clock::Timestamp;
 use crate::errors::Error;
-
-// "Deafult" trait is implemented for a few selected fixed-array types. Taken we can't implement
-// the trait outside of a crate, we created a new one that mimics the stdlib.
-pub trait DefaultFrom {
-    fn default() -> Self;
-}
-
-pub trait FromBytes {
-    fn from_bytes(data: &[u8]) -> Result<&Self, Error>;
-}
+use crate::istanbul::is_last_block_of_epoch;
+use crate::types::header::{Address, Hash, Header};
+use crate::types::istanbul::{CommittedSeal, ConsensusExtra, IstanbulAggregatedSeal};
+use crate::types::state::{TrustThreshold, Validator};
+use crate::types::validator_set::ValidatorSet;
 
 pub trait ToRlp {
     fn to_rlp(&self) -> Vec<u8>;
@@ -20,6 +16,24 @@ pub trait FromRlp {
         Self: std::marker::Sized;
 }
 
+/// FromRlpStrict decodes only the canonical RLP encoding, so that two different byte strings
+/// never decode to the same value. It should be used for the data coming from untrusted parties.
+pub trait FromRlpStrict {
+    fn from_rlp_strict(bytes: &[u8]) -> Result<Self, Error>
+    where
+        Self: std::marker::Sized;
+}
+
+pub trait ToJson {
+    fn to_json(&self) -> String;
+}
+
+pub trait FromJson {
+    fn from_json(bytes: &[u8]) -> Result<Self, Error>
+    where
+        Self: std::marker::Sized;
+}
+
 pub trait StateConfig {
     /// Epoch size expressed in number of blocks
     fn epoch_size(&self) -> u64;
@@ -27,6 +41,14 @@ pub trait StateConfig {
     /// Defines how far block timestamp can go in the future
     fn allowed_clock_skew(&self) -> u64;
 
+    /// Defines how long (in seconds) the latest trusted header can be relied on. Once it's older
+    /// than that, the validators may have already unbonded and could sign a fake chain without
+    /// getting slashed, so the state expires and no more headers are accepted. Zero disables
+    /// the expiration, which is the default.
+    fn trusting_period(&self) -> u64 {
+        0
+    }
+
     /// Whether to validate (BLS signature) epoch headers. It should always be set to true.
     fn verify_epoch_headers(&self) -> bool;
 
@@ -36,4 +58,80 @@ pub trait StateConfig {
 
     /// Whether to verify headers time against current time. It's recommended to keep it true
     fn verify_header_timestamp(&self) -> bool;
+
+    /// Whether to reject the non-canonical RLP encoding of the istanbul extra-data
+    fn strict_rlp_decoding(&self) -> bool {
+        false
+    }
+
+    /// Fraction of the trusted validator set that has to sign the epoch header for it to be
+    /// accepted several epochs ahead of the state (see
+    /// [`State::insert_header_skipping`](crate::State::insert_header_skipping)). None disables
+    /// the skipping verification.
+    fn trust_threshold(&self) -> Option<TrustThreshold> {
+        None
+    }
+}
+
+pub trait Clock {
+    /// Returns the current time, used to reject the headers from the future
+    fn now(&self) -> Timestamp;
+}
+
+/// SealVerifier is the backend verifying the BLS aggregated seals, so that the pairings may be
+/// computed elsewhere (ie. by a hardware accelerated library or a host function)
+pub trait SealVerifier {
+    /// Verifies the BLS signature of the aggregated seal over the header hash, against the
+    /// validators picked by the seal bitmap. The seal format, the bitmap range and the quorum
+    /// are validated by the caller (see
+    /// [`validate_aggregated_seal`](crate::validate_aggregated_seal)), so that the backend only
+    /// has to check the signature.
+    fn verify_aggregated_signature(
+        &self,
+        header_hash: Hash,
+        validators: &ValidatorSet,
+        aggregated_seal: &IstanbulAggregatedSeal,
+    ) -> Result<(), Error>;
+}
+
+/// IstanbulEngine defines the chain specific parts of the Istanbul BFT consensus, that is the
+/// extra-data format, the seal scheme (along with the quorum the seal must reach) and the blocks
+/// changing the validator set. It lets the State follow the IBFT chains other than Celo, which
+/// is the default (see [`CeloEngine`](crate::CeloEngine)).
+pub trait IstanbulEngine {
+    /// Decodes the consensus fields of the header extra-data. With `strict` set, only the
+    /// canonical encoding should be accepted.
+    fn decode_extra(&self, header: &Header, strict: bool) -> Result<ConsensusExtra, Error>;
+
+    /// Returns the block hash, that is the hash committed by the validators
+    fn header_hash(&self, header: &Header) -> Result<Hash, Error>;
+
+    /// Verifies that the committed seal over the header hash was signed by a quorum of the
+    /// validator set
+    fn verify_seal(
+        &self,
+        header_hash: Hash,
+        validators: &ValidatorSet,
+        seal: &CommittedSeal,
+    ) -> Result<(), Error>;
+
+    /// Returns the members of the validator set that signed the committed seal. The seal is
+    /// expected to be already verified.
+    fn seal_signers(
+        &self,
+        header_hash: Hash,
+        validators: &ValidatorSet,
+        seal: &CommittedSeal,
+    ) -> Result<Vec<Validator>, Error>;
+
+    /// Whether the header applies its validator set change. Celo updates the validator set with
+    /// the epoch headers only, while the vote driven chains (ie. QBFT) may change it in any block.
+    fn changes_validator_set(&self, number: u64, epoch_size: u64) -> bool {
+        is_last_block_of_epoch(number, epoch_size)
+    }
+}
+
+pub trait SignerRecovery {
+    /// Recovers the signer address from the 65 bytes [R || S || V] ECDSA signature over the hash
+    fn recover(&self, hash: &Hash, signature: &[u8]) -> Result<Address, Error>;
 }
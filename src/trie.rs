@@ -0,0 +1,293 @@
+use crate::errors::{Error, Kind};
+use crate::types::header::{Hash, Header, HASH_LENGTH};
+use rlp::RlpStream;
+use sha3::{Digest, Keccak256};
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// Trie is an in-memory Merkle-Patricia trie builder. It only computes the root hash, so no
+/// intermediate nodes are stored.
+#[derive(Clone, Default, Debug)]
+pub struct Trie {
+    items: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl Trie {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts the value under the given key, overwriting the previous value (if any). Empty value
+    /// removes the key, as in the Ethereum trie.
+    pub fn insert(&mut self, key: &[u8], value: &[u8]) {
+        if value.is_empty() {
+            self.items.remove(key);
+        } else {
+            self.items.insert(key.to_vec(), value.to_vec());
+        }
+    }
+
+    /// Computes the trie root hash
+    pub fn root_hash(&self) -> Hash {
+        keccak(&encode_node(&self.nibble_items(), 0, None))
+    }
+
+    /// Returns the Merkle proof for the key, that is the hashed nodes (starting from the root)
+    /// on the path to the key. It can be used to prove both existence and absence of the key.
+    pub fn proof(&self, key: &[u8]) -> Vec<Vec<u8>> {
+        let path = to_nibbles(key);
+        let mut proof = Vec::new();
+
+        let root = encode_node(&self.nibble_items(), 0, Some((&path, &mut proof)));
+        if root.len() < HASH_LENGTH {
+            // root node is always referenced by hash
+            proof.push(root);
+        }
+        proof.reverse();
+
+        proof
+    }
+
+    fn nibble_items(&self) -> Vec<(Vec<u8>, &[u8])> {
+        self.items
+            .iter()
+            .map(|(key, value)| (to_nibbles(key), value.as_slice()))
+            .collect()
+    }
+}
+
+/// Computes the root of a trie where the items are keyed by the RLP encoded index
+/// (ie. transactions or receipts root)
+pub fn derive_sha(items: &[Vec<u8>]) -> Hash {
+    let mut trie = Trie::new();
+    for (i, item) in items.iter().enumerate() {
+        trie.insert(&rlp::encode(&(i as u64)), item);
+    }
+
+    trie.root_hash()
+}
+
+/// Verifies that the block body (consensus encoded transactions and receipts) matches the header
+pub fn verify_block_body(
+    header: &Header,
+    transactions: &[Vec<u8>],
+    receipts: &[Vec<u8>],
+) -> Result<(), Error> {
+    if derive_sha(transactions) != header.tx_hash {
+        return Err(Kind::TxRootMismatch.into());
+    }
+
+    if derive_sha(receipts) != header.receipt_hash {
+        return Err(Kind::ReceiptRootMismatch.into());
+    }
+
+    Ok(())
+}
+
+/// ProofCollector holds the path (in nibbles) of the proven key and the collected nodes
+type ProofCollector<'a> = Option<(&'a [u8], &'a mut Vec<Vec<u8>>)>;
+
+/// Returns RLP encoded node built from the sorted items, where `depth` nibbles of every key are
+/// already consumed by the parent nodes. Hashed nodes on the proof path are collected bottom-up.
+fn encode_node(items: &[(Vec<u8>, &[u8])], depth: usize, mut proof: ProofCollector) -> Vec<u8> {
+    let mut stream = RlpStream::new();
+
+    match items {
+        [] => {
+            stream.append_empty_data();
+        }
+        [(key, value)] => {
+            stream.begin_list(2);
+            stream.append(&hex_prefix(&key[depth..], true));
+            stream.append(value);
+        }
+        _ => {
+            let shared = shared_prefix_length(items, depth);
+            if shared > 0 {
+                // extension node
+                stream.begin_list(2);
+                stream.append(&hex_prefix(&items[0].0[depth..depth + shared], false));
+                let child = encode_node(
+                    items,
+                    depth + shared,
+                    on_path(&mut proof, items, depth..depth + shared),
+                );
+                append_node_reference(&mut stream, &child);
+            } else {
+                // branch node, the items are sorted so the key equal to the path goes first
+                stream.begin_list(17);
+
+                let (value, mut rest) = match items.first() {
+                    Some((key, value)) if key.len() == depth => (Some(value), &items[1..]),
+                    _ => (None, items),
+                };
+
+                for nibble in 0..16 {
+                    let count = rest
+                        .iter()
+                        .take_while(|(key, _)| key[depth] == nibble)
+                        .count();
+                    if count == 0 {
+                        stream.append_empty_data();
+                    } else {
+                        let child_items = &rest[..count];
+                        let child = encode_node(
+                            child_items,
+                            depth + 1,
+                            on_path(&mut proof, child_items, depth..depth + 1),
+                        );
+                        append_node_reference(&mut stream, &child);
+                    }
+                    rest = &rest[count..];
+                }
+
+                match value {
+                    Some(value) => stream.append(value),
+                    None => stream.append_empty_data(),
+                };
+            }
+        }
+    }
+
+    let node = stream.out();
+    if let Some((_, nodes)) = proof {
+        if node.len() >= HASH_LENGTH {
+            nodes.push(node.clone());
+        }
+    }
+
+    node
+}
+
+/// Passes the proof collector down to the child node, if the proven key path leads through it
+fn on_path<'a>(
+    proof: &'a mut ProofCollector,
+    child_items: &[(Vec<u8>, &[u8])],
+    nibbles: Range<usize>,
+) -> ProofCollector<'a> {
+    match proof {
+        Some((path, nodes)) if path.get(nibbles.clone()) == child_items[0].0.get(nibbles) => {
+            Some((path, &mut **nodes))
+        }
+        _ => None,
+    }
+}
+
+/// Nodes shorter than the hash are embedded in the parent node, otherwise they're referenced by hash
+fn append_node_reference(stream: &mut RlpStream, node: &[u8]) {
+    if node.len() < HASH_LENGTH {
+        stream.append_raw(node, 1);
+    } else {
+        stream.append(&keccak(node).as_ref());
+    }
+}
+
+fn shared_prefix_length(items: &[(Vec<u8>, &[u8])], depth: usize) -> usize {
+    let first = &items[0].0[depth..];
+
+    items[1..].iter().fold(first.len(), |shared, (key, _)| {
+        first
+            .iter()
+            .zip(key[depth..].iter())
+            .take(shared)
+            .take_while(|(a, b)| a == b)
+            .count()
+    })
+}
+
+pub(crate) fn to_nibbles(key: &[u8]) -> Vec<u8> {
+    key.iter().flat_map(|b| vec![b >> 4, b & 0x0f]).collect()
+}
+
+/// Compact (hex-prefix) encoding of the nibbles path
+fn hex_prefix(nibbles: &[u8], leaf: bool) -> Vec<u8> {
+    let flag = if leaf { 0x20 } else { 0x00 };
+    let mut out = Vec::with_capacity(nibbles.len() / 2 + 1);
+
+    let rest = if nibbles.len() % 2 == 1 {
+        out.push(flag | 0x10 | nibbles[0]);
+        &nibbles[1..]
+    } else {
+        out.push(flag);
+        nibbles
+    };
+
+    for pair in rest.chunks(2) {
+        out.push(pair[0] << 4 | pair[1]);
+    }
+
+    out
+}
+
+pub(crate) fn keccak(data: &[u8]) -> Hash {
+    let digest = Keccak256::digest(data);
+
+    // NOTE: keccak256 digest is always 32 bytes long
+    let mut hash = Hash::default();
+    hash.copy_from_slice(&digest[..HASH_LENGTH]);
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EMPTY_ROOT: &str = "56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421";
+
+    #[test]
+    fn computes_empty_root() {
+        assert_eq!(hex::encode(Trie::new().root_hash()), EMPTY_ROOT);
+        assert_eq!(hex::encode(derive_sha(&[])), EMPTY_ROOT);
+    }
+
+    #[test]
+    fn computes_trie_root() {
+        let tests = vec![
+            (
+                vec![
+                    ("do", "verb"),
+                    ("dog", "puppy"),
+                    ("doge", "coin"),
+                    ("horse", "stallion"),
+                ],
+                "5991bb8c6514148a29db676a14ac506cd2cd5775ace63c30a4fe457715e9ac84",
+            ),
+            (
+                vec![
+                    ("doe", "reindeer"),
+                    ("dog", "puppy"),
+                    ("dogglesworth", "cat"),
+                ],
+                "8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3",
+            ),
+        ];
+
+        for (items, root) in tests {
+            let mut trie = Trie::new();
+            for (key, value) in items.iter().rev() {
+                trie.insert(key.as_bytes(), value.as_bytes());
+            }
+
+            assert_eq!(hex::encode(trie.root_hash()), root);
+        }
+    }
+
+    #[test]
+    fn verifies_block_body() {
+        // Indexes above 127 are encoded with two bytes (ie. 0x8180), next to single byte keys
+        let transactions: Vec<Vec<u8>> = (0..130u8).map(|i| vec![0xc1, i]).collect();
+        let receipts: Vec<Vec<u8>> = Vec::new();
+
+        let mut header = Header::new();
+        header.tx_hash = derive_sha(&transactions);
+        header.receipt_hash = derive_sha(&receipts);
+
+        assert!(verify_block_body(&header, &transactions, &receipts).is_ok());
+
+        let err = verify_block_body(&header, &transactions[1..], &receipts).unwrap_err();
+        assert!(matches!(err.kind(), Kind::TxRootMismatch));
+
+        let err = verify_block_body(&header, &transactions, &transactions).unwrap_err();
+        assert!(matches!(err.kind(), Kind::ReceiptRootMismatch));
+    }
+}
@@ -0,0 +1,205 @@
+use crate::errors::{Error, Kind};
+use crate::serialization::json::{JsonCodec, JsonValue};
+use num_bigint::BigUint;
+use num_traits::Zero;
+use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
+
+/// ValidatorBitmap has an active bit for each selected validator (ie. the signers of an aggregated
+/// seal or the validators removed by an epoch header). The bit index is the position of the
+/// validator in the validator set.
+///
+/// On the wire the bitmap is a big-endian unsigned integer (RLP: bytes without leading zeros,
+/// JSON: 0x prefixed hex number), the same way as the celo-blockchain encodes it.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Default, Debug)]
+pub struct ValidatorBitmap(#[serde(with = "crate::serialization::bytes::hexnum")] BigUint);
+
+impl ValidatorBitmap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates the bitmap with the given validators selected, each of them must be a valid
+    /// position in the validator set of the given size
+    pub fn from_indices(
+        indices: impl IntoIterator<Item = usize>,
+        validator_set_size: usize,
+    ) -> Result<Self, Error> {
+        let mut bitmap = Self::new();
+        for index in indices {
+            bitmap.set(index);
+        }
+        bitmap.validate(validator_set_size)?;
+
+        Ok(bitmap)
+    }
+
+    /// Decodes the bitmap from the big-endian bytes
+    pub fn from_bytes_be(bytes: &[u8]) -> Self {
+        ValidatorBitmap(BigUint::from_bytes_be(bytes))
+    }
+
+    /// Encodes the bitmap into big-endian bytes, where the empty bitmap is an empty vector
+    pub fn to_bytes_be(&self) -> Vec<u8> {
+        // BigUint library returns vec![0] for zero value where
+        // the celo blockchain expects empty vector
+        if self.0.is_zero() {
+            Vec::new()
+        } else {
+            self.0.to_bytes_be()
+        }
+    }
+
+    /// Checks that the bitmap refers only to the validators of the set of the given size
+    pub fn validate(&self, validator_set_size: usize) -> Result<(), Error> {
+        if self.0.bits() > validator_set_size as u64 {
+            return Err(Kind::BitmapOutOfRange {
+                bits: self.0.bits(),
+                validators: validator_set_size,
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+
+    pub fn is_set(&self, index: usize) -> bool {
+        self.0.bit(index as u64)
+    }
+
+    pub fn set(&mut self, index: usize) {
+        self.0.set_bit(index as u64, true);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    /// Returns the number of selected validators
+    pub fn count(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// Iterates over the positions of the selected validators, in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.0.bits() as usize).filter(move |index| self.is_set(*index))
+    }
+
+    pub fn union(&self, other: &ValidatorBitmap) -> ValidatorBitmap {
+        ValidatorBitmap(&self.0 | &other.0)
+    }
+
+    pub fn intersection(&self, other: &ValidatorBitmap) -> ValidatorBitmap {
+        ValidatorBitmap(&self.0 & &other.0)
+    }
+}
+
+impl From<u64> for ValidatorBitmap {
+    fn from(bitmap: u64) -> Self {
+        ValidatorBitmap(BigUint::from(bitmap))
+    }
+}
+
+impl From<BigUint> for ValidatorBitmap {
+    fn from(bitmap: BigUint) -> Self {
+        ValidatorBitmap(bitmap)
+    }
+}
+
+impl std::fmt::LowerHex for ValidatorBitmap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+
+impl Encodable for ValidatorBitmap {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.append_internal(&self.to_bytes_be());
+    }
+}
+
+impl Decodable for ValidatorBitmap {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        rlp.decoder()
+            .decode_value(|bytes| Ok(ValidatorBitmap::from_bytes_be(bytes)))
+    }
+}
+
+impl JsonCodec for ValidatorBitmap {
+    fn to_json_value(&self) -> JsonValue {
+        JsonValue::hex_big_uint(&self.0)
+    }
+
+    fn from_json_value(value: &JsonValue) -> Result<Self, Error> {
+        Ok(ValidatorBitmap(value.as_hex_big_uint()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serialization::rlp::big_int_to_rlp_compat_bytes;
+    use crate::types::test_utils::arb_bitmap;
+    use num_bigint::BigInt as Integer;
+    use proptest::prelude::*;
+
+    #[test]
+    fn creates_bitmap_for_validator_set() {
+        let bitmap = ValidatorBitmap::from_indices(vec![0, 2, 3], 4).unwrap();
+        assert_eq!(bitmap, ValidatorBitmap::from(0b1101));
+        assert_eq!(bitmap.iter().collect::<Vec<usize>>(), vec![0, 2, 3]);
+        assert_eq!(bitmap.count(), 3);
+        assert!(bitmap.is_set(2) && !bitmap.is_set(1) && !bitmap.is_set(4));
+
+        let err = ValidatorBitmap::from_indices(vec![0, 4], 4).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            Kind::BitmapOutOfRange {
+                bits: 5,
+                validators: 4
+            }
+        ));
+
+        assert!(ValidatorBitmap::new().is_empty());
+        assert!(ValidatorBitmap::new().validate(0).is_ok());
+        assert_eq!(ValidatorBitmap::new().iter().count(), 0);
+    }
+
+    #[test]
+    fn combines_bitmaps() {
+        let a = ValidatorBitmap::from(0b0111);
+        let b = ValidatorBitmap::from(0b1110);
+
+        assert_eq!(a.union(&b), ValidatorBitmap::from(0b1111));
+        assert_eq!(a.intersection(&b), ValidatorBitmap::from(0b0110));
+        assert!(a.intersection(&ValidatorBitmap::new()).is_empty());
+    }
+
+    #[test]
+    fn rejects_negative_bitmap() {
+        let err = ValidatorBitmap::from_json_value(&JsonValue::String("-0x1".into())).unwrap_err();
+        assert!(matches!(err.kind(), Kind::JsonDecodeError { .. }));
+
+        assert!(serde_json::from_str::<ValidatorBitmap>("\"-0x1\"").is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn encodes_as_unsigned_big_int(bitmap in arb_bitmap()) {
+            let integer = Integer::from(bitmap.0.clone());
+
+            // the same bytes as the BigInt based codecs
+            let mut stream = RlpStream::new();
+            stream.append(&big_int_to_rlp_compat_bytes(&integer));
+            prop_assert_eq!(rlp::encode(&bitmap), stream.out());
+            prop_assert_eq!(
+                serde_json::to_string(&bitmap).unwrap(),
+                format!("\"0x{:x}\"", integer)
+            );
+            prop_assert_eq!(bitmap.to_json_value(), JsonValue::hex_big_int(&integer));
+
+            prop_assert_eq!(&rlp::decode::<ValidatorBitmap>(&rlp::encode(&bitmap)).unwrap(), &bitmap);
+            let json = serde_json::to_string(&bitmap).unwrap();
+            prop_assert_eq!(&serde_json::from_str::<ValidatorBitmap>(&json).unwrap(), &bitmap);
+        }
+    }
+}
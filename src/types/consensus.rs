@@ -0,0 +1,361 @@
+use crate::errors::{Error, Kind};
+use crate::traits::{FromRlp, ToRlp};
+use crate::types::header::{Address, Hash, Header};
+use crate::types::istanbul::IstanbulMsg;
+use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
+use rlp_derive::{RlpDecodable, RlpEncodable};
+use sha3::{Digest, Keccak256};
+
+/// View identifies the IBFT consensus round at the given block height (sequence)
+#[derive(Serialize, Deserialize, RlpEncodable, RlpDecodable, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct View {
+    pub round: u64,
+    pub sequence: u64,
+}
+
+/// Subject is the payload of the Prepare message, it refers to the proposal by its digest
+#[derive(Serialize, Deserialize, RlpEncodable, RlpDecodable, Clone, PartialEq, Debug)]
+pub struct Subject {
+    pub view: View,
+    pub digest: Hash,
+}
+
+/// CommittedSubject is the payload of the Commit message, it carries the validator's BLS seal
+/// over the proposal
+#[derive(Serialize, Deserialize, RlpEncodable, RlpDecodable, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CommittedSubject {
+    pub subject: Subject,
+
+    #[serde(with = "crate::serialization::bytes::hexstring")]
+    pub committed_seal: Vec<u8>,
+
+    #[serde(with = "crate::serialization::bytes::hexstring")]
+    pub epoch_validator_set_seal: Vec<u8>,
+}
+
+/// Proposal is a block proposed by the round proposer. Only the header is decoded, the remaining
+/// block fields are kept as raw RLP items.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Proposal {
+    pub header: Header,
+    pub body: Vec<Vec<u8>>,
+}
+
+impl Proposal {
+    /// Returns the proposal digest (ie. block hash)
+    pub fn hash(&self) -> Result<Hash, Error> {
+        self.header.hash()
+    }
+}
+
+impl Encodable for Proposal {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(1 + self.body.len());
+
+        s.append(&self.header);
+        for item in self.body.iter() {
+            s.append_raw(item, 1);
+        }
+    }
+}
+
+impl Decodable for Proposal {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        let body = (1..rlp.item_count()?)
+            .map(|i| rlp.at(i).map(|item| item.as_raw().to_vec()))
+            .collect::<Result<Vec<Vec<u8>>, DecoderError>>()?;
+
+        Ok(Proposal {
+            header: rlp.val_at(0)?,
+            body,
+        })
+    }
+}
+
+/// Message is a signed IBFT consensus message, exchanged between the validators
+#[derive(Clone, PartialEq, Debug)]
+pub struct Message {
+    pub code: IstanbulMsg,
+
+    /// RLP encoded payload of the message (ie. Subject for Prepare message)
+    pub msg: Vec<u8>,
+
+    /// The sender of the message
+    pub address: Address,
+
+    /// ECDSA signature of the message created by the sender
+    pub signature: Vec<u8>,
+}
+
+impl Message {
+    /// Returns the hash signed by the sender (keccak256 of the message with empty signature)
+    pub fn signing_hash(&self) -> Result<Hash, Error> {
+        let unsigned = Message {
+            signature: Vec::new(),
+            ..self.clone()
+        };
+        let digest = Keccak256::digest(&unsigned.to_rlp());
+
+        Hash::from_slice(&digest)
+    }
+
+    /// Decodes the Prepare message payload
+    pub fn subject(&self) -> Result<Subject, Error> {
+        self.payload(IstanbulMsg::Prepare)
+    }
+
+    /// Decodes the Commit message payload
+    pub fn committed_subject(&self) -> Result<CommittedSubject, Error> {
+        self.payload(IstanbulMsg::Commit)
+    }
+
+    /// Decodes the PrePrepare message payload
+    pub fn preprepare(&self) -> Result<PrePrepare, Error> {
+        self.payload(IstanbulMsg::PrePrepare)
+    }
+
+    /// Decodes the RoundChange message payload
+    pub fn round_change(&self) -> Result<RoundChange, Error> {
+        self.payload(IstanbulMsg::RoundChange)
+    }
+
+    fn payload<T: Decodable>(&self, code: IstanbulMsg) -> Result<T, Error> {
+        if self.code != code {
+            return Err(Kind::UnexpectedMessageCode {
+                current: self.code as u64,
+                expected: code as u64,
+            }
+            .into());
+        }
+
+        rlp::decode(&self.msg).map_err(|e| Kind::RlpDecodeError.context(e).into())
+    }
+}
+
+impl Encodable for Message {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(4);
+
+        s.append(&(self.code as u64));
+        s.append(&self.msg);
+        s.append(&self.address);
+        s.append(&self.signature);
+    }
+}
+
+impl Decodable for Message {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        let code = IstanbulMsg::from_code(rlp.val_at(0)?)
+            .ok_or(DecoderError::Custom("unknown message code"))?;
+
+        Ok(Message {
+            code,
+            msg: rlp.val_at(1)?,
+            address: rlp.val_at(2)?,
+            signature: rlp.val_at(3)?,
+        })
+    }
+}
+
+impl ToRlp for Message {
+    fn to_rlp(&self) -> Vec<u8> {
+        rlp::encode(self)
+    }
+}
+
+impl FromRlp for Message {
+    fn from_rlp(bytes: &[u8]) -> Result<Self, Error> {
+        rlp::decode(&bytes).map_err(|e| Kind::RlpDecodeError.context(e).into())
+    }
+}
+
+/// PreparedCertificate proves that a quorum of validators prepared (or committed) the proposal
+#[derive(Clone, PartialEq, Debug)]
+pub struct PreparedCertificate {
+    pub proposal: Proposal,
+    pub prepare_or_commit_messages: Vec<Message>,
+}
+
+impl PreparedCertificate {
+    /// Returns true if the certificate carries no messages (ie. validator didn't prepare any block)
+    pub fn is_empty(&self) -> bool {
+        self.prepare_or_commit_messages.is_empty()
+    }
+}
+
+impl Encodable for PreparedCertificate {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(2);
+
+        s.append(&self.proposal);
+        s.append_list(&self.prepare_or_commit_messages);
+    }
+}
+
+impl Decodable for PreparedCertificate {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        Ok(PreparedCertificate {
+            proposal: rlp.val_at(0)?,
+            prepare_or_commit_messages: decode_messages(&rlp.at(1)?)?,
+        })
+    }
+}
+
+/// RoundChangeCertificate proves that a quorum of validators agreed to move to a new round
+#[derive(Clone, PartialEq, Debug)]
+pub struct RoundChangeCertificate {
+    pub round_change_messages: Vec<Message>,
+}
+
+impl RoundChangeCertificate {
+    /// Returns true if the certificate carries no messages (ie. proposal for the first round)
+    pub fn is_empty(&self) -> bool {
+        self.round_change_messages.is_empty()
+    }
+}
+
+impl Encodable for RoundChangeCertificate {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(1);
+
+        s.append_list(&self.round_change_messages);
+    }
+}
+
+impl Decodable for RoundChangeCertificate {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        Ok(RoundChangeCertificate {
+            round_change_messages: decode_messages(&rlp.at(0)?)?,
+        })
+    }
+}
+
+/// PrePrepare is the payload of the PrePrepare message, sent by the round proposer
+#[derive(Clone, PartialEq, Debug)]
+pub struct PrePrepare {
+    pub view: View,
+    pub proposal: Proposal,
+    pub round_change_certificate: RoundChangeCertificate,
+}
+
+impl Encodable for PrePrepare {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(3);
+
+        s.append(&self.view);
+        s.append(&self.proposal);
+        s.append(&self.round_change_certificate);
+    }
+}
+
+impl Decodable for PrePrepare {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        Ok(PrePrepare {
+            view: rlp.val_at(0)?,
+            proposal: rlp.val_at(1)?,
+            round_change_certificate: rlp.val_at(2)?,
+        })
+    }
+}
+
+/// RoundChange is the payload of the RoundChange message
+#[derive(Clone, PartialEq, Debug)]
+pub struct RoundChange {
+    pub view: View,
+    pub prepared_certificate: PreparedCertificate,
+}
+
+impl Encodable for RoundChange {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(2);
+
+        s.append(&self.view);
+        s.append(&self.prepared_certificate);
+    }
+}
+
+impl Decodable for RoundChange {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        Ok(RoundChange {
+            view: rlp.val_at(0)?,
+            prepared_certificate: rlp.val_at(1)?,
+        })
+    }
+}
+
+fn decode_messages(rlp: &Rlp) -> Result<Vec<Message>, DecoderError> {
+    (0..rlp.item_count()?).map(|i| rlp.val_at(i)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::test_utils::arb_message;
+    use proptest::prelude::*;
+
+    #[test]
+    fn encodes_consensus_messages() {
+        let subject = Subject {
+            view: View {
+                round: 1,
+                sequence: 100,
+            },
+            digest: Hash::from([7; 32]),
+        };
+        let prepare = Message {
+            code: IstanbulMsg::Prepare,
+            msg: rlp::encode(&subject),
+            address: Address::from([1; 20]),
+            signature: vec![2; 65],
+        };
+
+        let decoded = Message::from_rlp(&prepare.to_rlp()).unwrap();
+        assert_eq!(decoded, prepare);
+        assert_eq!(decoded.subject().unwrap(), subject);
+        assert!(decoded.committed_subject().is_err());
+
+        let mut header = Header::new();
+        header.number = 100.into();
+        let round_change = RoundChange {
+            view: View {
+                round: 2,
+                sequence: 100,
+            },
+            prepared_certificate: PreparedCertificate {
+                proposal: Proposal {
+                    header,
+                    body: vec![
+                        rlp::encode_list::<Vec<u8>, Vec<u8>>(&[]),
+                        rlp::encode(&vec![3u8; 32]),
+                    ],
+                },
+                prepare_or_commit_messages: vec![prepare],
+            },
+        };
+
+        let encoded = rlp::encode(&round_change);
+        let decoded: RoundChange = rlp::decode(&encoded).unwrap();
+        assert_eq!(decoded, round_change);
+        assert_eq!(rlp::encode(&decoded), encoded);
+    }
+
+    #[test]
+    fn rejects_unknown_message_code() {
+        let mut stream = RlpStream::new();
+        stream.begin_list(4);
+        stream.append(&4u64);
+        stream.append(&Vec::<u8>::new());
+        stream.append(&[0u8; 20].as_ref());
+        stream.append(&Vec::<u8>::new());
+
+        assert!(Message::from_rlp(&stream.out()).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn message_rlp_round_trip(message in arb_message()) {
+            prop_assert_eq!(&Message::from_rlp(&message.to_rlp()).unwrap(), &message);
+        }
+    }
+}
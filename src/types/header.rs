@@ -1,12 +1,12 @@
+use crate::clock::Timestamp;
 use crate::errors::{Error, Kind};
+use crate::fixed_bytes;
 use crate::istanbul::istanbul_filtered_header;
-use crate::serialization::rlp::{
-    big_int_to_rlp_compat_bytes, rlp_list_field_from_bytes, rlp_to_big_int,
-};
-use crate::slice_as_array_ref;
-use crate::traits::{DefaultFrom, FromBytes, FromRlp, ToRlp};
-use crate::types::istanbul::ISTANBUL_EXTRA_VANITY_LENGTH;
+use crate::serialization::json::{JsonCodec, JsonValue};
+use crate::serialization::rlp::{big_int_to_rlp_compat_bytes, rlp_decode_strict, rlp_to_big_int};
+use crate::traits::{FromJson, FromRlp, FromRlpStrict, ToJson, ToRlp};
 use num_bigint::BigInt as Integer;
+use num_traits::ToPrimitive;
 use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
 use sha3::{Digest, Keccak256};
 
@@ -19,39 +19,90 @@ pub const ADDRESS_LENGTH: usize = 20;
 /// BLOOM_BYTE_LENGTH represents the number of bytes used in a header log bloom
 pub const BLOOM_BYTE_LENGTH: usize = 256;
 
-/// Hash is the output of the cryptographic digest function
-pub type Hash = [u8; HASH_LENGTH];
+fixed_bytes! {
+    /// Hash is the output of the cryptographic digest function
+    pub struct Hash(HASH_LENGTH);
+}
+
+fixed_bytes! {
+    @custom_display
+    /// Address represents the 20 byte address of an Ethereum account. It's displayed with the
+    /// EIP-55 mixed-case checksum.
+    pub struct Address(ADDRESS_LENGTH);
+}
+
+fixed_bytes! {
+    /// Bloom represents a 2048 bit bloom filter
+    pub struct Bloom(BLOOM_BYTE_LENGTH);
+}
+
+impl Address {
+    /// Returns the 0x prefixed hex string with the EIP-55 checksum, that is a hex letter is
+    /// uppercased if the matching nibble of keccak256(lowercase hex address) is at least 8
+    pub fn to_checksum_string(&self) -> String {
+        let digits = hex::encode(self.0);
+        let digest = Keccak256::digest(digits.as_bytes());
+
+        let checksummed: String = digits
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+                let nibble = (digest[i / 2] >> if i % 2 == 0 { 4 } else { 0 }) & 0x0f;
+                if nibble >= 8 {
+                    c.to_ascii_uppercase()
+                } else {
+                    c
+                }
+            })
+            .collect();
+
+        format!("0x{}", checksummed)
+    }
+}
 
-/// Address represents the 20 byte address of an Ethereum account
-pub type Address = [u8; ADDRESS_LENGTH];
+impl std::fmt::Display for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_checksum_string())
+    }
+}
+
+impl std::str::FromStr for Address {
+    type Err = Error;
+
+    /// Parses the hex address. The mixed-case input must carry a valid EIP-55 checksum, while
+    /// the all lowercase (or uppercase) one is accepted as is.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let address = Address::from_hex(s)?;
 
-/// Bloom represents a 2048 bit bloom filter
-pub type Bloom = [u8; BLOOM_BYTE_LENGTH];
+        let digits = s.strip_prefix("0x").unwrap_or(s);
+        let mixed_case = digits.chars().any(|c| c.is_ascii_lowercase())
+            && digits.chars().any(|c| c.is_ascii_uppercase());
+        if mixed_case && address.to_checksum_string()[2..] != *digits {
+            return Err(Kind::InvalidAddressChecksum.into());
+        }
+
+        Ok(address)
+    }
+}
 
 /// Header contains block metadata in Celo Blockchain
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct Header {
-    #[serde(with = "crate::serialization::bytes::hexstring")]
     pub parent_hash: Hash,
 
-    #[serde(with = "crate::serialization::bytes::hexstring")]
     #[serde(rename = "miner")]
     pub coinbase: Address,
 
-    #[serde(with = "crate::serialization::bytes::hexstring")]
     #[serde(rename = "stateRoot")]
     pub root: Hash,
 
-    #[serde(with = "crate::serialization::bytes::hexstring")]
     #[serde(rename = "transactionsRoot")]
     pub tx_hash: Hash,
 
-    #[serde(with = "crate::serialization::bytes::hexstring")]
     #[serde(rename = "receiptsRoot")]
     pub receipt_hash: Hash,
 
-    #[serde(with = "crate::serialization::bytes::hexstring")]
     #[serde(rename = "logsBloom")]
     pub bloom: Bloom,
 
@@ -87,14 +138,23 @@ impl Header {
     }
 
     pub fn hash(&self) -> Result<Hash, Error> {
-        if self.extra.len() >= ISTANBUL_EXTRA_VANITY_LENGTH {
-            let istanbul_header = istanbul_filtered_header(&self, true);
-            if istanbul_header.is_ok() {
-                return rlp_hash(&istanbul_header?);
-            }
-        }
+        // NOTE: unlike celo-blockchain, we don't fall back to the plain RLP hash
+        // if the istanbul extra-data is malformed
+        let istanbul_header = istanbul_filtered_header(&self, true)?;
+
+        rlp_hash(&istanbul_header)
+    }
+
+    /// Returns the block number as u64, errors out if the number doesn't fit
+    pub fn number_u64(&self) -> Result<u64, Error> {
+        self.number
+            .to_u64()
+            .ok_or_else(|| Kind::InvalidBlockNumber.into())
+    }
 
-        rlp_hash(self)
+    /// Returns the block time, the header carries it in seconds
+    pub fn timestamp(&self) -> Timestamp {
+        Timestamp::from_secs(self.time)
     }
 }
 
@@ -104,6 +164,12 @@ impl FromRlp for Header {
     }
 }
 
+impl FromRlpStrict for Header {
+    fn from_rlp_strict(bytes: &[u8]) -> Result<Self, Error> {
+        rlp_decode_strict(&bytes).map_err(|e| Kind::RlpDecodeError.context(e).into())
+    }
+}
+
 impl ToRlp for Header {
     fn to_rlp(&self) -> Vec<u8> {
         rlp::encode(self)
@@ -115,22 +181,22 @@ impl Encodable for Header {
         s.begin_list(10);
 
         // parent_hash
-        s.append(&self.parent_hash.as_ref());
+        s.append(&self.parent_hash);
 
         // coinbase
-        s.append(&self.coinbase.as_ref());
+        s.append(&self.coinbase);
 
         // root
-        s.append(&self.root.as_ref());
+        s.append(&self.root);
 
         // tx_hash
-        s.append(&self.tx_hash.as_ref());
+        s.append(&self.tx_hash);
 
         // receipt_hash
-        s.append(&self.receipt_hash.as_ref());
+        s.append(&self.receipt_hash);
 
         // bloom
-        s.append(&self.bloom.as_ref());
+        s.append(&self.bloom);
 
         // number
         s.append(&big_int_to_rlp_compat_bytes(&self.number));
@@ -149,12 +215,12 @@ impl Encodable for Header {
 impl Decodable for Header {
     fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
         Ok(Header {
-            parent_hash: rlp_list_field_from_bytes(rlp, 0)?,
-            coinbase: rlp_list_field_from_bytes(rlp, 1)?,
-            root: rlp_list_field_from_bytes(rlp, 2)?,
-            tx_hash: rlp_list_field_from_bytes(rlp, 3)?,
-            receipt_hash: rlp_list_field_from_bytes(rlp, 4)?,
-            bloom: rlp_list_field_from_bytes(rlp, 5)?,
+            parent_hash: rlp.val_at(0)?,
+            coinbase: rlp.val_at(1)?,
+            root: rlp.val_at(2)?,
+            tx_hash: rlp.val_at(3)?,
+            receipt_hash: rlp.val_at(4)?,
+            bloom: rlp.val_at(5)?,
             number: rlp_to_big_int(rlp, 6)?,
             gas_used: rlp.val_at(7)?,
             time: rlp.val_at(8)?,
@@ -163,33 +229,67 @@ impl Decodable for Header {
     }
 }
 
-impl DefaultFrom for Bloom {
-    fn default() -> Self {
-        [0; BLOOM_BYTE_LENGTH]
+impl JsonCodec for Header {
+    fn to_json_value(&self) -> JsonValue {
+        JsonValue::object(vec![
+            ("parentHash", JsonValue::hex(&self.parent_hash)),
+            ("miner", JsonValue::hex(&self.coinbase)),
+            ("stateRoot", JsonValue::hex(&self.root)),
+            ("transactionsRoot", JsonValue::hex(&self.tx_hash)),
+            ("receiptsRoot", JsonValue::hex(&self.receipt_hash)),
+            ("logsBloom", JsonValue::hex(&self.bloom)),
+            ("number", JsonValue::hex_big_int(&self.number)),
+            ("gasUsed", JsonValue::hex_u64(self.gas_used)),
+            ("timestamp", JsonValue::hex_u64(self.time)),
+            ("extraData", JsonValue::hex(&self.extra)),
+        ])
+    }
+
+    fn from_json_value(value: &JsonValue) -> Result<Self, Error> {
+        // NOTE: the fields that are not a part of the header (ie. hash, size) are ignored
+        Ok(Header {
+            parent_hash: value.field("parentHash")?.as_hex_bytes()?,
+            coinbase: value.field("miner")?.as_hex_bytes()?,
+            root: value.field("stateRoot")?.as_hex_bytes()?,
+            tx_hash: value.field("transactionsRoot")?.as_hex_bytes()?,
+            receipt_hash: value.field("receiptsRoot")?.as_hex_bytes()?,
+            bloom: value.field("logsBloom")?.as_hex_bytes()?,
+            number: value.field("number")?.as_hex_big_int()?,
+            gas_used: value.field("gasUsed")?.as_hex_u64()?,
+            time: value.field("timestamp")?.as_hex_u64()?,
+            extra: value.field("extraData")?.as_hex()?,
+        })
     }
 }
 
-impl FromBytes for Bloom {
-    fn from_bytes(data: &[u8]) -> Result<&Bloom, Error> {
-        slice_as_array_ref!(&data[..BLOOM_BYTE_LENGTH], BLOOM_BYTE_LENGTH)
+impl ToJson for Header {
+    fn to_json(&self) -> String {
+        self.to_json_value().encode()
     }
 }
 
-impl FromBytes for Address {
-    fn from_bytes(data: &[u8]) -> Result<&Address, Error> {
-        slice_as_array_ref!(&data[..ADDRESS_LENGTH], ADDRESS_LENGTH)
+impl FromJson for Header {
+    fn from_json(bytes: &[u8]) -> Result<Self, Error> {
+        Self::from_json_value(&JsonValue::parse(bytes)?)
     }
 }
 
 fn rlp_hash(header: &Header) -> Result<Hash, Error> {
     let digest = Keccak256::digest(&rlp::encode(header));
 
-    Ok(slice_as_array_ref!(&digest[..HASH_LENGTH], HASH_LENGTH)?.to_owned())
+    Hash::from_slice(&digest)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::bitmap::ValidatorBitmap;
+    use crate::types::test_utils::arb_header;
+    use proptest::prelude::*;
+    use crate::types::istanbul::{
+        IstanbulAggregatedSeal, IstanbulExtra, IstanbulExtraVanity, AGGREGATED_SIGNATURE_LENGTH,
+        ISTANBUL_EXTRA_VANITY_LENGTH, PROPOSER_SEAL_LENGTH,
+    };
 
     const HEADER_WITH_EMPTY_EXTRA: &str = "f901a6a07285abd5b24742f184ad676e31f6054663b3529bc35ea2fcad8a3e0f642a46f7948888f1f195afa192cfee860698584c030f4c9db1a0ecc60e00b3fe5ce9f6e1a10e5469764daf51f1fe93c22ec3f9a7583a80357217a0d35d334d87c0cc0a202e3756bf81fae08b1575f286c7ee7a3f8df4f0f3afc55da056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421b901000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000001825208845c47775c80";
 
@@ -233,6 +333,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn strictly_decodes_header_from_rlp() {
+        let bytes = hex::decode(&HEADER_WITH_EMPTY_EXTRA).unwrap();
+        let expected = Header::from_rlp(&bytes).unwrap();
+        assert_eq!(Header::from_rlp_strict(&bytes).unwrap(), expected);
+
+        // block number prefixed with zero
+        let padded = hex::decode(
+            HEADER_WITH_EMPTY_EXTRA
+                .replacen("f901a6", "f901a8", 1)
+                .replace("01825208845c47775c80", "820001825208845c47775c80"),
+        )
+        .unwrap();
+        let trailing = [&bytes[..], &[0x80]].concat();
+
+        for bytes in vec![padded, trailing] {
+            assert_eq!(Header::from_rlp(&bytes).unwrap(), expected);
+
+            let err = Header::from_rlp_strict(&bytes).unwrap_err();
+            assert!(matches!(err.kind(), Kind::RlpDecodeError));
+        }
+    }
+
     #[test]
     fn serializes_and_deserializes_to_json() {
         for bytes in vec![hex::decode(&HEADER_WITH_EMPTY_EXTRA).unwrap()].iter() {
@@ -244,36 +367,179 @@ mod tests {
         }
     }
 
+    #[test]
+    fn encodes_and_decodes_float_free_json() {
+        let header = Header::from_rlp(&hex::decode(&HEADER_WITH_EMPTY_EXTRA).unwrap()).unwrap();
+
+        // compatible with the serde JSON format
+        let json = header.to_json();
+        assert_eq!(serde_json::from_str::<Header>(&json).unwrap(), header);
+        assert_eq!(Header::from_json(json.as_bytes()).unwrap(), header);
+
+        // the RPC response carries additional fields
+        let mut rpc_header: serde_json::Value = serde_json::to_value(&header).unwrap();
+        rpc_header["hash"] = serde_json::json!(format!("0x{}", "11".repeat(HASH_LENGTH)));
+        rpc_header["size"] = serde_json::json!("0x260");
+        rpc_header["transactions"] = serde_json::json!([]);
+        let json = serde_json::to_string(&rpc_header).unwrap();
+        assert_eq!(Header::from_json(json.as_bytes()).unwrap(), header);
+
+        // fixed size fields must have exact length
+        rpc_header["miner"] = serde_json::json!(format!("0x{}", hex::encode([1; 21])));
+        let json = serde_json::to_string(&rpc_header).unwrap();
+        let err = Header::from_json(json.as_bytes()).unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidDataLength { .. }));
+
+        // numbers must be canonical, so that the header has a single JSON representation
+        rpc_header["miner"] = serde_json::json!(format!("{:#x}", header.coinbase));
+        for number in ["0x01", "0x-1", "0X1"] {
+            rpc_header["number"] = serde_json::json!(number);
+            let json = serde_json::to_string(&rpc_header).unwrap();
+            let err = Header::from_json(json.as_bytes()).unwrap_err();
+            assert!(matches!(err.kind(), Kind::JsonDecodeError { .. }));
+        }
+    }
+
     #[test]
     fn generates_valid_header_hash() {
-        for (extra_bytes, hash_str) in vec![(
-            IST_EXTRA,
-            "5c012c65d46edfbfca86a426da5111c51114b75577fec9b82161d3e05d83b723",
-        )]
-        .iter()
-        {
-            let expected_hash: Hash = Hash::from_bytes(&hex::decode(hash_str).unwrap())
-                .unwrap()
-                .to_owned();
+        // known-answer vector of the plain RLP hash, that the istanbul hash is computed with
+        let mut header = Header::new();
+        header.extra = hex::decode(&IST_EXTRA).unwrap();
+        assert_eq!(
+            rlp_hash(&header).unwrap(),
+            to_hash("5c012c65d46edfbfca86a426da5111c51114b75577fec9b82161d3e05d83b723")
+        );
+
+        let mut header = Header::new();
+        header.extra = celo_extra(vec![1; PROPOSER_SEAL_LENGTH], ValidatorBitmap::from(1));
+        let hash = header.hash().unwrap();
+
+        // aggregated seal is not a part of the header hash
+        header.extra = celo_extra(vec![1; PROPOSER_SEAL_LENGTH], ValidatorBitmap::from(3));
+        assert_eq!(header.hash().unwrap(), hash);
+
+        // while the proposer seal is
+        header.extra = celo_extra(vec![2; PROPOSER_SEAL_LENGTH], ValidatorBitmap::from(1));
+        assert_ne!(header.hash().unwrap(), hash);
+    }
+
+    #[test]
+    fn rejects_malformed_extra_on_hash() {
+        for extra_bytes in vec![
+            // legacy istanbul extra-data (validators, seal, committed seals)
+            hex::decode(&IST_EXTRA).unwrap(),
+            // extra-data shorter than vanity
+            vec![0; ISTANBUL_EXTRA_VANITY_LENGTH - 1],
+            // extra-data without payload
+            vec![0; ISTANBUL_EXTRA_VANITY_LENGTH],
+        ] {
             let mut header = Header::new();
-            header.extra = hex::decode(&extra_bytes).unwrap();
+            header.extra = extra_bytes;
 
-            // for istanbul consensus
-            assert_eq!(header.hash().unwrap(), expected_hash);
+            assert!(header.hash().is_err());
+        }
+    }
+
+    #[test]
+    fn formats_address_with_checksum() {
+        // test vectors from EIP-55
+        for checksummed in vec![
+            "0x52908400098527886E0F7030069857D2E4169EE7",
+            "0xde709f2102306220921060314715629080e2fb77",
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ] {
+            let address: Address = checksummed.parse().unwrap();
+            assert_eq!(address.to_string(), checksummed);
+
+            // the checksum is optional
+            let lowercase = checksummed.to_lowercase();
+            assert_eq!(lowercase.parse::<Address>().unwrap(), address);
+            assert_eq!(lowercase[2..].parse::<Address>().unwrap(), address);
+            assert_eq!(format!("{:#x}", address), lowercase);
+        }
 
-            // append useless information to extra-data
-            header.extra.extend(vec![1, 2, 3]);
+        let err = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD"
+            .parse::<Address>()
+            .unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidAddressChecksum));
+    }
+
+    #[test]
+    fn parses_fixed_size_hex() {
+        let hash: Hash = "0x7285abd5b24742f184ad676e31f6054663b3529bc35ea2fcad8a3e0f642a46f7"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            hash.to_string(),
+            "0x7285abd5b24742f184ad676e31f6054663b3529bc35ea2fcad8a3e0f642a46f7"
+        );
+        assert_eq!(format!("{:?}", hash), hash.to_string());
+
+        let err = "0x7285".parse::<Hash>().unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidDataLength { .. }));
+
+        let err = "0xzz".parse::<Address>().unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidHexString));
+    }
 
-            assert_eq!(header.hash().unwrap(), rlp_hash(&header).unwrap());
+    #[test]
+    fn encodes_fixed_size_types() {
+        let address = Address::from([0xab; ADDRESS_LENGTH]);
+
+        // the same encoding as the plain byte array
+        assert_eq!(rlp::encode(&address), rlp::encode(&address.to_vec()));
+        assert_eq!(
+            rlp::decode::<Address>(&rlp::encode(&address)).unwrap(),
+            address
+        );
+        assert_eq!(
+            serde_json::to_string(&address).unwrap(),
+            format!("\"0x{}\"", "ab".repeat(ADDRESS_LENGTH))
+        );
+
+        // the length must match exactly
+        assert!(rlp::decode::<Address>(&rlp::encode(&vec![0xab; ADDRESS_LENGTH + 1])).is_err());
+        assert!(rlp::decode::<Address>(&rlp::encode(&vec![0xab; ADDRESS_LENGTH - 1])).is_err());
+        let json = format!("\"0x{}\"", "ab".repeat(ADDRESS_LENGTH + 1));
+        assert!(serde_json::from_str::<Address>(&json).is_err());
+        let json = format!("\"{}\"", "ab".repeat(ADDRESS_LENGTH));
+        assert!(serde_json::from_str::<Address>(&json).is_err());
+    }
+
+    fn celo_extra(seal: Vec<u8>, bitmap: ValidatorBitmap) -> Vec<u8> {
+        IstanbulExtra {
+            added_validators: Vec::new(),
+            added_validators_public_keys: Vec::new(),
+            removed_validators: ValidatorBitmap::new(),
+            seal,
+            aggregated_seal: IstanbulAggregatedSeal {
+                bitmap,
+                signature: vec![0; AGGREGATED_SIGNATURE_LENGTH],
+                round: Integer::from(0),
+            },
+            parent_aggregated_seal: IstanbulAggregatedSeal::new(),
         }
+        .to_rlp(&IstanbulExtraVanity::default())
     }
 
     pub fn to_hash<T>(data: &str) -> T
     where
-        T: FromBytes + Clone,
+        T: std::str::FromStr,
+        T::Err: std::fmt::Debug,
     {
-        T::from_bytes(&hex::decode(data).unwrap())
-            .unwrap()
-            .to_owned()
+        data.parse().unwrap()
+    }
+
+    proptest! {
+        #[test]
+        fn header_rlp_round_trip(header in arb_header()) {
+            let encoded = header.to_rlp();
+
+            prop_assert_eq!(&Header::from_rlp(&encoded).unwrap(), &header);
+            prop_assert_eq!(&Header::from_rlp_strict(&encoded).unwrap(), &header);
+        }
     }
 }
@@ -1,19 +1,23 @@
 use crate::errors::{Error, Kind};
-use crate::serialization::rlp::{
-    big_int_to_rlp_compat_bytes, rlp_field_from_bytes, rlp_to_big_int,
-};
-use crate::slice_as_array_ref;
-use crate::traits::{DefaultFrom, FromBytes};
+use crate::fixed_bytes;
+use crate::serialization::json::{hex_vec_from_json, hex_vec_to_json, JsonCodec, JsonValue};
+use crate::serialization::rlp::{big_int_to_rlp_compat_bytes, rlp_decode_strict, rlp_to_big_int};
+use crate::traits::{FromJson, ToJson};
+use crate::types::bitmap::ValidatorBitmap;
 use crate::types::header::Address;
+use crate::types::state::Validator;
 use num_bigint::BigInt as Integer;
+use num_traits::{ToPrimitive, Zero};
 use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
 
 /// PUBLIC_KEY_LENGTH represents the number of bytes used to represent BLS public key
 pub const PUBLIC_KEY_LENGTH: usize = 96;
 
-/// SerializedPublicKey is a public key of a validator that is used to i.e sign the validator set
-/// in the header
-pub type SerializedPublicKey = [u8; PUBLIC_KEY_LENGTH];
+fixed_bytes! {
+    /// SerializedPublicKey is a public key of a validator that is used to i.e sign the validator
+    /// set in the header
+    pub struct SerializedPublicKey(PUBLIC_KEY_LENGTH);
+}
 
 /// ISTANBUL_EXTRA_VANITY_LENGTH represents the number of bytes used to represent validator vanity
 pub const ISTANBUL_EXTRA_VANITY_LENGTH: usize = 32;
@@ -21,7 +25,22 @@ pub const ISTANBUL_EXTRA_VANITY_LENGTH: usize = 32;
 /// IstanbulExtraVanity is a portion of extra-data bytes reserved for validator vanity
 pub type IstanbulExtraVanity = [u8; ISTANBUL_EXTRA_VANITY_LENGTH];
 
-#[allow(dead_code)]
+/// ISTANBUL_EXTRA_MAX_LENGTH is the upper bound for the extra-data size. It's well above the
+/// size of an epoch header extra-data that replaces the whole validator set.
+pub const ISTANBUL_EXTRA_MAX_LENGTH: usize = 64 * 1024;
+
+/// AGGREGATED_SIGNATURE_LENGTH represents the number of bytes used by the aggregated BLS signature
+pub const AGGREGATED_SIGNATURE_LENGTH: usize = 48;
+
+/// PROPOSER_SEAL_LENGTH represents the number of bytes used by the proposer ECDSA signature
+pub const PROPOSER_SEAL_LENGTH: usize = 65;
+
+/// MAX_ROUND is the highest accepted IBFT round. Round change timeouts grow exponentially,
+/// so anything above this value can't be produced by a live network.
+pub const MAX_ROUND: u64 = 1 << 16;
+
+/// IstanbulMsg identifies the IBFT consensus message type
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum IstanbulMsg {
     PrePrepare,
     Prepare,
@@ -29,13 +48,25 @@ pub enum IstanbulMsg {
     RoundChange,
 }
 
+impl IstanbulMsg {
+    /// Converts the message code used on the wire into the message type
+    pub fn from_code(code: u64) -> Option<Self> {
+        match code {
+            0 => Some(IstanbulMsg::PrePrepare),
+            1 => Some(IstanbulMsg::Prepare),
+            2 => Some(IstanbulMsg::Commit),
+            3 => Some(IstanbulMsg::RoundChange),
+            _ => None,
+        }
+    }
+}
+
 /// IstanbulAggregatedSeal contains the aggregated BLS signature created via IBFT consensus
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct IstanbulAggregatedSeal {
     /// Bitmap is a bitmap having an active bit for each validator that signed this block
-    #[serde(with = "crate::serialization::bytes::hexbigint")]
-    pub bitmap: Integer,
+    pub bitmap: ValidatorBitmap,
 
     /// Signature is an aggregated BLS signature resulting from signatures by each validator that signed this block
     #[serde(with = "crate::serialization::bytes::hexstring")]
@@ -49,11 +80,55 @@ pub struct IstanbulAggregatedSeal {
 impl IstanbulAggregatedSeal {
     pub fn new() -> Self {
         Self {
-            bitmap: Integer::default(),
+            bitmap: ValidatorBitmap::new(),
             signature: Vec::default(),
             round: Integer::default(),
         }
     }
+
+    /// Returns true if the seal carries no data (ie. genesis block)
+    pub fn is_empty(&self) -> bool {
+        self.bitmap.is_empty() && self.signature.is_empty() && self.round.is_zero()
+    }
+
+    /// Strictly validates the seal against the size of the validator set that produced it
+    pub fn validate(&self, validator_set_size: usize) -> Result<(), Error> {
+        if self.signature.len() != AGGREGATED_SIGNATURE_LENGTH {
+            return Err(Kind::InvalidSignatureLength {
+                current: self.signature.len(),
+                expected: AGGREGATED_SIGNATURE_LENGTH,
+            }
+            .into());
+        }
+
+        self.bitmap.validate(validator_set_size)?;
+
+        self.validate_round()
+    }
+
+    /// Validates the seal format without the validator set context. An empty seal is accepted.
+    pub fn validate_format(&self) -> Result<(), Error> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        if self.signature.len() != AGGREGATED_SIGNATURE_LENGTH {
+            return Err(Kind::InvalidSignatureLength {
+                current: self.signature.len(),
+                expected: AGGREGATED_SIGNATURE_LENGTH,
+            }
+            .into());
+        }
+
+        self.validate_round()
+    }
+
+    fn validate_round(&self) -> Result<(), Error> {
+        match self.round.to_u64() {
+            Some(round) if round <= MAX_ROUND => Ok(()),
+            _ => Err(Kind::InvalidRound { max: MAX_ROUND }.into()),
+        }
+    }
 }
 
 impl Encodable for IstanbulAggregatedSeal {
@@ -61,7 +136,7 @@ impl Encodable for IstanbulAggregatedSeal {
         s.begin_list(3);
 
         // bitmap
-        s.append(&big_int_to_rlp_compat_bytes(&self.bitmap));
+        s.append(&self.bitmap);
 
         // signature
         s.append(&self.signature);
@@ -74,28 +149,43 @@ impl Encodable for IstanbulAggregatedSeal {
 impl Decodable for IstanbulAggregatedSeal {
     fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
         Ok(IstanbulAggregatedSeal {
-            bitmap: rlp_to_big_int(rlp, 0)?,
+            bitmap: rlp.val_at(0)?,
             signature: rlp.val_at(1)?,
             round: rlp_to_big_int(rlp, 2)?,
         })
     }
 }
 
+impl JsonCodec for IstanbulAggregatedSeal {
+    fn to_json_value(&self) -> JsonValue {
+        JsonValue::object(vec![
+            ("bitmap", self.bitmap.to_json_value()),
+            ("signature", JsonValue::hex(&self.signature)),
+            ("round", JsonValue::hex_big_int(&self.round)),
+        ])
+    }
+
+    fn from_json_value(value: &JsonValue) -> Result<Self, Error> {
+        Ok(IstanbulAggregatedSeal {
+            bitmap: ValidatorBitmap::from_json_value(value.field("bitmap")?)?,
+            signature: value.field("signature")?.as_hex()?,
+            round: value.field("round")?.as_hex_big_int()?,
+        })
+    }
+}
+
 /// IstanbulExtra represents IBFT consensus state data
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct IstanbulExtra {
     /// The validators that have been added in the block
-    #[serde(with = "crate::serialization::bytes::hexvec")]
     pub added_validators: Vec<Address>,
 
     /// The BLS public keys for the validators added in the block
-    #[serde(with = "crate::serialization::bytes::hexvec")]
     pub added_validators_public_keys: Vec<SerializedPublicKey>,
 
     /// Bitmap having an active bit for each removed validator in the block
-    #[serde(with = "crate::serialization::bytes::hexbigint")]
-    pub removed_validators: Integer,
+    pub removed_validators: ValidatorBitmap,
 
     /// ECDSA signature by the proposer
     #[serde(with = "crate::serialization::bytes::hexstring")]
@@ -110,40 +200,72 @@ pub struct IstanbulExtra {
 
 impl IstanbulExtra {
     pub fn from_rlp(bytes: &[u8]) -> Result<Self, Error> {
-        if bytes.len() < ISTANBUL_EXTRA_VANITY_LENGTH {
-            return Err(Kind::RlpDecodeError
-                .context(DecoderError::Custom("invalid istanbul header extra-data"))
-                .into());
-        }
+        IstanbulExtra::check_extra_length(bytes)?;
 
         rlp::decode(&bytes[ISTANBUL_EXTRA_VANITY_LENGTH..])
             .map_err(|e| Kind::RlpDecodeError.context(e).into())
     }
 
+    /// Decodes the extra-data, rejecting the non-canonical RLP encoding of the payload
+    pub fn from_rlp_strict(bytes: &[u8]) -> Result<Self, Error> {
+        IstanbulExtra::check_extra_length(bytes)?;
+
+        rlp_decode_strict(&bytes[ISTANBUL_EXTRA_VANITY_LENGTH..])
+            .map_err(|e| Kind::RlpDecodeError.context(e).into())
+    }
+
+    fn check_extra_length(bytes: &[u8]) -> Result<(), Error> {
+        if bytes.len() < ISTANBUL_EXTRA_VANITY_LENGTH {
+            return Err(Kind::InvalidExtraVanity {
+                current: bytes.len(),
+                expected: ISTANBUL_EXTRA_VANITY_LENGTH,
+            }
+            .into());
+        }
+
+        if bytes.len() > ISTANBUL_EXTRA_MAX_LENGTH {
+            return Err(Kind::ExtraDataTooLarge {
+                current: bytes.len(),
+                max: ISTANBUL_EXTRA_MAX_LENGTH,
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+
     pub fn to_rlp(&self, vanity: &IstanbulExtraVanity) -> Vec<u8> {
         let payload = rlp::encode(self);
 
         [&vanity[..], &payload[..]].concat()
     }
+
+    /// Strictly validates the extra-data fields that can be checked without the validator set
+    pub fn validate(&self) -> Result<(), Error> {
+        if !self.seal.is_empty() && self.seal.len() != PROPOSER_SEAL_LENGTH {
+            return Err(Kind::InvalidSealLength {
+                current: self.seal.len(),
+                expected: PROPOSER_SEAL_LENGTH,
+            }
+            .into());
+        }
+
+        self.aggregated_seal.validate_format()?;
+        self.parent_aggregated_seal.validate_format()
+    }
 }
 
 impl Encodable for IstanbulExtra {
     fn rlp_append(&self, s: &mut RlpStream) {
         // added_validators
         s.begin_list(6);
-        s.begin_list(self.added_validators.len());
-        for address in self.added_validators.iter() {
-            s.append(&address.as_ref());
-        }
+        s.append_list(&self.added_validators);
 
         // added_validators_public_keys
-        s.begin_list(self.added_validators_public_keys.len());
-        for address in self.added_validators_public_keys.iter() {
-            s.append(&address.as_ref());
-        }
+        s.append_list(&self.added_validators_public_keys);
 
         // removed_validators
-        s.append(&big_int_to_rlp_compat_bytes(&self.removed_validators));
+        s.append(&self.removed_validators);
 
         // seal
         s.append(&self.seal);
@@ -158,22 +280,10 @@ impl Encodable for IstanbulExtra {
 
 impl Decodable for IstanbulExtra {
     fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
-        let added_validators: Result<Vec<Address>, DecoderError> = rlp
-            .at(0)?
-            .iter()
-            .map(|r| rlp_field_from_bytes(&r))
-            .collect();
-
-        let added_validators_public_keys: Result<Vec<SerializedPublicKey>, DecoderError> = rlp
-            .at(1)?
-            .iter()
-            .map(|r| rlp_field_from_bytes(&r))
-            .collect();
-
         Ok(IstanbulExtra {
-            added_validators: added_validators?,
-            added_validators_public_keys: added_validators_public_keys?,
-            removed_validators: rlp_to_big_int(rlp, 2)?,
+            added_validators: rlp.list_at(0)?,
+            added_validators_public_keys: rlp.list_at(1)?,
+            removed_validators: rlp.val_at(2)?,
             seal: rlp.val_at(3)?,
             aggregated_seal: rlp.val_at(4)?,
             parent_aggregated_seal: rlp.val_at(5)?,
@@ -181,38 +291,114 @@ impl Decodable for IstanbulExtra {
     }
 }
 
-impl FromBytes for IstanbulExtraVanity {
-    fn from_bytes(data: &[u8]) -> Result<&IstanbulExtraVanity, Error> {
-        slice_as_array_ref!(
-            &data[..ISTANBUL_EXTRA_VANITY_LENGTH],
-            ISTANBUL_EXTRA_VANITY_LENGTH
-        )
+impl JsonCodec for IstanbulExtra {
+    fn to_json_value(&self) -> JsonValue {
+        JsonValue::object(vec![
+            ("addedValidators", hex_vec_to_json(&self.added_validators)),
+            (
+                "addedValidatorsPublicKeys",
+                hex_vec_to_json(&self.added_validators_public_keys),
+            ),
+            ("removedValidators", self.removed_validators.to_json_value()),
+            ("seal", JsonValue::hex(&self.seal)),
+            ("aggregatedSeal", self.aggregated_seal.to_json_value()),
+            (
+                "parentAggregatedSeal",
+                self.parent_aggregated_seal.to_json_value(),
+            ),
+        ])
+    }
+
+    fn from_json_value(value: &JsonValue) -> Result<Self, Error> {
+        Ok(IstanbulExtra {
+            added_validators: hex_vec_from_json(value.field("addedValidators")?)?,
+            added_validators_public_keys: hex_vec_from_json(
+                value.field("addedValidatorsPublicKeys")?,
+            )?,
+            removed_validators: ValidatorBitmap::from_json_value(
+                value.field("removedValidators")?,
+            )?,
+            seal: value.field("seal")?.as_hex()?,
+            aggregated_seal: IstanbulAggregatedSeal::from_json_value(
+                value.field("aggregatedSeal")?,
+            )?,
+            parent_aggregated_seal: IstanbulAggregatedSeal::from_json_value(
+                value.field("parentAggregatedSeal")?,
+            )?,
+        })
+    }
+}
+
+impl ToJson for IstanbulExtra {
+    fn to_json(&self) -> String {
+        self.to_json_value().encode()
     }
 }
 
-impl FromBytes for SerializedPublicKey {
-    fn from_bytes(data: &[u8]) -> Result<&SerializedPublicKey, Error> {
-        slice_as_array_ref!(&data[..PUBLIC_KEY_LENGTH], PUBLIC_KEY_LENGTH)
+impl FromJson for IstanbulExtra {
+    fn from_json(bytes: &[u8]) -> Result<Self, Error> {
+        Self::from_json_value(&JsonValue::parse(bytes)?)
     }
 }
 
-impl DefaultFrom for SerializedPublicKey {
-    fn default() -> Self {
-        [0; PUBLIC_KEY_LENGTH]
+/// ValidatorSetChange is the validator set update carried by the header extra-data
+#[derive(Clone, PartialEq, Debug)]
+pub enum ValidatorSetChange {
+    /// The validators removed from the current set (as a bitmap over it) and the validators
+    /// added along with their BLS public keys, ie. the Celo istanbul extra-data
+    Diff {
+        added: Vec<Validator>,
+        removed: ValidatorBitmap,
+    },
+
+    /// The addresses of the whole new validator set, for the chains that don't carry the
+    /// validator keys in the header (ie. the ECDSA sealed IBFT chains)
+    Replace(Vec<Address>),
+}
+
+/// CommittedSeal proves that the validators committed to the block
+#[derive(Clone, PartialEq, Debug)]
+pub enum CommittedSeal {
+    /// The BLS signature aggregated over the committed seals of the validators
+    Aggregated(IstanbulAggregatedSeal),
+
+    /// The ECDSA committed seals of the validators, one per signer
+    Ecdsa(Vec<Vec<u8>>),
+}
+
+/// ConsensusExtra contains the consensus fields of the header extra-data in the chain agnostic
+/// form, as decoded by the [`IstanbulEngine`](crate::traits::IstanbulEngine)
+#[derive(Clone, PartialEq, Debug)]
+pub struct ConsensusExtra {
+    /// The validator set update, that is applied by the epoch headers
+    pub validator_set_change: ValidatorSetChange,
+
+    /// The seal committed by the validators over the header hash
+    pub committed_seal: CommittedSeal,
+}
+
+impl ConsensusExtra {
+    /// Returns the aggregated seal stored in the state snapshot, that is empty for the chains
+    /// sealed with ECDSA signatures
+    pub fn aggregated_seal(&self) -> IstanbulAggregatedSeal {
+        match &self.committed_seal {
+            CommittedSeal::Aggregated(seal) => seal.clone(),
+            CommittedSeal::Ecdsa(_) => IstanbulAggregatedSeal::new(),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::test_utils::{arb_istanbul_extra, ISTANBUL_EXTRA_DUMPED};
+    use num_bigint::BigUint;
     use num_traits::Num;
+    use proptest::prelude::*;
 
     // tiny example to assert validity of basic data
     const ISTANBUL_EXTRA_TINY: &str = "f6ea9444add0ec310f115a0e603b2d7db9f067778eaf8a94294fc7e8f22b3bcdcf955dd7ff3ba2ed833f8212c00c80c3808080c3808080";
 
-    // real life example (0x2a300 block from Celo main network) to assert validty of all the fields
-    const ISTANBUL_EXTRA_DUMPED: &str = "d983010000846765746889676f312e31332e3130856c696e7578000000000000f90c6ef9020d94e8cea87569eb67b24196a0d3e2dfdd52f64d037d94e3b49907192aaabe84512629ce7c01be816bbb9d940439ad8b999acc4cda10b2aacae058732c4f2f8694edba91c8cc9fd2d88c5fa18aa1ffd6fac6959281947f4afdae66b590a90f2250e2d78bd27b4294a5dd94dcf109c09081042e1bfef3f21fa93ba61d3a3d5a944e986af5c4796432bfa2a3fb7ade1b0e9cda988b94f6b0e762344a8d10f77dd4f8f918e7d1c83be2f3946fabedc952b6bc7a11548f412e61f3af7ebe0e8394f2435e0b468e4c45eb4875127470da5c6842909d9424cee8c02fe517e19eaa783e0b7eadea66e5e6c294a063831defdc73d5a8a3f8993fdf4f108d5b044b94b5011538fceec8cbda40af59be09c552aead71389409b353c4e4c4d836b4a4ca4686370b78bc7a215294764a4ee67687994ababfe97f3a81411209011158949acda9211cfc11ed40fe05e20bb1429118199c9194e3feea837446183a233317f1c5d804b98fd50c6b945f897eed6797c0e7dfce3d97c48bf7ed032ee32194fe91ff8733bd21e3277190c0a00c300a9431d7879410ea19a286daf51dfdafbd3656a1262be4980a58946632c91b891e26229c86c59340917591be732028943edbd914a59a79417d0274fad7d80e39f8b219f794440a4917c9c833aa3984322f5c9e852cf300936594ff3bd8067551f79ac3ebbb1fa00d114e4b53b642947c079b1af73dfad29b89e4787aa4ce82475fe66ff90992b8608d483d8a391fce6ed73e9d4d2169d0bd569f3cedd6923350cd7195bab999517cb002564ef28d8c0205ca4ce3d42f7701fba58217bbd83288c7897904af6c2bedbfbbf220bc2a0239dfe505b07628b16f61e091a313cc7c34c7ee624b849e3f80b860a91c5c4939664eec9a8182ef7da28610450385ab8c74cc8f25c68c87ff1141bc9c7bd1259d674e21cb7ffb48b4034e017b529c80ec314eebcf1d360690c5777dcff42995327d5b1a9858cacc35076661704e0b50bd1502d7f8ebf18bdc4ca501b8603386f70c4fd8b3d9773af643ec70a6ae6d90365941170c44d6145b9725b89adf1489dadf0ca3d133019a82eb434cf200c2504bb8ce0282294c586644d29be422957a508fc6692ef711a1438b7d266598466ca6f8e5ac0e037df84885c6f92b00b860334b3d31ac71cdf8ad37ec78c314878f7d9b976b7b0e9cae348aba3c13a8d78fb1ce4be8c4a93e48a68467d14b503000b6f9dac56dc638e513e76b76e7e9787c31ccd2be57edba5c1ee9687703af14ea651ff497e1bd3541b0b99506d62eba80b8602c4fa91541e13318ba5bb9c5bf2204e320d333dcfd61bd6207476e2610ed2fc0133e919bfa6150563b66a015d6347a01947fe549bdc9bc0adf8c36fe42d47c1f25d67a74196eba4fc1a02b65cb9c0bd05860e33b059d86a119955327b4495500b86043c11c8346d8393ac9f0d5035f5c85ef69155e25ba9d78dba46b5fb4edfb7e05db29f2ffb8d0db07f03d4b704f3c4c010cf0f889cdd868ef6691f1acec197b7dcf8e8ac545913768ae7e1a97aefd4d450094b0bedcc8db78a649c90c1e9c6281b860af795e211ad3ed4e46e11b94dadba56f201dc3a36eb41f6ad6fbeae119088293a54bab374aecd2305037c330e59230018065c0869480b6ab0b490e8a6f766d0b5930220c0df4c7c898e50d87bba477ce850b03e7a00b936a487e27c3f674a981b8607ef1b4aa6216a489a587e96df4ad109ce1279240176664a0845b837f83d09206ce20adab624fa618ab0916091bc23a00eb6a400c521734ee4e67dc895912f7f6f13cc9c6f1298dae95b7aa9f752481097d4b27b9fd09a5eb70958aaae3eade00b8604eac7f432065192a70dd7cd724d9ce3a466ca987087bf75c361bf132d7611352093d3430029d6021695a3dd717cfa3007d1110c281c40da6b9ba287b2865cf8082b59e67242b0aa3d4576586047b926b9dd3bd383b9a1651d7776759956c1d00b8605effa652455d663ad1f7d02b76cea6333551f7ed4c4c0bab0d0078bc34ae24e81426c81b157b57cb6078b7804d925d0012d3fd8c8e0be5f2521db02cd0c6523ec7b2d43e91f69eb3433c0bf249cda1258d859e8463ca2b6c1b19bfc4ea018901b8606e92d50b8b2cbb1d03a6d92b8b0a53685f38225771854c596a21baaca797b2a36bf0c783bad5cad1093c4441470aed00a4077fb8fe382e201a038afb0a32448a982263ad75405f127baf5935e35ec6e1cec624e4ae619fd92945664ac407f680b860f6914b8a9c6227e00eb732298034585b1519e51bdd3cea087d59ccd78c9fe6ea37c626a970a18924c2e4d3dfa2f37900994be34deca5a4fab594990251b4ae85da8845ede08b3190ace9f7653fcffec4b599bd1ba76dee9a7e3bdcdd79990d80b86050f45c41b3e8398b7f04734b8461e1fc137dd211c8a3bd657dbde0f773563a582cf74b037c7a4879787a1695242cde00bd366d131bc43d71efb41dd959c74b0b51469bd409d1bff7f3abb400dc8309d880f1551e18a1000af7a7f3a52af42580b860068f6a5e22ec195f52015004199aa8fdda8735bc6b7eb2d030f4807b01293131ee36f0cf78d45e6bbfbacc05d931700006c7c8d7fad54137d10d9d09abd33f92114e6f5d12eccc69bd2adbac1cc78061c97fa9d5b30fa5636402039766a6e980b8609261e0674d685b4e29e12fd55aac90128577523c82979572cbe1453bf2c638ecba7d3cfb67708d9427a365e5e2c384000edaa42a23b59cb723f77330fd5b6d2ef3a93d518b32e0eeb195a2fbb99ef431001445df09c8e88b0dc783a94bb48f01b8608ea9ae193ad42ddbcc7222858bbc3ec5f342e9a00fde212b244c6ceedb07827cfb4b50e8f3365e80d35fa64a412e24001bc4628ae2e34df40f29348b32762be6a2da3e65a9199e9ae5285d048e7caaa0cfdf3aaaac0cec0499688d0c2b7eab80b860a37b611c1ac3453817699183e3e703cd79452080919ed2e7ecaf9a14ee6602cedb44346bd7920d7fde6bd50762bc6700014aa1db8445d898708a090baa113e39e63b0541583bb4d4ab3811631e0ac903a817269e42ab042de79f7605e9b7c700b86085e7fad58fbdb49110b38142d90753f107560b6a52eb2674d223fdd7c97f9da36e9de4d16deb3eac169807082bc89c004a52ed567d9065f4e5b6dba9e279fc0f46d976da4e562d5873da8fdc8a8fb52d647a1394e46e998938c7e2a2b22a1080b860139d447bc50301235f68e91e31f799e9194aa98f1c61295d5fe207e47e6b4c903e4c66e7efd3993039d633633d6fc200cf17a5b49a3b90db55e10679edb6876c6bb3aa138c5bdd073c411287847ae858d2e7b0cf53b80d056568be0fa5074000b8602e8f2c8609cc8ef00c4f70d7e5aafe6908e8827606f559ab0268ec9b6725b88c7e6cfcc8cdc7061ef5cb4e8526a3090032b29d15ff64845f7451c299ea2d92fbc3d72961a16c9beea0c6ded4a0d2162550fe63752a64b884703d209b7d7d1e81b860ce50e9605cac46755090ba8f29e155834eefbe7d5fc0821ba00d7e6b1ff80df74bd04f989a319aaff8217d1f07d5a600a42f94bbd737aa4562f1e6a0f31c68029cb2924d3ca4e78cfe0dab82f785ba6f09be625514f04957d457dc4f789d3001b860fbca8830e0e95a7b6d23121c279a125085f8c2bc39e40912689cbb2462c7cc1c6b8cfb7cd2fda28f5cbe8aa5d2ee8b01a20659e6a55d7143db9b889ffd6bc96f4cdcde900597c957f56fd0e0c943fe13a9e3ac376cac6d8f48db7a8082292501b860edb8634cb66357c8ec2534c82dc4f3dfc04cd0c2a10468015ad7303d61868dcdf5f6f2aa96822cf4f9abce05e98e49000ac9cedd9d89f7d41e263d375969fdb5ff9babc3cc761778c088f17b2064e0ceed08c61934e550d3e673267c85f83281b8602d9da563d6b418ecaa931a669603e6736d7012ddd2c44ab429ce88123942e9f91f5c7c2c87d07d2d5af905fff2e8c2006e7bee1e0522ad6d7eb8e36e897120592f2ce30a77ab2d58ff131374cd11d8b6f5b1f7fa1aa4379e9e9607f1a9228380b860fc55fe153f5f7ab3914bc2c36f71055224fc42ac8bba492a6b901d0cd7e16e95f07726d5b137fd9778b2390278b057003b7c115b6d88085c11f612a8a47d5255d18f078db4033c1eb0de07366d1548587a1f6361ccb2e14f524403815c335580890138100001801e0005b8419c0095d64903827be6b1ca1072109074d30aae4e6a209bf2c3c4c83bc38c1a29551a08d975a5aad0a64be5b85dd5a4fb9bc50a6220668e23382cd362d9672ba900f83c8901eca063eebfbbfdf9b0428e302ff6aab449d68fbdde248a4494b0db5f166a0a64244defcaea0e8342f8d1361bfe60df5e9180087fb703f57b8180f83c8901efeffffffffffffbb03669d77a600391712293fac898ff03637cf87789ff7b4ba7479554f8acbfd864f9d454246b4788024d95c5063e039c8080";
-
     #[test]
     fn encodes_istanbul_extra_to_rlp() {
         for extra_bytes in vec![
@@ -220,8 +406,9 @@ mod tests {
             hex::decode(&ISTANBUL_EXTRA_DUMPED).unwrap(),
         ] {
             let decoded_ist = IstanbulExtra::from_rlp(&extra_bytes).unwrap();
-            let vanity = IstanbulExtraVanity::from_bytes(&extra_bytes);
-            let encoded_ist_bytes = decoded_ist.to_rlp(vanity.unwrap());
+            let mut vanity = IstanbulExtraVanity::default();
+            vanity.copy_from_slice(&extra_bytes[..ISTANBUL_EXTRA_VANITY_LENGTH]);
+            let encoded_ist_bytes = decoded_ist.to_rlp(&vanity);
 
             assert_eq!(encoded_ist_bytes, extra_bytes);
         }
@@ -236,7 +423,7 @@ mod tests {
                     "294fc7e8f22b3bcdcf955dd7ff3ba2ed833f8212",
                 ]),
                 added_validators_public_keys: vec![],
-                removed_validators: Integer::from(12),
+                removed_validators: ValidatorBitmap::from(12),
                 seal: Vec::new(),
                 aggregated_seal: IstanbulAggregatedSeal::new(),
                 parent_aggregated_seal: IstanbulAggregatedSeal::new(),
@@ -296,15 +483,15 @@ mod tests {
                     "2d9da563d6b418ecaa931a669603e6736d7012ddd2c44ab429ce88123942e9f91f5c7c2c87d07d2d5af905fff2e8c2006e7bee1e0522ad6d7eb8e36e897120592f2ce30a77ab2d58ff131374cd11d8b6f5b1f7fa1aa4379e9e9607f1a9228380",
                     "fc55fe153f5f7ab3914bc2c36f71055224fc42ac8bba492a6b901d0cd7e16e95f07726d5b137fd9778b2390278b057003b7c115b6d88085c11f612a8a47d5255d18f078db4033c1eb0de07366d1548587a1f6361ccb2e14f524403815c335580"
                 ]),
-                removed_validators: Integer::from_str_radix("22486472945905303557", 10).unwrap(),
+                removed_validators: BigUint::from_str_radix("22486472945905303557", 10).unwrap().into(),
                 seal: hex::decode("9c0095d64903827be6b1ca1072109074d30aae4e6a209bf2c3c4c83bc38c1a29551a08d975a5aad0a64be5b85dd5a4fb9bc50a6220668e23382cd362d9672ba900").unwrap(),
                 aggregated_seal: IstanbulAggregatedSeal{
-                    bitmap: Integer::from_str_radix("35497482140004384249", 10).unwrap(),
+                    bitmap: BigUint::from_str_radix("35497482140004384249", 10).unwrap().into(),
                     signature: hex::decode("428e302ff6aab449d68fbdde248a4494b0db5f166a0a64244defcaea0e8342f8d1361bfe60df5e9180087fb703f57b81").unwrap(),
                     round: Integer::from(0),
                 },
                 parent_aggregated_seal: IstanbulAggregatedSeal{
-                    bitmap: Integer::from_str_radix("35736063043184885755", 10).unwrap(),
+                    bitmap: BigUint::from_str_radix("35736063043184885755", 10).unwrap().into(),
                     signature: hex::decode("3669d77a600391712293fac898ff03637cf87789ff7b4ba7479554f8acbfd864f9d454246b4788024d95c5063e039c80").unwrap(),
                     round: Integer::from(0),
                 },
@@ -324,6 +511,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn strictly_decodes_istanbul_extra() {
+        let canonical = prepend_vanity(ISTANBUL_EXTRA_TINY);
+        let expected = IstanbulExtra::from_rlp(&canonical).unwrap();
+        assert_eq!(IstanbulExtra::from_rlp_strict(&canonical).unwrap(), expected);
+
+        let dumped = hex::decode(&ISTANBUL_EXTRA_DUMPED).unwrap();
+        assert!(IstanbulExtra::from_rlp_strict(&dumped).is_ok());
+
+        // removed validators bitmap prefixed with zero
+        let padded = prepend_vanity(
+            &ISTANBUL_EXTRA_TINY
+                .replace("f6ea", "f838ea")
+                .replace("c00c80", "c082000c80"),
+        );
+        let trailing = [&canonical[..], &[0x80]].concat();
+
+        for bytes in vec![padded, trailing] {
+            assert_eq!(IstanbulExtra::from_rlp(&bytes).unwrap(), expected);
+
+            let err = IstanbulExtra::from_rlp_strict(&bytes).unwrap_err();
+            assert!(matches!(err.kind(), Kind::RlpDecodeError));
+        }
+    }
+
     #[test]
     fn rejects_insufficient_vanity() {
         let bytes = vec![0; ISTANBUL_EXTRA_VANITY_LENGTH - 1];
@@ -331,6 +543,67 @@ mod tests {
         assert!(IstanbulExtra::from_rlp(&bytes).is_err());
     }
 
+    #[test]
+    fn rejects_invalid_extra_data_length() {
+        let err = IstanbulExtra::from_rlp(&vec![0; ISTANBUL_EXTRA_VANITY_LENGTH - 1]).unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidExtraVanity { .. }));
+
+        let err = IstanbulExtra::from_rlp(&vec![0; ISTANBUL_EXTRA_MAX_LENGTH + 1]).unwrap_err();
+        assert!(matches!(err.kind(), Kind::ExtraDataTooLarge { .. }));
+    }
+
+    #[test]
+    fn validates_istanbul_extra() {
+        let extra_bytes = hex::decode(&ISTANBUL_EXTRA_DUMPED).unwrap();
+        let extra = IstanbulExtra::from_rlp(&extra_bytes).unwrap();
+        assert!(extra.validate().is_ok());
+
+        let mut invalid = extra.clone();
+        invalid.seal = vec![0; PROPOSER_SEAL_LENGTH - 1];
+        let err = invalid.validate().unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidSealLength { .. }));
+
+        let mut invalid = extra.clone();
+        invalid.parent_aggregated_seal.signature = vec![0; AGGREGATED_SIGNATURE_LENGTH + 1];
+        let err = invalid.validate().unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidSignatureLength { .. }));
+
+        let mut invalid = extra.clone();
+        invalid.aggregated_seal.round = Integer::from(MAX_ROUND + 1);
+        let err = invalid.validate().unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidRound { .. }));
+    }
+
+    #[test]
+    fn validates_aggregated_seal() {
+        let seal = IstanbulAggregatedSeal {
+            bitmap: ValidatorBitmap::from(0b111),
+            signature: vec![0; AGGREGATED_SIGNATURE_LENGTH],
+            round: Integer::from(1),
+        };
+        assert!(seal.validate(3).is_ok());
+
+        let err = seal.validate(2).unwrap_err();
+        assert!(matches!(err.kind(), Kind::BitmapOutOfRange { bits: 3, validators: 2 }));
+
+        let mut invalid = seal.clone();
+        invalid.signature = Vec::new();
+        let err = invalid.validate(3).unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidSignatureLength { .. }));
+
+        // empty seal is well formed (ie. genesis block), but can't be verified
+        assert!(IstanbulAggregatedSeal::new().validate_format().is_ok());
+        assert!(IstanbulAggregatedSeal::new().validate(3).is_err());
+    }
+
+    #[test]
+    fn rejects_short_fixed_size_data() {
+        let bytes = vec![0; 10];
+
+        assert!(SerializedPublicKey::from_slice(&bytes).is_err());
+        assert!(Address::from_slice(&bytes).is_err());
+    }
+
     #[test]
     fn serializes_and_deserializes_to_json() {
         for bytes in vec![
@@ -347,6 +620,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn encodes_and_decodes_float_free_json() {
+        for bytes in vec![
+            prepend_vanity(ISTANBUL_EXTRA_TINY),
+            hex::decode(&ISTANBUL_EXTRA_DUMPED).unwrap(),
+        ]
+        .iter()
+        {
+            let parsed = IstanbulExtra::from_rlp(&bytes).unwrap();
+
+            // compatible with the serde JSON format
+            let json = parsed.to_json();
+            assert_eq!(serde_json::from_str::<IstanbulExtra>(&json).unwrap(), parsed);
+
+            let json = serde_json::to_string(&parsed).unwrap();
+            assert_eq!(IstanbulExtra::from_json(json.as_bytes()).unwrap(), parsed);
+        }
+
+        let err = IstanbulExtra::from_json(br#"{"seal": ""}"#).unwrap_err();
+        assert!(matches!(err.kind(), Kind::JsonDecodeError { .. }));
+    }
+
     fn prepend_vanity(data: &str) -> Vec<u8> {
         let data = hex::decode(data).unwrap();
         let vanity = IstanbulExtraVanity::default();
@@ -355,23 +650,26 @@ mod tests {
     }
 
     fn to_serialized_pub_key_vec(keys: Vec<&str>) -> Vec<SerializedPublicKey> {
-        keys.iter()
-            .map(|key| {
-                SerializedPublicKey::from_bytes(hex::decode(key).unwrap().as_slice())
-                    .unwrap()
-                    .to_owned()
-            })
-            .collect()
+        keys.iter().map(|key| key.parse().unwrap()).collect()
     }
 
     fn to_address_vec(addresses: Vec<&str>) -> Vec<Address> {
         addresses
             .iter()
-            .map(|address| {
-                Address::from_bytes(hex::decode(address).unwrap().as_slice())
-                    .unwrap()
-                    .to_owned()
-            })
+            .map(|address| address.parse().unwrap())
             .collect()
     }
+
+    proptest! {
+        #[test]
+        fn istanbul_extra_rlp_round_trip(
+            extra in arb_istanbul_extra(),
+            vanity in any::<IstanbulExtraVanity>(),
+        ) {
+            let encoded = extra.to_rlp(&vanity);
+
+            prop_assert_eq!(&IstanbulExtra::from_rlp(&encoded).unwrap(), &extra);
+            prop_assert_eq!(&IstanbulExtra::from_rlp_strict(&encoded).unwrap(), &extra);
+        }
+    }
 }
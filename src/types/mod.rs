@@ -1,3 +1,9 @@
+pub(crate) mod bitmap;
+pub(crate) mod consensus;
 pub(crate) mod header;
 pub(crate) mod istanbul;
 pub(crate) mod state;
+pub(crate) mod validator_set;
+
+#[cfg(test)]
+pub(crate) mod test_utils;
@@ -1,20 +1,25 @@
 use crate::bls::verify_aggregated_seal;
+use crate::clock::Timestamp;
 use crate::errors::{Error, Kind};
-use crate::serialization::rlp::{rlp_field_from_bytes, rlp_list_field_from_bytes};
-use crate::traits::{FromRlp, StateConfig, ToRlp};
+use crate::serialization::json::{JsonCodec, JsonValue};
+use crate::serialization::rlp::{
+    rlp_begin_versioned, rlp_check_canonical, rlp_check_single_item, rlp_decode_strict,
+    rlp_versioned_fields,
+};
+use crate::traits::{FromJson, FromRlp, FromRlpStrict, StateConfig, ToJson, ToRlp};
 use crate::types::header::{Address, Hash};
 use crate::types::istanbul::{IstanbulAggregatedSeal, SerializedPublicKey};
+use crate::types::validator_set::ValidatorSet;
 
 use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
-use rlp_derive::{RlpDecodable, RlpEncodable};
+use std::convert::TryFrom;
+use std::time::Duration;
 
 /// Validator identifies block producer by public key and address
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct Validator {
-    #[serde(with = "crate::serialization::bytes::hexstring")]
     pub address: Address,
 
-    #[serde(with = "crate::serialization::bytes::hexstring")]
     pub public_key: SerializedPublicKey,
 }
 
@@ -22,20 +27,60 @@ impl Encodable for Validator {
     fn rlp_append(&self, s: &mut RlpStream) {
         s.begin_list(2);
 
-        s.append(&self.address.as_ref());
-        s.append(&self.public_key.as_ref());
+        s.append(&self.address);
+        s.append(&self.public_key);
     }
 }
 
 impl Decodable for Validator {
     fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
         Ok(Validator {
-            address: rlp_field_from_bytes(&rlp.at(0)?)?,
-            public_key: rlp_field_from_bytes(&rlp.at(1)?)?,
+            address: rlp.val_at(0)?,
+            public_key: rlp.val_at(1)?,
         })
     }
 }
 
+impl JsonCodec for Validator {
+    fn to_json_value(&self) -> JsonValue {
+        JsonValue::object(vec![
+            ("address", JsonValue::hex(&self.address)),
+            ("public_key", JsonValue::hex(&self.public_key)),
+        ])
+    }
+
+    fn from_json_value(value: &JsonValue) -> Result<Self, Error> {
+        Ok(Validator {
+            address: value.field("address")?.as_hex_bytes()?,
+            public_key: value.field("public_key")?.as_hex_bytes()?,
+        })
+    }
+}
+
+impl ToJson for Validator {
+    fn to_json(&self) -> String {
+        self.to_json_value().encode()
+    }
+}
+
+impl FromJson for Validator {
+    fn from_json(bytes: &[u8]) -> Result<Self, Error> {
+        Self::from_json_value(&JsonValue::parse(bytes)?)
+    }
+}
+
+impl ToJson for Vec<Validator> {
+    fn to_json(&self) -> String {
+        self.to_json_value().encode()
+    }
+}
+
+impl FromJson for Vec<Validator> {
+    fn from_json(bytes: &[u8]) -> Result<Self, Error> {
+        Self::from_json_value(&JsonValue::parse(bytes)?)
+    }
+}
+
 impl ToRlp for Vec<Validator> {
     fn to_rlp(&self) -> Vec<u8> {
         rlp::encode_list(&self)
@@ -44,18 +89,180 @@ impl ToRlp for Vec<Validator> {
 
 impl FromRlp for Vec<Validator> {
     fn from_rlp(bytes: &[u8]) -> Result<Self, Error> {
-        Ok(rlp::decode_list(&bytes))
+        let rlp = Rlp::new(bytes);
+        let item_count = rlp
+            .item_count()
+            .map_err(|e| Kind::RlpDecodeError.context(e))?;
+
+        (0..item_count)
+            .map(|i| rlp.val_at(i))
+            .collect::<Result<Vec<Validator>, DecoderError>>()
+            .map_err(|e| Kind::RlpDecodeError.context(e).into())
+    }
+}
+
+impl FromRlpStrict for Vec<Validator> {
+    fn from_rlp_strict(bytes: &[u8]) -> Result<Self, Error> {
+        rlp_check_single_item(bytes).map_err(|e| Kind::RlpDecodeError.context(e))?;
+
+        let validators = Vec::<Validator>::from_rlp(bytes)?;
+        rlp_check_canonical(bytes, &validators.to_rlp())
+            .map_err(|e| Kind::RlpDecodeError.context(e))?;
+
+        Ok(validators)
+    }
+}
+
+/// TrustThreshold is the fraction of the trusted validator set, whose members have to sign the
+/// header for it to be accepted by the skipping verification. The fraction is validated on
+/// creation as well as on decoding, so that it can't be set below 1/3.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(try_from = "TrustThresholdFields")]
+pub struct TrustThreshold {
+    numerator: u64,
+    denominator: u64,
+}
+
+/// The unvalidated serde representation of the TrustThreshold
+#[derive(Deserialize)]
+struct TrustThresholdFields {
+    numerator: u64,
+    denominator: u64,
+}
+
+impl TryFrom<TrustThresholdFields> for TrustThreshold {
+    type Error = Error;
+
+    fn try_from(fields: TrustThresholdFields) -> Result<Self, Error> {
+        TrustThreshold::new(fields.numerator, fields.denominator)
+    }
+}
+
+impl Encodable for TrustThreshold {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(2);
+
+        s.append(&self.numerator);
+        s.append(&self.denominator);
+    }
+}
+
+impl Decodable for TrustThreshold {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        if rlp.item_count()? != 2 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+
+        TrustThreshold::new(rlp.val_at(0)?, rlp.val_at(1)?)
+            .map_err(|_| DecoderError::Custom("invalid trust threshold"))
+    }
+}
+
+impl TrustThreshold {
+    /// The default threshold, that guarantees at least one honest signer as long as less than
+    /// a third of the trusted validator set is byzantine
+    pub const ONE_THIRD: TrustThreshold = TrustThreshold {
+        numerator: 1,
+        denominator: 3,
+    };
+
+    /// Creates the threshold, that must be within the [1/3, 1] range
+    pub fn new(numerator: u64, denominator: u64) -> Result<Self, Error> {
+        if denominator == 0
+            || numerator > denominator
+            || u128::from(numerator) * 3 < u128::from(denominator)
+        {
+            return Err(Kind::InvalidTrustThreshold {
+                numerator,
+                denominator,
+            }
+            .into());
+        }
+
+        Ok(TrustThreshold {
+            numerator,
+            denominator,
+        })
+    }
+
+    pub fn numerator(&self) -> u64 {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> u64 {
+        self.denominator
+    }
+
+    /// Whether the signers make up more than the threshold of the validator set
+    pub fn is_exceeded_by(&self, signers: usize, validators: usize) -> bool {
+        signers as u128 * u128::from(self.denominator)
+            > validators as u128 * u128::from(self.numerator)
     }
 }
 
 /// Config contains state related configuration flags
-#[derive(Serialize, Deserialize, RlpEncodable, RlpDecodable, Clone, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct Config {
     pub epoch_size: u64,
     pub allowed_clock_skew: u64,
+    #[serde(default)]
+    pub trusting_period: u64,
     pub verify_epoch_headers: bool,
     pub verify_non_epoch_headers: bool,
     pub verify_header_timestamp: bool,
+    #[serde(default)]
+    pub trust_threshold: Option<TrustThreshold>,
+}
+
+impl Encodable for Config {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(7);
+
+        s.append(&self.epoch_size);
+        s.append(&self.allowed_clock_skew);
+        s.append(&self.trusting_period);
+        s.append(&self.verify_epoch_headers);
+        s.append(&self.verify_non_epoch_headers);
+        s.append(&self.verify_header_timestamp);
+
+        // optional fields are encoded as a list of zero or one element
+        match &self.trust_threshold {
+            Some(threshold) => s.begin_list(1).append(threshold),
+            None => s.begin_list(0),
+        };
+    }
+}
+
+impl Decodable for Config {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        // the legacy layouts lack the trusting period (5 items) or the trust threshold (6 items)
+        let item_count = rlp.item_count()?;
+        let (trusting_period, flags) = match item_count {
+            5 => (0, 2),
+            6 | 7 => (rlp.val_at(2)?, 3),
+            _ => return Err(DecoderError::RlpIncorrectListLen),
+        };
+
+        let trust_threshold = if item_count == 7 {
+            match rlp.at(6)?.item_count()? {
+                0 => None,
+                1 => Some(rlp.at(6)?.val_at(0)?),
+                _ => return Err(DecoderError::RlpIncorrectListLen),
+            }
+        } else {
+            None
+        };
+
+        Ok(Config {
+            epoch_size: rlp.val_at(0)?,
+            allowed_clock_skew: rlp.val_at(1)?,
+            trusting_period,
+            verify_epoch_headers: rlp.val_at(flags)?,
+            verify_non_epoch_headers: rlp.val_at(flags + 1)?,
+            verify_header_timestamp: rlp.val_at(flags + 2)?,
+            trust_threshold,
+        })
+    }
 }
 
 impl ToRlp for Config {
@@ -77,6 +284,9 @@ impl StateConfig for Config {
     fn allowed_clock_skew(&self) -> u64 {
         self.allowed_clock_skew
     }
+    fn trusting_period(&self) -> u64 {
+        self.trusting_period
+    }
 
     fn verify_epoch_headers(&self) -> bool {
         self.verify_epoch_headers
@@ -87,8 +297,15 @@ impl StateConfig for Config {
     fn verify_header_timestamp(&self) -> bool {
         self.verify_header_timestamp
     }
+
+    fn trust_threshold(&self) -> Option<TrustThreshold> {
+        self.trust_threshold
+    }
 }
 
+/// SNAPSHOT_ENCODING_VERSION is the current version of the Snapshot RLP encoding
+pub const SNAPSHOT_ENCODING_VERSION: u64 = 1;
+
 /// Snapshot represents an IBFT consensus state at specified block height
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct Snapshot {
@@ -99,7 +316,7 @@ pub struct Snapshot {
     pub timestamp: u64,
 
     /// Snapshot of current validator set
-    pub validators: Vec<Validator>,
+    pub validators: ValidatorSet,
 
     // Hash and aggregated seal are required to validate the header against the validator set
 
@@ -112,42 +329,85 @@ pub struct Snapshot {
 
 impl Encodable for Snapshot {
     fn rlp_append(&self, s: &mut RlpStream) {
-        s.begin_list(5);
+        rlp_begin_versioned(s, SNAPSHOT_ENCODING_VERSION, 5);
 
         s.append(&self.number);
         s.append(&self.timestamp);
 
-        s.begin_list(self.validators.len());
-        for validator in self.validators.iter() {
-            s.append(validator);
-        }
-
-        s.append(&self.hash.as_ref());
+        s.append(&self.validators);
+        s.append(&self.hash);
         s.append(&self.aggregated_seal);
     }
 }
 
 impl Decodable for Snapshot {
     fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
-        let validators: Result<Vec<Validator>, DecoderError> =
-            rlp.at(2)?.iter().map(|r| r.as_val()).collect();
+        let (version, fields) = rlp_versioned_fields(rlp)?;
+
+        match version {
+            // the unversioned (legacy) layout is the same as v1
+            0 | 1 => Snapshot::decode_v1(&fields),
+            _ => Err(DecoderError::Custom("unsupported snapshot encoding version")),
+        }
+    }
+}
+
+impl Snapshot {
+    fn decode_v1(rlp: &Rlp) -> Result<Self, DecoderError> {
+        if rlp.item_count()? != 5 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
 
         Ok(Snapshot {
-            validators: validators?,
+            validators: rlp.val_at(2)?,
             number: rlp.val_at(0)?,
             timestamp: rlp.val_at(1)?,
-            hash: rlp_list_field_from_bytes(rlp, 3)?,
+            hash: rlp.val_at(3)?,
             aggregated_seal: rlp.val_at(4)?,
         })
     }
 }
 
+impl JsonCodec for Snapshot {
+    fn to_json_value(&self) -> JsonValue {
+        JsonValue::object(vec![
+            ("number", JsonValue::u64(self.number)),
+            ("timestamp", JsonValue::u64(self.timestamp)),
+            ("validators", self.validators.to_json_value()),
+            ("hash", JsonValue::hex(&self.hash)),
+            ("aggregated_seal", self.aggregated_seal.to_json_value()),
+        ])
+    }
+
+    fn from_json_value(value: &JsonValue) -> Result<Self, Error> {
+        Ok(Snapshot {
+            number: value.field("number")?.as_u64()?,
+            timestamp: value.field("timestamp")?.as_u64()?,
+            validators: JsonCodec::from_json_value(value.field("validators")?)?,
+            hash: value.field("hash")?.as_hex_bytes()?,
+            aggregated_seal: JsonCodec::from_json_value(value.field("aggregated_seal")?)?,
+        })
+    }
+}
+
+impl ToJson for Snapshot {
+    fn to_json(&self) -> String {
+        self.to_json_value().encode()
+    }
+}
+
+impl FromJson for Snapshot {
+    fn from_json(bytes: &[u8]) -> Result<Self, Error> {
+        Self::from_json_value(&JsonValue::parse(bytes)?)
+    }
+}
+
 impl Snapshot {
     pub fn new() -> Self {
         Self {
             number: 0,
             timestamp: 0,
-            validators: Vec::new(),
+            validators: ValidatorSet::new(),
             hash: Hash::default(),
             aggregated_seal: IstanbulAggregatedSeal::new(),
         }
@@ -156,6 +416,19 @@ impl Snapshot {
     pub fn verify(&self) -> Result<(), Error> {
         verify_aggregated_seal(self.hash, &self.validators, &self.aggregated_seal)
     }
+
+    /// Whether the snapshot is older than the trusting period (in seconds) at the given time.
+    /// The zero trusting period disables the expiration.
+    pub fn is_expired(&self, trusting_period: u64, now: Timestamp) -> bool {
+        if trusting_period == 0 {
+            return false;
+        }
+
+        let expires_at = Timestamp::from_secs(self.timestamp)
+            .saturating_add(Duration::from_secs(trusting_period));
+
+        expires_at <= now
+    }
 }
 
 impl ToRlp for Snapshot {
@@ -169,3 +442,260 @@ impl FromRlp for Snapshot {
         rlp::decode(&bytes).map_err(|e| Kind::RlpDecodeError.context(e).into())
     }
 }
+
+// NOTE: only the current encoding version is canonical, the older ones are rejected
+impl FromRlpStrict for Snapshot {
+    fn from_rlp_strict(bytes: &[u8]) -> Result<Self, Error> {
+        rlp_decode_strict(&bytes).map_err(|e| Kind::RlpDecodeError.context(e).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::bitmap::ValidatorBitmap;
+    use crate::types::istanbul::PUBLIC_KEY_LENGTH;
+    use crate::types::test_utils::{arb_config, arb_snapshot, arb_validators};
+    use num_bigint::BigInt as Integer;
+    use proptest::prelude::*;
+
+    // Snapshot encoded before the versioning was introduced
+    const SNAPSHOT_UNVERSIONED: &str = "f8d8824380845f5e1000f879f877940101010101010101010101010101010101010101b860020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202a00505050505050505050505050505050505050505050505050505050505050505f303b006060606060606060606060606060606060606060606060606060606060606060606060606060606060606060606060601";
+
+    #[test]
+    fn decodes_versioned_snapshot() {
+        let expected = Snapshot {
+            number: 17280,
+            timestamp: 1_600_000_000,
+            validators: vec![Validator {
+                address: Address::from([1; 20]),
+                public_key: SerializedPublicKey::from([2; PUBLIC_KEY_LENGTH]),
+            }]
+            .into(),
+            hash: Hash::from([5; 32]),
+            aggregated_seal: IstanbulAggregatedSeal {
+                bitmap: ValidatorBitmap::from(3),
+                signature: vec![6; 48],
+                round: Integer::from(1),
+            },
+        };
+
+        // legacy layout is upgraded
+        let legacy = hex::decode(SNAPSHOT_UNVERSIONED).unwrap();
+        assert_eq!(Snapshot::from_rlp(&legacy).unwrap(), expected);
+
+        // current layout is tagged with the version
+        let encoded = expected.to_rlp();
+        let rlp = Rlp::new(&encoded);
+        assert_eq!(rlp.val_at::<u64>(0).unwrap(), SNAPSHOT_ENCODING_VERSION);
+        assert_eq!(rlp.at(1).unwrap().as_raw(), &legacy[..]);
+        assert_eq!(Snapshot::from_rlp(&encoded).unwrap(), expected);
+
+        // unknown versions are rejected
+        let mut stream = RlpStream::new();
+        rlp_begin_versioned(&mut stream, SNAPSHOT_ENCODING_VERSION + 1, 0);
+        let err = Snapshot::from_rlp(&stream.out()).unwrap_err();
+        assert!(matches!(err.kind(), Kind::RlpDecodeError));
+    }
+
+    #[test]
+    fn strictly_decodes_snapshot() {
+        let legacy = hex::decode(SNAPSHOT_UNVERSIONED).unwrap();
+        let snapshot = Snapshot::from_rlp(&legacy).unwrap();
+        let encoded = snapshot.to_rlp();
+
+        assert_eq!(Snapshot::from_rlp_strict(&encoded).unwrap(), snapshot);
+        assert!(Snapshot::from_rlp_strict(&legacy).is_err());
+
+        let trailing = [&encoded[..], &[0x80]].concat();
+        let err = Snapshot::from_rlp_strict(&trailing).unwrap_err();
+        assert!(matches!(err.kind(), Kind::RlpDecodeError));
+
+        let validators = snapshot.validators.to_rlp();
+        assert_eq!(
+            Vec::<Validator>::from_rlp_strict(&validators).unwrap(),
+            &snapshot.validators[..]
+        );
+        assert!(Vec::<Validator>::from_rlp_strict(&[&validators[..], &[0x80]].concat()).is_err());
+    }
+
+    #[test]
+    fn encodes_and_decodes_float_free_json() {
+        let snapshot = Snapshot {
+            number: 17280,
+            timestamp: 1_600_000_000,
+            validators: vec![
+                Validator {
+                    address: Address::from([1; 20]),
+                    public_key: SerializedPublicKey::from([2; PUBLIC_KEY_LENGTH]),
+                },
+                Validator {
+                    address: Address::from([3; 20]),
+                    public_key: SerializedPublicKey::from([4; PUBLIC_KEY_LENGTH]),
+                },
+            ]
+            .into(),
+            hash: Hash::from([5; 32]),
+            aggregated_seal: IstanbulAggregatedSeal {
+                bitmap: ValidatorBitmap::from(3),
+                signature: vec![6; 48],
+                round: Integer::from(1),
+            },
+        };
+
+        // compatible with the serde JSON format
+        let json = snapshot.to_json();
+        assert_eq!(serde_json::from_str::<Snapshot>(&json).unwrap(), snapshot);
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(Snapshot::from_json(json.as_bytes()).unwrap(), snapshot);
+
+        let json = snapshot.validators.to_json();
+        assert_eq!(
+            Vec::<Validator>::from_json(json.as_bytes()).unwrap(),
+            &snapshot.validators[..]
+        );
+
+        // numbers must be integers
+        let json = snapshot.to_json().replace("\"number\":17280", "\"number\":17280.0");
+        let err = Snapshot::from_json(json.as_bytes()).unwrap_err();
+        assert!(matches!(err.kind(), Kind::JsonDecodeError { .. }));
+    }
+
+    #[test]
+    fn expires_after_trusting_period() {
+        let mut snapshot = Snapshot::new();
+        snapshot.timestamp = 1_600_000_000;
+
+        let now = Timestamp::from_secs(1_600_000_099);
+        assert!(!snapshot.is_expired(100, now));
+        assert!(snapshot.is_expired(100, Timestamp::from_secs(1_600_000_100)));
+        assert!(snapshot.is_expired(99, now));
+
+        // zero trusting period never expires
+        assert!(!snapshot.is_expired(0, Timestamp::from_secs(u64::MAX)));
+        // overflow doesn't wrap around
+        assert!(!snapshot.is_expired(u64::MAX, now));
+    }
+
+    #[test]
+    fn validates_trust_threshold() {
+        assert_eq!(
+            TrustThreshold::new(1, 3).unwrap(),
+            TrustThreshold::ONE_THIRD
+        );
+        TrustThreshold::new(2, 3).unwrap();
+        TrustThreshold::new(1, 1).unwrap();
+
+        for (numerator, denominator) in [(1, 4), (4, 3), (0, 0), (1, 0)] {
+            let err = TrustThreshold::new(numerator, denominator).unwrap_err();
+            assert!(matches!(err.kind(), Kind::InvalidTrustThreshold { .. }));
+        }
+
+        // more than a third is required
+        let threshold = TrustThreshold::ONE_THIRD;
+        assert!(!threshold.is_exceeded_by(3, 9));
+        assert!(threshold.is_exceeded_by(4, 9));
+        assert!(threshold.is_exceeded_by(1, 1));
+        assert!(!threshold.is_exceeded_by(0, 0));
+        assert!(!threshold.is_exceeded_by(usize::MAX / 3, usize::MAX));
+    }
+
+    #[test]
+    fn rejects_decoding_invalid_trust_threshold() {
+        let mut stream = RlpStream::new_list(2);
+        stream.append(&0u64).append(&1u64);
+        let threshold = stream.out().to_vec();
+        assert!(rlp::decode::<TrustThreshold>(&threshold).is_err());
+
+        let mut stream = RlpStream::new_list(7);
+        stream
+            .append(&17280u64)
+            .append(&5u64)
+            .append(&0u64)
+            .append(&true)
+            .append(&true)
+            .append(&true);
+        stream.begin_list(1).append_raw(&threshold, 1);
+        let err = Config::from_rlp(&stream.out()).unwrap_err();
+        assert!(matches!(err.kind(), Kind::RlpDecodeError));
+
+        let json = r#"{"numerator":0,"denominator":1}"#;
+        assert!(serde_json::from_str::<TrustThreshold>(json).is_err());
+
+        let json = r#"{"numerator":1,"denominator":3}"#;
+        assert_eq!(
+            serde_json::from_str::<TrustThreshold>(json).unwrap(),
+            TrustThreshold::ONE_THIRD
+        );
+    }
+
+    #[test]
+    fn decodes_legacy_config() {
+        let expected = Config {
+            epoch_size: 17280,
+            allowed_clock_skew: 5,
+            trusting_period: 0,
+            verify_epoch_headers: true,
+            verify_non_epoch_headers: false,
+            verify_header_timestamp: true,
+            trust_threshold: None,
+        };
+
+        // written before the trusting period was added
+        let mut stream = RlpStream::new_list(5);
+        stream
+            .append(&17280u64)
+            .append(&5u64)
+            .append(&true)
+            .append(&false)
+            .append(&true);
+        assert_eq!(Config::from_rlp(&stream.out()).unwrap(), expected);
+
+        // written before the trust threshold was added
+        let mut stream = RlpStream::new_list(6);
+        stream
+            .append(&17280u64)
+            .append(&5u64)
+            .append(&100u64)
+            .append(&true)
+            .append(&false)
+            .append(&true);
+        let config = Config {
+            trusting_period: 100,
+            ..expected.clone()
+        };
+        assert_eq!(Config::from_rlp(&stream.out()).unwrap(), config);
+
+        let mut stream = RlpStream::new_list(4);
+        stream
+            .append(&17280u64)
+            .append(&5u64)
+            .append(&true)
+            .append(&false);
+        assert!(Config::from_rlp(&stream.out()).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn snapshot_rlp_round_trip(snapshot in arb_snapshot()) {
+            let encoded = snapshot.to_rlp();
+
+            prop_assert_eq!(&Snapshot::from_rlp(&encoded).unwrap(), &snapshot);
+            prop_assert_eq!(&Snapshot::from_rlp_strict(&encoded).unwrap(), &snapshot);
+        }
+
+        #[test]
+        fn validators_rlp_round_trip(validators in arb_validators()) {
+            let encoded = validators.to_rlp();
+
+            prop_assert_eq!(&Vec::<Validator>::from_rlp(&encoded).unwrap(), &validators);
+            prop_assert_eq!(&Vec::<Validator>::from_rlp_strict(&encoded).unwrap(), &validators);
+        }
+
+        #[test]
+        fn config_rlp_round_trip(config in arb_config()) {
+            prop_assert_eq!(&Config::from_rlp(&config.to_rlp()).unwrap(), &config);
+        }
+    }
+}
@@ -0,0 +1,330 @@
+use crate::bls::aggregate_public_keys;
+use crate::errors::Error;
+use crate::istanbul::min_quorum_size;
+use crate::serialization::json::{JsonCodec, JsonValue};
+use crate::traits::{FromJson, FromRlp, FromRlpStrict, ToJson, ToRlp};
+use crate::types::bitmap::ValidatorBitmap;
+use crate::types::header::{Address, Hash};
+use crate::types::state::Validator;
+use bls_crypto::PublicKey;
+use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
+use sha3::{Digest, Keccak256};
+use std::collections::HashMap;
+use std::ops::Deref;
+
+/// ValidatorSet is an ordered list of validators indexed by address. The order matters, since
+/// the aggregated seal bitmaps refer to the validators by their position in the set.
+///
+/// The set is encoded (RLP, JSON) the same way as the plain list of validators.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(from = "Vec<Validator>", into = "Vec<Validator>")]
+pub struct ValidatorSet {
+    validators: Vec<Validator>,
+
+    /// Position of the validator in the set, by address
+    index: HashMap<Address, usize>,
+
+    /// Aggregated public key of all of the validators (only if computed beforehand)
+    aggregated_public_key: Option<PublicKey>,
+}
+
+impl ValidatorSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the position of the validator in the set
+    pub fn position(&self, address: &Address) -> Option<usize> {
+        self.index.get(address).copied()
+    }
+
+    pub fn contains(&self, address: &Address) -> bool {
+        self.index.contains_key(address)
+    }
+
+    pub fn get_by_address(&self, address: &Address) -> Option<&Validator> {
+        self.position(address)
+            .map(|position| &self.validators[position])
+    }
+
+    /// Returns the validators selected by the bitmap (ie. the signers of an aggregated seal)
+    pub fn signers(&self, bitmap: &ValidatorBitmap) -> Result<Vec<&Validator>, Error> {
+        bitmap.validate(self.validators.len())?;
+
+        Ok(bitmap.iter().map(|i| &self.validators[i]).collect())
+    }
+
+    /// Returns the minimum number of signers required to commit a Celo block. The other IBFT
+    /// chains define their quorum in the engine's seal verification
+    /// (see [`IstanbulEngine::verify_seal`](crate::IstanbulEngine::verify_seal)).
+    pub fn quorum_size(&self) -> usize {
+        min_quorum_size(self.validators.len())
+    }
+
+    /// Returns the aggregated public key of the whole set, if it has been computed
+    pub fn aggregated_public_key(&self) -> Option<&PublicKey> {
+        self.aggregated_public_key.as_ref()
+    }
+
+    /// Computes and caches the aggregated public key of the whole set. The cached key speeds up
+    /// the verification of the seals signed by all of the validators. It's dropped whenever the
+    /// set changes.
+    pub fn compute_aggregated_public_key(&mut self) -> Result<&PublicKey, Error> {
+        let public_key = aggregate_public_keys(self.validators.iter())?;
+
+        Ok(self.aggregated_public_key.get_or_insert(public_key))
+    }
+
+    /// Returns the commitment to the validator set, that is the keccak256 hash of its RLP encoding
+    pub fn hash(&self) -> Hash {
+        let mut hash = Hash::default();
+        hash.copy_from_slice(&Keccak256::digest(&self.to_rlp()));
+
+        hash
+    }
+
+    /// Appends the validators to the set. None of the addresses can be already present in the
+    /// set, nor repeated in the list.
+    pub fn add(&mut self, validators: Vec<Validator>) -> bool {
+        let mut new_addresses: HashMap<Address, ()> = HashMap::new();
+        for validator in validators.iter() {
+            if self.contains(&validator.address)
+                || new_addresses.insert(validator.address, ()).is_some()
+            {
+                return false;
+            }
+        }
+
+        for validator in validators {
+            self.index.insert(validator.address, self.validators.len());
+            self.validators.push(validator);
+        }
+        self.aggregated_public_key = None;
+
+        true
+    }
+
+    /// Removes the validators selected by the bitmap, the order of the remaining ones is kept
+    pub fn remove(&mut self, bitmap: &ValidatorBitmap) -> bool {
+        if bitmap.is_empty() {
+            return true;
+        }
+
+        if bitmap.validate(self.validators.len()).is_err() {
+            return false;
+        }
+
+        let validators: Vec<Validator> = std::mem::take(&mut self.validators)
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| !bitmap.is_set(*i))
+            .map(|(_, validator)| validator)
+            .collect();
+        *self = ValidatorSet::from(validators);
+
+        true
+    }
+}
+
+impl From<Vec<Validator>> for ValidatorSet {
+    fn from(validators: Vec<Validator>) -> Self {
+        let mut index = HashMap::with_capacity(validators.len());
+        for (position, validator) in validators.iter().enumerate() {
+            // NOTE: the set can't contain duplicates if built with `add`, but the decoded one
+            // could, so the first position wins
+            index.entry(validator.address).or_insert(position);
+        }
+
+        Self {
+            validators,
+            index,
+            aggregated_public_key: None,
+        }
+    }
+}
+
+impl From<ValidatorSet> for Vec<Validator> {
+    fn from(set: ValidatorSet) -> Self {
+        set.validators
+    }
+}
+
+impl Deref for ValidatorSet {
+    type Target = [Validator];
+
+    fn deref(&self) -> &Self::Target {
+        &self.validators
+    }
+}
+
+// NOTE: the cached aggregated public key is derived from the validators, hence not compared
+impl PartialEq for ValidatorSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.validators == other.validators
+    }
+}
+
+impl std::fmt::Debug for ValidatorSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.validators.iter()).finish()
+    }
+}
+
+impl Encodable for ValidatorSet {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.append_list(&self.validators);
+    }
+}
+
+impl Decodable for ValidatorSet {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        let validators: Result<Vec<Validator>, DecoderError> =
+            (0..rlp.item_count()?).map(|i| rlp.val_at(i)).collect();
+
+        Ok(ValidatorSet::from(validators?))
+    }
+}
+
+impl ToRlp for ValidatorSet {
+    fn to_rlp(&self) -> Vec<u8> {
+        rlp::encode(self)
+    }
+}
+
+impl FromRlp for ValidatorSet {
+    fn from_rlp(bytes: &[u8]) -> Result<Self, Error> {
+        Vec::<Validator>::from_rlp(bytes).map(ValidatorSet::from)
+    }
+}
+
+impl FromRlpStrict for ValidatorSet {
+    fn from_rlp_strict(bytes: &[u8]) -> Result<Self, Error> {
+        Vec::<Validator>::from_rlp_strict(bytes).map(ValidatorSet::from)
+    }
+}
+
+impl JsonCodec for ValidatorSet {
+    fn to_json_value(&self) -> JsonValue {
+        self.validators.to_json_value()
+    }
+
+    fn from_json_value(value: &JsonValue) -> Result<Self, Error> {
+        Vec::<Validator>::from_json_value(value).map(ValidatorSet::from)
+    }
+}
+
+impl ToJson for ValidatorSet {
+    fn to_json(&self) -> String {
+        self.to_json_value().encode()
+    }
+}
+
+impl FromJson for ValidatorSet {
+    fn from_json(bytes: &[u8]) -> Result<Self, Error> {
+        Self::from_json_value(&JsonValue::parse(bytes)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bls::test_utils::generate_validators;
+    use crate::errors::Kind;
+    use crate::types::test_utils::arb_validators;
+    use proptest::prelude::*;
+
+    #[test]
+    fn indexes_validators_by_address() {
+        let (_, validators) = generate_validators(4);
+        let mut set = ValidatorSet::from(validators.clone());
+
+        assert_eq!(set.len(), 4);
+        assert_eq!(set.position(&Address::from([2; 20])), Some(2));
+        assert_eq!(
+            set.get_by_address(&Address::from([3; 20])),
+            Some(&validators[3])
+        );
+        assert!(!set.contains(&Address::from([4; 20])));
+
+        // remove the first two validators, the index follows the new positions
+        assert!(set.remove(&ValidatorBitmap::from(0b0011)));
+        assert_eq!(set.position(&Address::from([0; 20])), None);
+        assert_eq!(set.position(&Address::from([2; 20])), Some(0));
+        assert_eq!(set.position(&Address::from([3; 20])), Some(1));
+
+        // already in the set
+        assert!(!set.add(vec![validators[2].clone()]));
+        // repeated in the list
+        assert!(!set.add(vec![validators[0].clone(), validators[0].clone()]));
+        // out of range
+        assert!(!set.remove(&ValidatorBitmap::from(0b0100)));
+        assert_eq!(set.len(), 2);
+
+        assert!(set.add(vec![validators[0].clone()]));
+        assert_eq!(set.position(&Address::from([0; 20])), Some(2));
+    }
+
+    #[test]
+    fn selects_signers_by_bitmap() {
+        let (_, validators) = generate_validators(4);
+        let set = ValidatorSet::from(validators.clone());
+
+        let signers = set.signers(&ValidatorBitmap::from(0b1010)).unwrap();
+        assert_eq!(signers, vec![&validators[1], &validators[3]]);
+        assert_eq!(set.quorum_size(), 3);
+
+        let err = set.signers(&ValidatorBitmap::from(0b10000)).unwrap_err();
+        assert!(matches!(err.kind(), Kind::BitmapOutOfRange { .. }));
+    }
+
+    #[test]
+    fn caches_aggregated_public_key() {
+        let (_, validators) = generate_validators(3);
+        let mut set = ValidatorSet::from(validators[..2].to_vec());
+        assert!(set.aggregated_public_key().is_none());
+
+        set.compute_aggregated_public_key().unwrap();
+        assert!(set.aggregated_public_key().is_some());
+        assert_eq!(
+            set.clone().aggregated_public_key(),
+            set.aggregated_public_key()
+        );
+
+        // the cache is dropped on change
+        assert!(set.add(vec![validators[2].clone()]));
+        assert!(set.aggregated_public_key().is_none());
+    }
+
+    #[test]
+    fn commits_to_validator_set() {
+        let (_, validators) = generate_validators(3);
+        let set = ValidatorSet::from(validators.clone());
+
+        let digest = Keccak256::digest(&validators.to_rlp());
+        assert_eq!(&set.hash()[..], &digest[..]);
+
+        let mut reordered = validators.clone();
+        reordered.swap(0, 1);
+        assert_ne!(ValidatorSet::from(reordered).hash(), set.hash());
+    }
+
+    proptest! {
+        #[test]
+        fn encodes_as_validators_list(validators in arb_validators()) {
+            let set = ValidatorSet::from(validators.clone());
+            let encoded = set.to_rlp();
+
+            prop_assert_eq!(&encoded, &validators.to_rlp());
+            prop_assert_eq!(&ValidatorSet::from_rlp(&encoded).unwrap(), &set);
+            prop_assert_eq!(&ValidatorSet::from_rlp_strict(&encoded).unwrap(), &set);
+
+            let json = set.to_json();
+            prop_assert_eq!(&json, &validators.to_json());
+            prop_assert_eq!(&ValidatorSet::from_json(json.as_bytes()).unwrap(), &set);
+            prop_assert_eq!(
+                serde_json::to_string(&set).unwrap(),
+                serde_json::to_string(&validators).unwrap()
+            );
+        }
+    }
+}
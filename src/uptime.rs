@@ -0,0 +1,274 @@
+use crate::errors::{Error, Kind};
+use crate::istanbul::{get_epoch_number, min_quorum_size};
+use crate::types::header::{Address, Header};
+use crate::types::istanbul::{IstanbulAggregatedSeal, IstanbulExtra};
+use crate::types::state::Validator;
+use num_traits::ToPrimitive;
+use std::collections::BTreeMap;
+
+/// ValidatorUptime holds the signing statistics of a single validator
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ValidatorUptime {
+    pub address: Address,
+
+    /// Number of blocks the validator signed
+    pub signed: u64,
+
+    /// Number of blocks the validator didn't sign
+    pub missed: u64,
+}
+
+/// UptimeReport summarizes the validators participation over a range of blocks
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct UptimeReport {
+    /// First block covered by the report
+    pub first_block: u64,
+
+    /// Last block covered by the report
+    pub last_block: u64,
+
+    /// Per-validator statistics, in the validator set order
+    pub validators: Vec<ValidatorUptime>,
+
+    /// Number of blocks committed in the given round
+    pub rounds: BTreeMap<u64, u64>,
+
+    /// The smallest difference between the number of signers and the minimum quorum size
+    pub min_quorum_margin: i64,
+
+    /// The sum of quorum margins over all the blocks (ie. to compute the average margin)
+    pub total_quorum_margin: i64,
+}
+
+impl UptimeReport {
+    /// Number of blocks covered by the report
+    pub fn blocks(&self) -> u64 {
+        self.rounds.values().sum()
+    }
+}
+
+/// Builds an uptime report for the headers of a single epoch, signed by the given validator set.
+///
+/// The signers of a block are taken from the `parent_aggregated_seal` of its child (if present
+/// in the range), as it carries the final set of commits. Otherwise the block's own
+/// `aggregated_seal` is used.
+pub fn uptime_report(
+    validators: &[Validator],
+    headers: &[Header],
+    epoch_size: u64,
+) -> Result<UptimeReport, Error> {
+    if validators.is_empty() {
+        return Err(Kind::EmptyValidatorSet.into());
+    }
+
+    if epoch_size == 0 {
+        return Err(Kind::InvalidHeaderRange {
+            msg: "epoch size must be greater than zero",
+        }
+        .into());
+    }
+
+    let first_header = match headers.first() {
+        Some(header) => header,
+        None => {
+            return Err(Kind::InvalidHeaderRange {
+                msg: "no headers provided",
+            }
+            .into())
+        }
+    };
+    let epoch = get_epoch_number(first_header.number_u64()?, epoch_size);
+
+    let mut seals: BTreeMap<u64, IstanbulAggregatedSeal> = BTreeMap::new();
+    for header in headers {
+        let number = header.number_u64()?;
+        if get_epoch_number(number, epoch_size) != epoch {
+            return Err(Kind::InvalidHeaderRange {
+                msg: "headers span over multiple epochs",
+            }
+            .into());
+        }
+
+        let extra = IstanbulExtra::from_rlp(&header.extra)?;
+        extra.validate()?;
+
+        // The parent seal of the epoch's first block was created by the previous validator set
+        if number > 0
+            && get_epoch_number(number - 1, epoch_size) == epoch
+            && !extra.parent_aggregated_seal.is_empty()
+        {
+            seals.insert(number - 1, extra.parent_aggregated_seal);
+        }
+
+        seals.entry(number).or_insert(extra.aggregated_seal);
+    }
+
+    let quorum = min_quorum_size(validators.len()) as i64;
+    let mut report = UptimeReport {
+        first_block: 0,
+        last_block: 0,
+        validators: validators
+            .iter()
+            .map(|validator| ValidatorUptime {
+                address: validator.address,
+                signed: 0,
+                missed: 0,
+            })
+            .collect(),
+        rounds: BTreeMap::new(),
+        min_quorum_margin: i64::MAX,
+        total_quorum_margin: 0,
+    };
+
+    let mut first_block = None;
+    for (number, seal) in seals.iter() {
+        // genesis block doesn't carry any signatures
+        if seal.is_empty() {
+            continue;
+        }
+        seal.validate(validators.len())?;
+
+        let mut signers: i64 = 0;
+        for (i, uptime) in report.validators.iter_mut().enumerate() {
+            if seal.bitmap.is_set(i) {
+                uptime.signed += 1;
+                signers += 1;
+            } else {
+                uptime.missed += 1;
+            }
+        }
+
+        // NOTE: the round has been checked by the seal validation
+        let round = seal.round.to_u64().unwrap_or_default();
+        *report.rounds.entry(round).or_insert(0) += 1;
+
+        report.min_quorum_margin = report.min_quorum_margin.min(signers - quorum);
+        report.total_quorum_margin += signers - quorum;
+
+        first_block = first_block.or(Some(*number));
+        report.last_block = *number;
+    }
+
+    report.first_block = match first_block {
+        Some(number) => number,
+        None => {
+            return Err(Kind::InvalidHeaderRange {
+                msg: "no signed blocks in the range",
+            }
+            .into())
+        }
+    };
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::bitmap::ValidatorBitmap;
+    use crate::types::istanbul::{
+        IstanbulExtraVanity, SerializedPublicKey, AGGREGATED_SIGNATURE_LENGTH,
+    };
+    use num_bigint::BigInt as Integer;
+
+    const EPOCH_SIZE: u64 = 10;
+
+    #[test]
+    fn reports_validators_uptime() {
+        let validators = validators(4);
+        let headers = vec![
+            header(11, seal(0b0111, 0), IstanbulAggregatedSeal::new()),
+            header(12, seal(0b0011, 1), seal(0b1111, 0)),
+            header(13, seal(0b1110, 0), seal(0b1011, 1)),
+        ];
+
+        let report = uptime_report(&validators, &headers, EPOCH_SIZE).unwrap();
+
+        assert_eq!(report.first_block, 11);
+        assert_eq!(report.last_block, 13);
+        assert_eq!(report.blocks(), 3);
+
+        // block 11: 0b1111 (parent seal), block 12: 0b1011 (parent seal), block 13: 0b1110
+        let signed: Vec<u64> = report.validators.iter().map(|v| v.signed).collect();
+        let missed: Vec<u64> = report.validators.iter().map(|v| v.missed).collect();
+        assert_eq!(signed, vec![2, 3, 2, 3]);
+        assert_eq!(missed, vec![1, 0, 1, 0]);
+
+        assert_eq!(report.rounds.get(&0), Some(&2));
+        assert_eq!(report.rounds.get(&1), Some(&1));
+
+        // quorum size for 4 validators is 3
+        assert_eq!(report.min_quorum_margin, 0);
+        assert_eq!(report.total_quorum_margin, 1);
+    }
+
+    #[test]
+    fn ignores_parent_seal_from_previous_epoch() {
+        let validators = validators(3);
+        let headers = vec![header(11, seal(0b011, 0), seal(0b100, 0))];
+
+        let report = uptime_report(&validators, &headers, EPOCH_SIZE).unwrap();
+
+        assert_eq!(report.first_block, 11);
+        assert_eq!(report.blocks(), 1);
+        assert_eq!(report.validators[2].missed, 1);
+    }
+
+    #[test]
+    fn rejects_invalid_ranges() {
+        let validators = validators(3);
+
+        assert!(uptime_report(&validators, &[], EPOCH_SIZE).is_err());
+        assert!(uptime_report(&[], &[header(11, seal(1, 0), seal(1, 0))], EPOCH_SIZE).is_err());
+
+        // headers from different epochs
+        let headers = vec![
+            header(10, seal(0b011, 0), seal(0b011, 0)),
+            header(11, seal(0b011, 0), seal(0b011, 0)),
+        ];
+        let err = uptime_report(&validators, &headers, EPOCH_SIZE).unwrap_err();
+        assert!(matches!(err.kind(), Kind::InvalidHeaderRange { .. }));
+
+        // bitmap refers to non existing validator
+        let headers = vec![header(11, seal(0b1000, 0), IstanbulAggregatedSeal::new())];
+        let err = uptime_report(&validators, &headers, EPOCH_SIZE).unwrap_err();
+        assert!(matches!(err.kind(), Kind::BitmapOutOfRange { .. }));
+    }
+
+    fn validators(count: u8) -> Vec<Validator> {
+        (0..count)
+            .map(|i| Validator {
+                address: Address::from([i; 20]),
+                public_key: SerializedPublicKey::default(),
+            })
+            .collect()
+    }
+
+    fn seal(bitmap: u64, round: u64) -> IstanbulAggregatedSeal {
+        IstanbulAggregatedSeal {
+            bitmap: ValidatorBitmap::from(bitmap),
+            signature: vec![0; AGGREGATED_SIGNATURE_LENGTH],
+            round: Integer::from(round),
+        }
+    }
+
+    fn header(
+        number: u64,
+        aggregated_seal: IstanbulAggregatedSeal,
+        parent_aggregated_seal: IstanbulAggregatedSeal,
+    ) -> Header {
+        let mut header = Header::new();
+        header.number = Integer::from(number);
+        header.extra = IstanbulExtra {
+            added_validators: Vec::new(),
+            added_validators_public_keys: Vec::new(),
+            removed_validators: ValidatorBitmap::new(),
+            seal: Vec::new(),
+            aggregated_seal,
+            parent_aggregated_seal,
+        }
+        .to_rlp(&IstanbulExtraVanity::default());
+
+        header
+    }
+}